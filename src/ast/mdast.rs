@@ -0,0 +1,379 @@
+// ==================
+// conversion from and to mdast, the tree of unified/remark, with the mdx and gfm node types e.g.
+//
+// { "type": "heading", "depth": 1, "children": [{ "type": "text", "value": "Hello" }] }
+//
+// blocks keep the `position` the parser read them at, inline nodes and nodes built by transformers have none,
+// which mdast allows. reading keeps the position of blocks and places errors at the node that caused them.
+// what mdast has no place for is dropped: the table of contents, code line metadata and heading anchors.
+// headings keep their markdown as text in scc, it is parsed into inline children here and written back as
+// markdown from them, so `# **Bold** title` round-trips.
+//
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use crate::{compiler::MarkdownCompiler, diagnostics::Diagnostic, parser::MdxParser};
+
+use super::{
+  Ast, Blockquote, Bold, CodeBlock, Heading, InlineCode, Italic, Link, List, ListItem, Node, Paragraph, Position,
+  ReactComponent, Root, Table, Text, VueComponent,
+};
+
+pub fn to_mdast(ast: &Ast) -> Value {
+  let Ast::Root(root) = ast;
+  let mut children = Vec::new();
+  if !root.frontmatter.is_empty() {
+    let yaml: Vec<String> = root
+      .frontmatter
+      .iter()
+      .map(|(key, value)| format!("{}: {}", key, value))
+      .collect();
+    children.push(json!({ "type": "yaml", "value": yaml.join("\n") }));
+  }
+  children.extend(root.children.iter().map(|node| node_to_mdast(node, false)));
+  json!({ "type": "root", "children": children })
+}
+
+// `inline` picks mdxJsxTextElement over mdxJsxFlowElement for components
+fn node_to_mdast(node: &Node, inline: bool) -> Value {
+  let mut value = match node {
+    Node::Text(text) => json!({ "type": "text", "value": text.text }),
+    Node::SoftBreak => json!({ "type": "text", "value": "\n" }),
+    Node::HardBreak => json!({ "type": "break" }),
+    Node::ThematicBreak => json!({ "type": "thematicBreak" }),
+    Node::InlineCode(inline_code) => json!({ "type": "inlineCode", "value": inline_code.code }),
+    Node::Html(html) => json!({ "type": "html", "value": html }),
+    Node::CodeBlock(code_block) => json!({
+      "type": "code",
+      "lang": if code_block.language.is_empty() { None } else { Some(&code_block.language) },
+      "meta": code_block.meta.as_ref().map(|meta| meta.join(" ")),
+      "value": code_block.code,
+    }),
+    Node::Heading(heading) => {
+      let mut value = json!({
+        "type": "heading",
+        "depth": heading.level,
+        "children": heading_children(&heading.text),
+      });
+      if let Some(id) = &heading.id {
+        // the remark-slug convention
+        value["data"] = json!({ "id": id, "hProperties": { "id": id } });
+      }
+      value
+    }
+    Node::Paragraph(paragraph) => json!({ "type": "paragraph", "children": nodes_to_mdast(&paragraph.children, true) }),
+    Node::Bold(bold) => json!({ "type": "strong", "children": nodes_to_mdast(&bold.children, true) }),
+    Node::Italic(italic) => json!({ "type": "emphasis", "children": nodes_to_mdast(&italic.children, true) }),
+    Node::Blockquote(blockquote) => {
+      json!({ "type": "blockquote", "children": nodes_to_mdast(&blockquote.children, false) })
+    }
+    Node::List(list) => json!({
+      "type": "list",
+      "ordered": list.ordered,
+      "start": list.start,
+      "spread": list.loose,
+      "children": nodes_to_mdast(&list.children, false),
+    }),
+    Node::ListItem(item) => json!({ "type": "listItem", "spread": false, "children": flow_to_mdast(&item.children) }),
+    Node::Table(table) => {
+      let rows = std::iter::once(&table.header).chain(&table.rows).map(|row| {
+        let cells: Vec<Value> = row
+          .iter()
+          .map(|cell| json!({ "type": "tableCell", "children": [{ "type": "text", "value": cell }] }))
+          .collect();
+        json!({ "type": "tableRow", "children": cells })
+      });
+      json!({
+        "type": "table",
+        "align": (0..table.header.len())
+          .map(|index| json!(table.align.get(index).copied().flatten()))
+          .collect::<Vec<_>>(),
+        "children": rows.collect::<Vec<_>>(),
+      })
+    }
+    Node::Link(link) => json!({
+      "type": "link",
+      "url": link.url,
+      "title": link.title,
+      "children": [{ "type": "text", "value": link.alt }],
+    }),
+    Node::Image(image) => json!({ "type": "image", "url": image.url, "title": image.title, "alt": image.alt }),
+    Node::ReactComponent(component) => {
+      component_to_mdast(&component.name, &component.props, &component.children, inline)
+    }
+    Node::VueComponent(component) => {
+      let mut value = component_to_mdast(&component.name, &component.props, &component.children, inline);
+      value["data"] = json!({ "scc": "vue" });
+      value
+    }
+  };
+  if let Some(position) = node.position() {
+    value["position"] = json!(position);
+  }
+  value
+}
+
+// the inline nodes of the heading markdown, as text when it doesn't parse
+fn heading_children(text: &str) -> Vec<Value> {
+  match MdxParser::parse_inline(text.trim()) {
+    Ok(nodes) => nodes_to_mdast(&nodes, true),
+    Err(_) => vec![json!({ "type": "text", "value": text.trim() })],
+  }
+}
+
+fn nodes_to_mdast(nodes: &[Node], inline: bool) -> Vec<Value> {
+  nodes.iter().map(|node| node_to_mdast(node, inline)).collect()
+}
+
+// list items hold their text directly, mdast wants it in paragraphs
+fn flow_to_mdast(nodes: &[Node]) -> Vec<Value> {
+  let mut children = Vec::new();
+  let mut phrasing = Vec::new();
+  for node in nodes {
+    if is_phrasing(node) {
+      phrasing.push(node_to_mdast(node, true));
+      continue;
+    }
+    if !phrasing.is_empty() {
+      children.push(json!({ "type": "paragraph", "children": std::mem::take(&mut phrasing) }));
+    }
+    children.push(node_to_mdast(node, false));
+  }
+  if !phrasing.is_empty() {
+    children.push(json!({ "type": "paragraph", "children": phrasing }));
+  }
+  children
+}
+
+fn is_phrasing(node: &Node) -> bool {
+  matches!(
+    node,
+    Node::Text(_)
+      | Node::SoftBreak
+      | Node::HardBreak
+      | Node::Bold(_)
+      | Node::Italic(_)
+      | Node::Link(_)
+      | Node::Image(_)
+      | Node::InlineCode(_)
+  )
+}
+
+// props are kept as written, `kind="note"` or `open={true}`
+fn component_to_mdast(name: &str, props: &[String], children: &[Node], inline: bool) -> Value {
+  let attributes: Vec<Value> = props
+    .iter()
+    .map(|prop| {
+      let value = match prop.split_once('=') {
+        Some((_, value)) if value.starts_with('{') && value.ends_with('}') => {
+          json!({ "type": "mdxJsxAttributeValueExpression", "value": value[1..value.len() - 1] })
+        }
+        Some((_, value)) => json!(value.trim_matches(|c| c == '"' || c == '\'')),
+        None => Value::Null,
+      };
+      let name = prop.split('=').next().unwrap_or_default();
+      json!({ "type": "mdxJsxAttribute", "name": name, "value": value })
+    })
+    .collect();
+  json!({
+    "type": if inline { "mdxJsxTextElement" } else { "mdxJsxFlowElement" },
+    "name": name,
+    "attributes": attributes,
+    "children": nodes_to_mdast(children, inline),
+  })
+}
+
+// ==================
+// mdast to scc, node types scc has no equivalent for are errors e.g. footnotes, references and strikethrough
+//
+pub fn from_mdast(value: &Value) -> Result<Ast, Diagnostic> {
+  if kind(value)? != "root" {
+    return Err(error(format!("expected a root node but got `{}`", kind(value)?)));
+  }
+  let mut root = Root::default();
+  let mut children = Vec::new();
+  for child in array(value, "children")? {
+    match kind(child)? {
+      "yaml" => root.frontmatter = frontmatter(string(child, "value")?),
+      _ => children.push(node_from_mdast(child)?),
+    }
+  }
+  root.children = Box::new(children);
+  Ok(Ast::Root(root))
+}
+
+// errors without a position are placed at the node, so the innermost node with a position wins
+fn node_from_mdast(value: &Value) -> Result<Node, Diagnostic> {
+  let position = position(value);
+  let mut node = build_node(value).map_err(|error| match (error.line, position) {
+    (None, Some(position)) => error.at(position.start.line, position.start.column),
+    _ => error,
+  })?;
+  if let Some(position) = position {
+    node.set_position(position);
+  }
+  Ok(node)
+}
+
+fn build_node(value: &Value) -> Result<Node, Diagnostic> {
+  let node = match kind(value)? {
+    "text" => Node::Text(Text { text: string(value, "value")?.to_string() }),
+    "break" => Node::HardBreak,
+    "thematicBreak" => Node::ThematicBreak,
+    "inlineCode" => Node::InlineCode(InlineCode { code: string(value, "value")?.to_string() }),
+    "html" => Node::Html(string(value, "value")?.to_string()),
+    "code" => Node::CodeBlock(CodeBlock::new(
+      optional_string(value, "lang").unwrap_or_default().to_string(),
+      string(value, "value")?.to_string(),
+      optional_string(value, "meta").map(|meta| meta.split_whitespace().map(String::from).collect()),
+    )),
+    "heading" => {
+      let data = value.get("data");
+      let id = data
+        .and_then(|data| {
+          data
+            .get("id")
+            .or(data.get("hProperties").and_then(|properties| properties.get("id")))
+        })
+        .and_then(Value::as_str);
+      Node::Heading(Heading {
+        level: value.get("depth").and_then(Value::as_u64).unwrap_or(1) as usize,
+        text: heading_text(value)?,
+        id: id.map(String::from),
+        anchor: None,
+        position: None,
+      })
+    }
+    "paragraph" => Node::Paragraph(Paragraph { children: Box::new(nodes_from_mdast(value)?), position: None }),
+    "strong" => Node::Bold(Bold { children: Box::new(nodes_from_mdast(value)?) }),
+    "emphasis" => Node::Italic(Italic { children: Box::new(nodes_from_mdast(value)?) }),
+    "blockquote" => Node::Blockquote(Blockquote { children: Box::new(nodes_from_mdast(value)?), position: None }),
+    "list" => Node::List(List {
+      ordered: value.get("ordered").and_then(Value::as_bool).unwrap_or(false),
+      start: value.get("start").and_then(Value::as_u64).map(|start| start as usize),
+      loose: value.get("spread").and_then(Value::as_bool).unwrap_or(false),
+      children: Box::new(nodes_from_mdast(value)?),
+      position: None,
+    }),
+    "listItem" => {
+      let mut children = nodes_from_mdast(value)?;
+      // undoes the paragraph `flow_to_mdast` adds around the text
+      if let [Node::Paragraph(paragraph)] = children.as_mut_slice() {
+        children = std::mem::take(&mut *paragraph.children);
+      }
+      Node::ListItem(ListItem { children: Box::new(children), position: None })
+    }
+    "table" => {
+      let mut rows = array(value, "children")?
+        .iter()
+        .map(|row| Ok(array(row, "children")?.iter().map(text_content).collect()))
+        .collect::<Result<Vec<Vec<String>>, Diagnostic>>()?;
+      let header = if rows.is_empty() { Vec::new() } else { rows.remove(0) };
+      let align = array(value, "align")?
+        .iter()
+        .map(|align| serde_json::from_value(align.clone()).unwrap_or(None))
+        .collect();
+      Node::Table(Table { header, align, rows, position: None })
+    }
+    "link" => Node::Link(Link {
+      url: string(value, "url")?.to_string(),
+      alt: text_content(value),
+      title: optional_string(value, "title").map(String::from),
+    }),
+    "image" => Node::Image(Link {
+      url: string(value, "url")?.to_string(),
+      alt: optional_string(value, "alt").unwrap_or_default().to_string(),
+      title: optional_string(value, "title").map(String::from),
+    }),
+    "mdxJsxFlowElement" | "mdxJsxTextElement" => {
+      let name = optional_string(value, "name").unwrap_or_default().to_string();
+      let props = array(value, "attributes")?
+        .iter()
+        .map(attribute_from_mdast)
+        .collect::<Result<_, _>>()?;
+      let children = Box::new(nodes_from_mdast(value)?);
+      match value.pointer("/data/scc").and_then(Value::as_str) {
+        Some("vue") => Node::VueComponent(VueComponent { name, props, children, position: None }),
+        _ => Node::ReactComponent(ReactComponent { name, props, children, position: None }),
+      }
+    }
+    kind => return Err(error(format!("unsupported mdast node `{}`", kind))),
+  };
+  Ok(node)
+}
+
+fn nodes_from_mdast(value: &Value) -> Result<Vec<Node>, Diagnostic> {
+  let children = array(value, "children")?
+    .iter()
+    .map(node_from_mdast)
+    .collect::<Result<_, _>>()?;
+  Ok(children)
+}
+
+fn attribute_from_mdast(attribute: &Value) -> Result<String, Diagnostic> {
+  if kind(attribute)? != "mdxJsxAttribute" {
+    return Err(error(format!("unsupported mdx attribute `{}`", kind(attribute)?)));
+  }
+  let name = string(attribute, "name")?;
+  let prop = match attribute.get("value") {
+    Some(Value::String(value)) => format!("{}=\"{}\"", name, value),
+    Some(Value::Object(_)) => format!("{}={{{}}}", name, string(&attribute["value"], "value")?),
+    _ => name.to_string(),
+  };
+  Ok(prop)
+}
+
+// the children of a heading written back as markdown, e.g. `**Bold** title`
+fn heading_text(value: &Value) -> Result<String, Diagnostic> {
+  let paragraph = Node::Paragraph(Paragraph { children: Box::new(nodes_from_mdast(value)?), position: None });
+  let mut ast = Ast::Root(Root { children: Box::new(vec![paragraph]), ..Root::default() });
+  Ok(MarkdownCompiler::new().compile(&mut ast).trim().to_string())
+}
+
+// title: Hello\nlayout: post
+fn frontmatter(yaml: &str) -> BTreeMap<String, String> {
+  yaml
+    .lines()
+    .filter_map(|line| line.split_once(':'))
+    .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+    .collect()
+}
+
+// the text of a node and its children, like mdast-util-to-string
+fn text_content(value: &Value) -> String {
+  if let Some(text) = value.get("value").and_then(Value::as_str) {
+    return text.to_string();
+  }
+  let children = value.get("children").and_then(Value::as_array);
+  children.into_iter().flatten().map(text_content).collect()
+}
+
+fn kind(value: &Value) -> Result<&str, Diagnostic> {
+  string(value, "type")
+}
+
+fn string<'v>(value: &'v Value, field: &str) -> Result<&'v str, Diagnostic> {
+  optional_string(value, field).ok_or_else(|| error(format!("mdast node without a `{}` string", field)))
+}
+
+fn optional_string<'v>(value: &'v Value, field: &str) -> Option<&'v str> {
+  value.get(field).and_then(Value::as_str)
+}
+
+fn array<'v>(value: &'v Value, field: &str) -> Result<&'v [Value], Diagnostic> {
+  match value.get(field) {
+    Some(Value::Array(items)) => Ok(items),
+    None | Some(Value::Null) => Ok(&[]),
+    Some(_) => Err(error(format!("mdast node with a `{}` that is not an array", field))),
+  }
+}
+
+// the `position` of remark, None when it is missing or incomplete
+fn position(value: &Value) -> Option<Position> {
+  serde_json::from_value(value.get("position")?.clone()).ok()
+}
+
+fn error(message: String) -> Diagnostic {
+  Diagnostic::error(&message)
+}
@@ -1,114 +1,386 @@
-#[derive(Debug, Clone)]
+// ==================
+// the tree produced by the parser, serialized with serde as
+// { "type": "heading", "level": 1, "text": "Hello", ... }, see `mdast` for the unified/remark shape
+//
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub mod mdast;
+pub mod visit;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Ast {
   Root(Root),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Root {
   pub children: Box<Vec<Node>>,
+  pub toc: Vec<TocEntry>,                    // filled by the table of contents transformer
+  pub frontmatter: BTreeMap<String, String>, // ---\ntitle: Hello\n---
 }
 
 impl Default for Root {
   fn default() -> Self {
-    Root { children: Box::new(Vec::new()) }
+    Root { children: Box::new(Vec::new()), toc: Vec::new(), frontmatter: BTreeMap::new() }
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TocEntry {
+  pub level: usize,
+  pub text: String, // plain text, without the inline markdown of the heading
+  #[serde(default)]
+  pub markdown: String, // the heading as written without its links, the text of the toc links
+  pub id: String,
+  pub children: Vec<TocEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Node {
   Text(Text),
-  CodeBlock(CodeBlock),           // ```ts code```
-  Html(String),                   // <div>html</div>
-  Heading(Heading),               // # heading
-  Paragraph(Paragraph),           // paragraph
-  List(List),                     // - list
-  Blockquote(Blockquote),         // > blockquote
-  Table(Table),                   // | table |
-  ThematicBreak,                  // ---
-  SoftBreak,                      // \
-  HardBreak,                      // \
-  Bold(Bold),                     // **strong**
-  Italic(Italic),                 // ~~strikethrough~~ or *italic*
-  Link(Link),                     // [link](url)
-  Image(Link),                    // ![image](url)
-  InlineCode(InlineCode),         // `inline code`
-  ReactComponent(ReactComponent), // React components e.g. <Sidebar is_open={true}/>
-  VueComponent(VueComponent),     // Vue components e.g. <Sidebar v-bind:is-open="true"/>
-}
-
-#[derive(Debug, Clone)]
+  CodeBlock(CodeBlock),                      // ```ts code```
+  Html(#[serde(with = "html_node")] String), // <div>html</div>
+  Heading(Heading),                          // # heading
+  Paragraph(Paragraph),                      // paragraph
+  List(List),                                // - list
+  ListItem(ListItem),                        // - item
+  Blockquote(Blockquote),                    // > blockquote
+  Table(Table),                              // | table |
+  ThematicBreak,                             // ---
+  SoftBreak,                                 // \
+  HardBreak,                                 // \
+  Bold(Bold),                                // **strong**
+  Italic(Italic),                            // *italic*
+  Link(Link),                                // [link](url)
+  Image(Link),                               // ![image](url)
+  InlineCode(InlineCode),                    // `inline code`
+  ReactComponent(ReactComponent),            // React components e.g. <Sidebar is_open={true}/>
+  VueComponent(VueComponent),                // Vue components e.g. <Sidebar v-bind:is-open="true"/>
+}
+
+impl Node {
+  pub fn children(&self) -> &[Node] {
+    match self {
+      Node::Paragraph(paragraph) => &paragraph.children,
+      Node::List(list) => &list.children,
+      Node::ListItem(item) => &item.children,
+      Node::Blockquote(blockquote) => &blockquote.children,
+      Node::Bold(bold) => &bold.children,
+      Node::Italic(italic) => &italic.children,
+      Node::ReactComponent(component) => &component.children,
+      Node::VueComponent(component) => &component.children,
+      _ => &[],
+    }
+  }
+
+  // where the parser read a block, inline nodes and nodes built by transformers have none
+  pub fn position(&self) -> Option<Position> {
+    match self {
+      Node::Heading(heading) => heading.position,
+      Node::CodeBlock(code_block) => code_block.position,
+      Node::Paragraph(paragraph) => paragraph.position,
+      Node::List(list) => list.position,
+      Node::ListItem(item) => item.position,
+      Node::Blockquote(blockquote) => blockquote.position,
+      Node::Table(table) => table.position,
+      Node::ReactComponent(component) => component.position,
+      Node::VueComponent(component) => component.position,
+      _ => None,
+    }
+  }
+
+  pub fn set_position(&mut self, position: Position) {
+    let field = match self {
+      Node::Heading(heading) => &mut heading.position,
+      Node::CodeBlock(code_block) => &mut code_block.position,
+      Node::Paragraph(paragraph) => &mut paragraph.position,
+      Node::List(list) => &mut list.position,
+      Node::ListItem(item) => &mut item.position,
+      Node::Blockquote(blockquote) => &mut blockquote.position,
+      Node::Table(table) => &mut table.position,
+      Node::ReactComponent(component) => &mut component.position,
+      Node::VueComponent(component) => &mut component.position,
+      _ => return,
+    };
+    *field = Some(position);
+  }
+
+  // None for the nodes that cannot have children
+  pub fn children_mut(&mut self) -> Option<&mut Vec<Node>> {
+    match self {
+      Node::Paragraph(paragraph) => Some(&mut paragraph.children),
+      Node::List(list) => Some(&mut list.children),
+      Node::ListItem(item) => Some(&mut item.children),
+      Node::Blockquote(blockquote) => Some(&mut blockquote.children),
+      Node::Bold(bold) => Some(&mut bold.children),
+      Node::Italic(italic) => Some(&mut italic.children),
+      Node::ReactComponent(component) => Some(&mut component.children),
+      Node::VueComponent(component) => Some(&mut component.children),
+      _ => None,
+    }
+  }
+}
+
+// the span of a node in the document, the same as the mdast `position`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+  pub start: Point,
+  pub end: Point,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Point {
+  pub line: usize,   // from 1
+  pub column: usize, // from 1, in characters
+  pub offset: usize, // from 0, in bytes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InlineCode {
   pub code: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeBlock {
   pub language: String,
   pub code: String,
   pub meta: Option<Vec<String>>,
+  #[serde(default)]
+  pub classes: Vec<String>, // classes added to the <pre> e.g. has-diff
+  #[serde(default)]
+  pub lines: Vec<CodeLine>, // per-line metadata, one entry per line of `code` once a transformer touches it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
+}
+
+impl CodeBlock {
+  pub fn new(language: String, code: String, meta: Option<Vec<String>>) -> Self {
+    CodeBlock { language, code, meta, classes: Vec::new(), lines: Vec::new(), position: None }
+  }
+
+  pub fn add_class(&mut self, class: &str) {
+    if !self.classes.iter().any(|current| current == class) {
+      self.classes.push(class.to_string());
+    }
+  }
+
+  // makes sure there is one `CodeLine` for every line of `code`
+  pub fn ensure_lines(&mut self) {
+    let count = self.code.split('\n').count();
+    self.lines.resize_with(count, CodeLine::default);
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeLine {
+  pub classes: Vec<String>, // e.g. diff add, diff remove, highlighted, focused
+  pub words: Vec<String>,   // words highlighted in this line
 }
 
-#[derive(Debug, Clone)]
+impl CodeLine {
+  pub fn add_class(&mut self, class: &str) {
+    if !self.classes.iter().any(|current| current == class) {
+      self.classes.push(class.to_string());
+    }
+  }
+
+  pub fn add_word(&mut self, word: &str) {
+    if !word.is_empty() && !self.words.iter().any(|current| current == word) {
+      self.words.push(word.to_string());
+    }
+  }
+
+  // splits `line` into (text, is_highlighted_word) segments
+  pub fn segments<'l>(&self, line: &'l str) -> Vec<(&'l str, bool)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut cursor = 0;
+    while cursor < line.len() {
+      let word = self
+        .words
+        .iter()
+        .filter(|word| line[cursor..].starts_with(word.as_str()))
+        .max_by_key(|word| word.len());
+      match word {
+        Some(word) => {
+          if start < cursor {
+            segments.push((&line[start..cursor], false));
+          }
+          segments.push((&line[cursor..cursor + word.len()], true));
+          cursor += word.len();
+          start = cursor;
+        }
+        None => cursor += line[cursor..].chars().next().map_or(1, char::len_utf8),
+      }
+    }
+    if start < line.len() {
+      segments.push((&line[start..], false));
+    }
+    segments
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Text {
   pub text: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Link {
   pub url: String,
   pub alt: String,
   pub title: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bold {
   pub children: Box<Vec<Node>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Italic {
   pub children: Box<Vec<Node>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Heading {
   pub level: usize,
   pub text: String,
+  pub id: Option<String>, // slug e.g. `## Table of contents` -> table-of-contents
+  #[serde(default)]
+  pub anchor: Option<AnchorPosition>, // self-link <a class="anchor" href="#id">
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+impl Heading {
+  // the text without its inline markdown e.g. `See [the docs](url)` -> `See the docs`, for slugs and titles
+  pub fn plain_text(&self) -> String {
+    source_text(self.text.trim())
+  }
+}
+
+// headings and the text of links are kept as written, a source that doesn't parse is its own text
+fn source_text(source: &str) -> String {
+  match crate::parser::MdxParser::parse_inline(source) {
+    Ok(nodes) => plain_text(&nodes),
+    Err(_) => source.to_string(),
+  }
+}
+
+// the text of inline nodes without their markup, images are dropped
+pub fn plain_text(nodes: &[Node]) -> String {
+  let mut text = String::new();
+  for node in nodes {
+    match node {
+      Node::Text(node) => text.push_str(&node.text),
+      Node::InlineCode(code) => text.push_str(&code.code),
+      Node::Link(link) => text.push_str(&source_text(&link.alt)),
+      Node::Html(html) => text.push_str(html),
+      Node::SoftBreak | Node::HardBreak => text.push(' '),
+      _ => text.push_str(&plain_text(node.children())),
+    }
+  }
+  text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorPosition {
+  Before, // <h2 id="api"><a class="anchor" href="#api">#</a>API</h2>
+  After,  // <h2 id="api">API<a class="anchor" href="#api">#</a></h2>
+  Wrap,   // <h2 id="api"><a class="anchor" href="#api">API</a></h2>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Paragraph {
   pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct List {
   pub ordered: bool,
   pub start: Option<usize>,
+  #[serde(default)]
+  pub loose: bool, // items or the blocks inside them are separated by blank lines
   pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListItem {
+  pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Blockquote {
   pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReactComponent {
   pub name: String,
   pub props: Vec<String>,
   pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VueComponent {
   pub name: String,
   pub props: Vec<String>,
   pub children: Box<Vec<Node>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Table {
   pub header: Vec<String>,
+  #[serde(default)]
+  pub align: Vec<Option<Align>>, // per column, from the delimiter row e.g. `| :--- | :---: | ---: |`
   pub rows: Vec<Vec<String>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+  Left,   // :---
+  Center, // :---:
+  Right,  // ---:
+}
+
+// tagged variants have to be maps, `Html(String)` is { "type": "html", "html": "<div>html</div>" }
+mod html_node {
+  use serde::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(html: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("html", html)?;
+    map.end()
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    struct Html {
+      html: String,
+    }
+    Html::deserialize(deserializer).map(|node| node.html)
+  }
 }
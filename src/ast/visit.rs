@@ -0,0 +1,133 @@
+// ==================
+// walking the tree without writing the recursion over every `Node` variant e.g.
+//
+// let mut links = 0;
+// (|node: &ast::Node| {
+//   if let ast::Node::Link(_) = node {
+//     links += 1;
+//   }
+//   Visit::Continue
+// })
+// .visit_ast(&ast);
+//
+// `enter` runs before the children of a node and `exit` after them, overriding `visit_node` changes the
+// traversal itself, `walk_node` is the default one.
+//
+use super::{Ast, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+  Continue, // visit the children
+  Skip,     // leave the children out, `exit` still runs
+}
+
+pub trait Visitor {
+  fn enter(&mut self, _node: &Node) -> Visit {
+    Visit::Continue
+  }
+
+  fn exit(&mut self, _node: &Node) {}
+
+  fn visit_ast(&mut self, ast: &Ast) {
+    walk_ast(self, ast);
+  }
+
+  fn visit_node(&mut self, node: &Node) {
+    walk_node(self, node);
+  }
+}
+
+pub fn walk_ast<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast) {
+  let Ast::Root(root) = ast;
+  for node in root.children.iter() {
+    visitor.visit_node(node);
+  }
+}
+
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+  if visitor.enter(node) == Visit::Continue {
+    for child in node.children() {
+      visitor.visit_node(child);
+    }
+  }
+  visitor.exit(node);
+}
+
+// a closure is a visitor that only enters
+impl<F: FnMut(&Node) -> Visit> Visitor for F {
+  fn enter(&mut self, node: &Node) -> Visit {
+    self(node)
+  }
+}
+
+// ==================
+// the same walk over a mutable tree, where a node can also be removed or replaced
+//
+#[derive(Debug, Clone)]
+pub enum Action {
+  Continue,           // visit the children
+  Skip,               // leave the children out
+  Remove,             // drop the node, the children are not visited
+  Replace(Vec<Node>), // put these nodes in its place, they are not visited
+}
+
+pub trait VisitorMut {
+  fn enter(&mut self, _node: &mut Node) -> Action {
+    Action::Continue
+  }
+
+  // runs once the children are visited, `Continue` and `Skip` keep the node
+  fn exit(&mut self, _node: &mut Node) -> Action {
+    Action::Continue
+  }
+
+  fn visit_ast(&mut self, ast: &mut Ast) {
+    walk_ast_mut(self, ast);
+  }
+
+  fn visit_node(&mut self, node: &mut Node) -> Action {
+    walk_node_mut(self, node)
+  }
+}
+
+pub fn walk_ast_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ast: &mut Ast) {
+  let Ast::Root(root) = ast;
+  walk_nodes_mut(visitor, &mut root.children);
+}
+
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut Node) -> Action {
+  match visitor.enter(node) {
+    Action::Continue => {
+      if let Some(children) = node.children_mut() {
+        walk_nodes_mut(visitor, children);
+      }
+    }
+    Action::Skip => {}
+    action => return action,
+  }
+  visitor.exit(node)
+}
+
+pub fn walk_nodes_mut<V: VisitorMut + ?Sized>(visitor: &mut V, nodes: &mut Vec<Node>) {
+  let mut index = 0;
+  while index < nodes.len() {
+    match visitor.visit_node(&mut nodes[index]) {
+      Action::Continue | Action::Skip => index += 1,
+      Action::Remove => {
+        nodes.remove(index);
+      }
+      Action::Replace(replacement) => {
+        let count = replacement.len();
+        nodes.splice(index..=index, replacement);
+        index += count;
+      }
+    }
+  }
+}
+
+// a closure is a visitor that only enters
+impl<F: FnMut(&mut Node) -> Action> VisitorMut for F {
+  fn enter(&mut self, node: &mut Node) -> Action {
+    self(node)
+  }
+}
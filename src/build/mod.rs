@@ -0,0 +1,362 @@
+// ==================
+// scc build <src> --out <dir>
+//
+// compiles every .md/.mdx under <src> into <dir> keeping the same directory structure,
+// any other file is copied as a static asset. with `search` the sections of the documents are
+// written to <dir>/search.json as well, see the search module.
+//
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  compiler::{is_man_section, HtmlMode, Target},
+  config::Config,
+  diagnostics::{Diagnostic, DiagnosticFormat, Diagnostics},
+  search::{document_sections, SearchIndex, SearchOptions, SearchSection},
+  template::LayoutOptions,
+  transformer::TransformerRewriteLinks,
+};
+
+const SOURCE_EXTENSIONS: [&str; 2] = ["md", "mdx"];
+
+pub struct BuildOptions {
+  pub src: PathBuf,
+  pub out: PathBuf,
+  pub target: Target,
+  pub html_mode: HtmlMode,
+  pub layout: LayoutOptions,
+  pub config: Config,                // parser extensions and transformers
+  pub search: Option<SearchOptions>, // write a search index of the documents
+}
+
+#[derive(Default)]
+pub struct BuildSummary {
+  pub compiled: Vec<PathBuf>,
+  pub copied: Vec<PathBuf>,
+  pub errors: Vec<(PathBuf, Diagnostics)>,
+}
+
+impl BuildSummary {
+  // the errors go to stderr as diagnostics, the counts to stdout
+  pub fn print(&self, format: DiagnosticFormat) {
+    for (path, errors) in &self.errors {
+      errors.clone().in_file(&path.display().to_string()).print(format);
+    }
+    println!(
+      "compiled {} documents, copied {} assets, {} errors",
+      self.compiled.len(),
+      self.copied.len(),
+      self.errors.len()
+    );
+  }
+}
+
+pub fn build(options: &BuildOptions) -> Result<BuildSummary, String> {
+  if !options.src.is_dir() {
+    return Err(format!("{} is not a directory", options.src.display()));
+  }
+  let mut summary = BuildSummary::default();
+  fs::create_dir_all(&options.out).map_err(|err| format!("could not create {}: {}", options.out.display(), err))?;
+  let mut sections = Vec::new();
+  for path in content_files(options)? {
+    let relative = path.strip_prefix(&options.src).unwrap_or(&path).to_path_buf();
+    match build_path(&path, options) {
+      Ok(Built::Compiled(document)) => {
+        summary.compiled.push(relative);
+        sections.extend(document);
+      }
+      Ok(Built::Copied) => summary.copied.push(relative),
+      Err(err) => summary.errors.push((relative, err)),
+    }
+  }
+  if let Some(search) = &options.search {
+    write_search_index(options, search, sections)?;
+  }
+  Ok(summary)
+}
+
+// the files under <src> without the output and layouts directories
+fn content_files(options: &BuildOptions) -> Result<Vec<PathBuf>, String> {
+  let skip: Vec<PathBuf> = [&options.out, &options.layout.layouts_dir]
+    .iter()
+    .filter_map(|path| fs::canonicalize(path).ok())
+    .collect();
+  let mut files = Vec::new();
+  collect_files(&options.src, &skip, &mut files).map_err(|err| err.to_string())?;
+  Ok(files)
+}
+
+pub enum Built {
+  Compiled(Vec<SearchSection>), // the search sections of the document, none without `search`
+  Copied,
+}
+
+// compiles a document or copies an asset from <src> to its place in <out>
+pub fn build_path(path: &Path, options: &BuildOptions) -> Result<Built, Diagnostics> {
+  if is_source(path) {
+    let document = compile_document(path, options)?;
+    let output = output_path(path, options, &document.frontmatter);
+    // a man page whose section changed leaves no page behind under the old one
+    for stale in outputs(path, options).into_iter().filter(|stale| *stale != output) {
+      let _ = fs::remove_file(stale);
+    }
+    write_file(&output, document.code.as_bytes()).map_err(|err| Diagnostic::error(&err))?;
+    let sections = match &options.search {
+      Some(_) => document_sections(&document.ast, &output_url(&output, options), path),
+      None => Vec::new(),
+    };
+    return Ok(Built::Compiled(sections));
+  }
+  let bytes = fs::read(path).map_err(|err| Diagnostic::error(&err.to_string()))?;
+  write_file(&output_path(path, options, &BTreeMap::new()), &bytes).map_err(|err| Diagnostic::error(&err))?;
+  Ok(Built::Copied)
+}
+
+// <src>/guide/intro.md -> <out>/guide/intro.html, `frontmatter` is the document's e.g. for the man section
+pub fn output_path(path: &Path, options: &BuildOptions, frontmatter: &BTreeMap<String, String>) -> PathBuf {
+  let relative = path.strip_prefix(&options.src).unwrap_or(path);
+  if is_source(path) {
+    return options
+      .out
+      .join(relative)
+      .with_extension(options.target.output_extension(frontmatter));
+  }
+  options.out.join(relative)
+}
+
+// the files in <out> that `path` may have been written to, a man page under any section
+pub fn outputs(path: &Path, options: &BuildOptions) -> Vec<PathBuf> {
+  let output = output_path(path, options, &BTreeMap::new());
+  if !matches!(options.target, Target::Roff) || !is_source(path) {
+    return vec![output];
+  }
+  let (Some(dir), Some(stem)) = (output.parent(), output.file_stem()) else {
+    return vec![output];
+  };
+  let Ok(entries) = fs::read_dir(dir) else {
+    return Vec::new();
+  };
+  let mut pages: Vec<PathBuf> = entries
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|page| {
+      let section = page.extension().map(|section| section.to_string_lossy());
+      page.file_stem() == Some(stem) && section.is_some_and(|section| is_man_section(&section))
+    })
+    .collect();
+  pages.sort();
+  pages
+}
+
+// <out>/guide/intro.html -> guide/intro.html
+fn output_url(output: &Path, options: &BuildOptions) -> String {
+  let relative = output.strip_prefix(&options.out).unwrap_or(output);
+  let parts: Vec<String> = relative
+    .components()
+    .map(|part| part.as_os_str().to_string_lossy().to_string())
+    .collect();
+  parts.join("/")
+}
+
+// one bad document does not stop the whole build, its diagnostics end up in the summary
+pub fn compile_file(path: &Path, options: &BuildOptions) -> Result<String, Diagnostics> {
+  compile_document(path, options).map(|output| output.code)
+}
+
+fn compile_document(path: &Path, options: &BuildOptions) -> Result<crate::Output, Diagnostics> {
+  let source = fs::read_to_string(path).map_err(|err| Diagnostic::error(&err.to_string()))?;
+  let mut compile_options = options
+    .config
+    .options(options.target)
+    .map_err(|err| Diagnostic::error(&err))?
+    .use_transformer(Box::new(TransformerRewriteLinks::new(options.target.extension())));
+  compile_options.html_mode = options.html_mode;
+  compile_options.layout = options.layout.clone();
+  crate::compile(&source, compile_options)
+}
+
+fn write_search_index(
+  options: &BuildOptions,
+  search: &SearchOptions,
+  sections: Vec<SearchSection>,
+) -> Result<(), String> {
+  let index = SearchIndex::new(sections, search);
+  write_file(&options.out.join(&search.output), index.to_json().as_bytes())
+}
+
+// the search sections of every document, kept by `scc build --watch` so a save only compiles the documents
+// that changed. the documents with errors are left out.
+#[derive(Default)]
+pub struct SearchSections {
+  documents: BTreeMap<PathBuf, Vec<SearchSection>>,
+}
+
+impl SearchSections {
+  // compiles every document once, nothing without `search`
+  pub fn collect(options: &BuildOptions) -> Result<Self, String> {
+    let mut sections = Self::default();
+    if options.search.is_none() {
+      return Ok(sections);
+    }
+    for path in content_files(options)?.into_iter().filter(|path| is_source(path)) {
+      if let Ok(document) = compile_document(&path, options) {
+        let url = output_url(&output_path(&path, options, &document.frontmatter), options);
+        let document = document_sections(&document.ast, &url, &path);
+        sections.documents.insert(path, document);
+      }
+    }
+    Ok(sections)
+  }
+
+  pub fn update(&mut self, path: &Path, sections: Vec<SearchSection>) {
+    self.documents.insert(path.to_path_buf(), sections);
+  }
+
+  pub fn remove(&mut self, path: &Path) {
+    self.documents.remove(path);
+  }
+
+  // in the order of the paths, like `build`
+  pub fn write(&self, options: &BuildOptions) -> Result<(), String> {
+    let Some(search) = &options.search else {
+      return Ok(());
+    };
+    let sections = self.documents.values().flatten().cloned().collect();
+    write_search_index(options, search, sections)
+  }
+}
+
+// hidden files, the output directory and the layouts directory are skipped
+fn collect_files(dir: &Path, skip: &[PathBuf], files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+  let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<_, _>>()?;
+  entries.sort();
+  for path in entries {
+    let hidden = path
+      .file_name()
+      .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    if hidden || fs::canonicalize(&path).is_ok_and(|path| skip.contains(&path)) {
+      continue;
+    }
+    if path.is_dir() {
+      collect_files(&path, skip, files)?;
+    } else {
+      files.push(path);
+    }
+  }
+  Ok(())
+}
+
+// the documents of `paths`, directories are searched for .md and .mdx files
+pub fn source_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+  let mut files = Vec::new();
+  for path in paths {
+    if !path.is_dir() {
+      files.push(path.clone());
+      continue;
+    }
+    let mut found = Vec::new();
+    collect_files(path, &[], &mut found).map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    files.extend(found.into_iter().filter(|path| is_source(path)));
+  }
+  Ok(files)
+}
+
+pub fn is_source(path: &Path) -> bool {
+  path
+    .extension()
+    .is_some_and(|extension| SOURCE_EXTENSIONS.iter().any(|source| extension == *source))
+}
+
+pub fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+  }
+  fs::write(path, bytes).map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::{build_path, outputs, BuildOptions, Built, SearchSections};
+  use crate::{compiler::HtmlMode, config::Config, search::SearchOptions, template::LayoutOptions, Target};
+
+  #[test]
+  fn updates_the_search_sections_of_rebuilt_documents_only() {
+    let dir = std::env::temp_dir().join(format!("scc-build-search-{}", std::process::id()));
+    let src = dir.join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("a.md"), "# A\n\nfirst\n").unwrap();
+    fs::write(src.join("b.md"), "# B\n\nsecond\n").unwrap();
+    let options = BuildOptions {
+      src: src.clone(),
+      out: dir.join("out"),
+      target: Target::Html,
+      html_mode: HtmlMode::default(),
+      layout: LayoutOptions { layouts_dir: src.join("layouts"), ..LayoutOptions::default() },
+      config: Config::default(),
+      search: Some(SearchOptions { inverted_index: false, ..SearchOptions::default() }),
+    };
+    let mut sections = SearchSections::collect(&options).unwrap();
+    let contents = |sections: &SearchSections| -> Vec<String> {
+      sections.write(&options).unwrap();
+      let index: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("out/search.json")).unwrap()).unwrap();
+      let sections = index["sections"].as_array().unwrap().iter();
+      sections
+        .map(|section| section["content"].as_str().unwrap().to_string())
+        .collect()
+    };
+    assert_eq!(contents(&sections), ["first", "second"]);
+
+    // b is not compiled again, its sections stay as they were
+    fs::write(src.join("a.md"), "# A\n\nchanged\n").unwrap();
+    fs::write(src.join("b.md"), "# B\n\nnot rebuilt\n").unwrap();
+    let Ok(Built::Compiled(document)) = build_path(&src.join("a.md"), &options) else {
+      panic!("a.md did not compile");
+    };
+    sections.update(&src.join("a.md"), document);
+    assert_eq!(contents(&sections), ["changed", "second"]);
+
+    sections.remove(&src.join("a.md"));
+    assert_eq!(contents(&sections), ["second"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn names_man_pages_after_their_section() {
+    let dir = std::env::temp_dir().join(format!("scc-build-roff-{}", std::process::id()));
+    let src = dir.join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("scc.md"), "---\ntitle: scc\nsection: 5\n---\n# Name\n").unwrap();
+    fs::write(src.join("scc.png"), "").unwrap();
+    fs::write(src.join("other.md"), "# Name\n").unwrap();
+    let options = BuildOptions {
+      src: src.clone(),
+      out: dir.join("out"),
+      target: Target::Roff,
+      html_mode: HtmlMode::default(),
+      layout: LayoutOptions { layouts_dir: src.join("layouts"), ..LayoutOptions::default() },
+      config: Config::default(),
+      search: None,
+    };
+    let built = |name: &str| {
+      build_path(&src.join(name), &options).ok().unwrap();
+    };
+    built("scc.md");
+    built("scc.png");
+    built("other.md");
+    assert!(dir.join("out/scc.5").is_file() && dir.join("out/other.1").is_file());
+
+    // the page under the old section goes away, the asset with the same name stays
+    fs::write(src.join("scc.md"), "---\ntitle: scc\nsection: 8\n---\n# Name\n").unwrap();
+    built("scc.md");
+    assert_eq!(outputs(&src.join("scc.md"), &options), [dir.join("out/scc.8")]);
+    assert!(dir.join("out/scc.png").is_file());
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -1,27 +1,283 @@
 use clap::{Arg, ArgAction, Command};
 
+// flags left out fall back to scc.toml, then to the defaults
 pub fn command_line() -> clap::ArgMatches {
   let matches = Command::new("scc.rs")
     .about("Rust-based Structured Content Compiler.")
     .subcommand_required(true)
     .arg_required_else_help(true)
     .author("yazaldefi <yazaldefilimon@gmail.com>")
+    .arg(
+      Arg::new("config")
+        .long("config")
+        .global(true)
+        .help("the config file, by default the closest scc.toml."),
+    )
+    .subcommand(
+      Command::new("compile")
+        .about("compile a mdx or md file.")
+        .arg(file_arg())
+        .arg(from_arg())
+        .arg(target_arg())
+        .arg(html_mode_arg())
+        .args(layout_args())
+        .arg(output_arg())
+        .arg(format_arg())
+        .arg(debug_arg())
+        .arg(watch_arg()),
+    )
     .subcommand(
       Command::new("run")
         .about("compile a mdx or md file to html.")
-        .arg(Arg::new("file").help("the mdx or md file to compile.").required(true)),
+        .arg(file_arg())
+        .arg(from_arg())
+        .arg(html_mode_arg())
+        .args(layout_args())
+        .arg(output_arg())
+        .arg(format_arg())
+        .arg(debug_arg())
+        .arg(watch_arg()),
+    )
+    .subcommand(
+      Command::new("build")
+        .about("compile every mdx or md file in a directory.")
+        .arg(Arg::new("src").help("the content directory, `src` in scc.toml by default."))
+        .arg(out_arg())
+        .arg(target_arg())
+        .arg(html_mode_arg())
+        .args(layout_args())
+        .arg(
+          Arg::new("search-index")
+            .long("search-index")
+            .action(ArgAction::SetTrue)
+            .help("write search.json with the sections of every document, see [search] in scc.toml."),
+        )
+        .arg(format_arg())
+        .arg(watch_arg()),
+    )
+    .subcommand(
+      Command::new("serve")
+        .about("build a directory, serve it on localhost and reload the browser on changes.")
+        .arg(Arg::new("src").help("the content directory, `src` in scc.toml by default."))
+        .arg(
+          Arg::new("port")
+            .long("port")
+            .short('p')
+            .help("the port to listen on.")
+            .value_parser(clap::value_parser!(u16))
+            .default_value("3000"),
+        )
+        .arg(out_arg())
+        .arg(html_mode_arg())
+        .args(layout_args().into_iter().filter(|arg| arg.get_id() != "standalone"))
+        .arg(format_arg()),
+    )
+    .subcommand(
+      Command::new("fmt")
+        .about("format mdx or md files in place.")
+        .arg(
+          Arg::new("files")
+            .num_args(0..)
+            .help("files or directories, `src` in scc.toml or the current directory by default, - formats stdin."),
+        )
+        .arg(
+          Arg::new("check")
+            .long("check")
+            .action(ArgAction::SetTrue)
+            .help("list the files that are not formatted instead of rewriting them."),
+        )
+        .arg(format_arg()),
+    )
+    .subcommand(
+      Command::new("lint")
+        .about("check mdx or md files against the rules in [lint].")
+        .arg(
+          Arg::new("files")
+            .num_args(0..)
+            .help("files or directories, `src` in scc.toml or the current directory by default, - lints stdin."),
+        )
+        .arg(
+          Arg::new("fix")
+            .long("fix")
+            .action(ArgAction::SetTrue)
+            .help("rewrite the files with trailing whitespace and bare urls fixed."),
+        )
+        .arg(format_arg()),
+    )
+    .subcommand(
+      Command::new("check-links")
+        .about("check that the links and images of mdx or md files point to files and headings that exist.")
+        .arg(
+          Arg::new("files")
+            .num_args(0..)
+            .help("files or directories, `src` in scc.toml or the current directory by default."),
+        )
+        .arg(format_arg()),
     )
     .subcommand(
       Command::new("run-jsx")
         .about("compile a mdx or md file to jsx.")
-        .arg(Arg::new("file").help("the mdx or md file to compile.").required(true)),
+        .arg(file_arg())
+        .arg(output_arg())
+        .arg(format_arg())
+        .arg(debug_arg()),
     )
     .subcommand(
       Command::new("run-vue")
         .about("compile a mdx or md file to vue.")
-        .arg(Arg::new("file").help("the mdx or md file to compile.").required(true)),
+        .arg(file_arg())
+        .arg(output_arg())
+        .arg(format_arg())
+        .arg(debug_arg()),
+    )
+    .subcommand(
+      Command::new("run-svelte")
+        .about("compile a mdx or md file to a svelte component.")
+        .arg(file_arg())
+        .arg(output_arg())
+        .arg(format_arg())
+        .arg(debug_arg()),
+    )
+    .subcommand(
+      Command::new("inspect")
+        .about("print the ast of a mdx or md file.")
+        .arg(file_arg())
+        .arg(
+          Arg::new("stages")
+            .long("stages")
+            .action(ArgAction::SetTrue)
+            .help("print the ast after parsing and after every transformer."),
+        )
+        .arg(
+          Arg::new("json")
+            .long("json")
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("scc")
+            .value_parser(["scc", "mdast"])
+            .help("print the ast as json, --json=mdast for the unified/remark tree."),
+        )
+        .arg(output_arg())
+        .arg(format_arg()),
+    )
+    .subcommand(
+      Command::new("view")
+        .about("render a mdx or md file in the terminal.")
+        .arg(file_arg())
+        .arg(
+          Arg::new("width")
+            .long("width")
+            .help("wrap at this many columns, the terminal width by default.")
+            .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+          Arg::new("no-pager")
+            .long("no-pager")
+            .action(ArgAction::SetTrue)
+            .help("print everything instead of opening $PAGER for documents taller than the terminal."),
+        )
+        .arg(format_arg()),
+    )
+    .subcommand(
+      Command::new("stats")
+        .about("print the word count, reading time and node counts of a mdx or md file.")
+        .arg(file_arg())
+        .arg(
+          Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .help("print the stats as json."),
+        )
+        .arg(output_arg())
+        .arg(format_arg()),
     )
     .get_matches();
 
-  return matches;
+  matches
+}
+
+fn watch_arg() -> Arg {
+  Arg::new("watch")
+    .long("watch")
+    .short('w')
+    .action(ArgAction::SetTrue)
+    .help("recompile when the sources or their dependencies change.")
+}
+
+fn file_arg() -> Arg {
+  Arg::new("file")
+    .help("the mdx or md file to compile, - reads from stdin.")
+    .required(true)
+}
+
+fn from_arg() -> Arg {
+  Arg::new("from")
+    .long("from")
+    .help("what the file holds, mdast reads a unified/remark tree as json.")
+    .value_parser(["markdown", "mdast"])
+    .default_value("markdown")
+}
+
+fn output_arg() -> Arg {
+  Arg::new("output")
+    .long("output")
+    .short('o')
+    .help("write the result to a file instead of stdout.")
+}
+
+fn out_arg() -> Arg {
+  Arg::new("out")
+    .long("out")
+    .short('o')
+    .help("the output directory, dist by default.")
+}
+
+fn target_arg() -> Arg {
+  Arg::new("target")
+    .long("target")
+    .help("the output format, html by default.")
+    .value_parser([
+      "html", "jsx", "tsx", "vue", "svelte", "markdown", "text", "latex", "roff",
+    ])
+}
+
+fn html_mode_arg() -> Arg {
+  Arg::new("html-mode")
+    .long("html-mode")
+    .help("how the html is laid out, pretty by default.")
+    .value_parser(["pretty", "minified"])
+}
+
+fn layout_args() -> [Arg; 4] {
+  [
+    Arg::new("template")
+      .long("template")
+      .help("html template with {{content}}, {{title}}, {{toc}}..."),
+    Arg::new("standalone")
+      .long("standalone")
+      .action(ArgAction::SetTrue)
+      .help("wrap the html in the built-in page layout, or the latex in a document with a preamble."),
+    Arg::new("layouts")
+      .long("layouts")
+      .help("directory where `layout: name` is found as name.html."),
+    Arg::new("stylesheet")
+      .long("stylesheet")
+      .action(ArgAction::Append)
+      .help("stylesheet linked from the page layout, can be repeated."),
+  ]
+}
+
+fn format_arg() -> Arg {
+  Arg::new("format")
+    .long("format")
+    .help("how errors are printed on stderr.")
+    .value_parser(["human", "json"])
+    .default_value("human")
+}
+
+fn debug_arg() -> Arg {
+  Arg::new("debug")
+    .long("debug")
+    .action(ArgAction::SetTrue)
+    .help("print the ast after parsing and after every transformer on stderr.")
 }
@@ -0,0 +1,701 @@
+use crate::{ast, highlighter::Highlighter};
+
+// what the terminal supports, `scc view` turns color off with NO_COLOR and both off when piped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnsiOptions {
+  pub width: usize,     // paragraphs are wrapped and code blocks and tables fit in it
+  pub color: bool,      // colors and bold/italic/underline
+  pub hyperlinks: bool, // osc 8 links, otherwise the url is written after the text
+}
+
+impl Default for AnsiOptions {
+  fn default() -> Self {
+    Self { width: 80, color: true, hyperlinks: true }
+  }
+}
+
+// text attributes of the inline nodes, each word is written with its own codes so a line can be wrapped
+// or paged anywhere without the style leaking
+#[derive(Debug, Clone, Copy, Default)]
+struct Style {
+  bold: bool,
+  italic: bool,
+  code: bool,
+  link: bool,
+}
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+
+// a word of a paragraph, `width` is without the escape codes
+struct Word {
+  text: String,
+  width: usize,
+}
+
+// renders the tree for a terminal, one String of lines ending with a newline
+#[derive(Default)]
+pub struct AnsiCompiler {
+  options: AnsiOptions,
+  highlighter: Option<Highlighter>, // colors the code blocks in 24-bit color when `color` is on
+}
+
+impl AnsiCompiler {
+  pub fn new() -> Self {
+    Self::with_options(AnsiOptions::default())
+  }
+
+  pub fn with_options(options: AnsiOptions) -> Self {
+    Self { options, highlighter: None }
+  }
+
+  pub fn with_highlighter(mut self, highlighter: Option<Highlighter>) -> Self {
+    self.highlighter = highlighter;
+    self
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let ast::Ast::Root(root) = ast;
+    let lines = self.render_blocks(&root.children, self.options.width.max(20), true);
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+  }
+
+  fn paint(&self, codes: &str, text: &str) -> String {
+    match self.options.color && !codes.is_empty() {
+      true => format!("{}{}{}", codes, text, RESET),
+      false => text.to_string(),
+    }
+  }
+
+  // ==================
+  // blocks, each one is a list of lines that fit in `width` so the parents can indent them
+  //
+  fn render_blocks(&self, nodes: &[ast::Node], width: usize, spaced: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut inline: Vec<ast::Node> = Vec::new();
+    let mut blocks = Vec::new();
+    for node in nodes {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      if !inline.is_empty() {
+        blocks.push(self.render_paragraph(&std::mem::take(&mut inline), width));
+      }
+      blocks.push(self.render_block(node, width));
+    }
+    if !inline.is_empty() {
+      blocks.push(self.render_paragraph(&inline, width));
+    }
+    for block in blocks.into_iter().filter(|block| !block.is_empty()) {
+      if spaced && !lines.is_empty() {
+        lines.push(String::new());
+      }
+      lines.extend(block);
+    }
+    lines
+  }
+
+  fn render_block(&self, node: &ast::Node, width: usize) -> Vec<String> {
+    match node {
+      ast::Node::Heading(heading) => self.render_heading(heading, width),
+      ast::Node::Paragraph(paragraph) => self.render_paragraph(&paragraph.children, width),
+      ast::Node::CodeBlock(code_block) => self.render_code_block(code_block, width),
+      ast::Node::List(list) => self.render_list(list, width),
+      ast::Node::ListItem(item) => self.render_blocks(&item.children, width, false),
+      ast::Node::Blockquote(blockquote) => {
+        let bar = self.paint(DIM, "│ ");
+        let lines = self.render_blocks(&blockquote.children, width.saturating_sub(2).max(10), true);
+        lines.into_iter().map(|line| format!("{}{}", bar, line)).collect()
+      }
+      ast::Node::Table(table) => self.render_table(table, width),
+      ast::Node::ThematicBreak => vec![self.paint(DIM, &"─".repeat(width))],
+      ast::Node::Html(html) => html.trim_end().lines().map(|line| self.paint(DIM, line)).collect(),
+      ast::Node::ReactComponent(ast::ReactComponent { name, children, .. })
+      | ast::Node::VueComponent(ast::VueComponent { name, children, .. }) => {
+        let mut lines = vec![self.paint(DIM, &format!("<{}>", name))];
+        let inner = self.render_blocks(children, width.saturating_sub(2).max(10), true);
+        lines.extend(inner.into_iter().map(|line| format!("  {}", line)));
+        lines.push(self.paint(DIM, &format!("</{}>", name)));
+        lines
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  // h1 and h2 are underlined with a rule, the deeper ones keep their #
+  fn render_heading(&self, heading: &ast::Heading, width: usize) -> Vec<String> {
+    let text = heading.text.trim();
+    let (codes, rule) = match heading.level {
+      1 => ("\x1b[1;35m", Some('═')),
+      2 => ("\x1b[1;36m", Some('─')),
+      _ => ("\x1b[1m", None),
+    };
+    let text = match rule {
+      Some(_) => text.to_string(),
+      None => format!("{} {}", "#".repeat(heading.level), text),
+    };
+    let mut lines: Vec<String> = wrap_plain(&text, width)
+      .iter()
+      .map(|line| self.paint(codes, line))
+      .collect();
+    if let Some(rule) = rule {
+      let length = display_width(&text).min(width);
+      lines.push(self.paint(DIM, &rule.to_string().repeat(length)));
+    }
+    lines
+  }
+
+  fn render_list(&self, list: &ast::List, width: usize) -> Vec<String> {
+    let start = list.start.unwrap_or(1);
+    let markers: Vec<String> = (0..list.children.len())
+      .map(|index| match list.ordered {
+        true => format!("{}.", start + index),
+        false => "•".to_string(),
+      })
+      .collect();
+    let indent = markers.iter().map(|marker| display_width(marker)).max().unwrap_or(1) + 1;
+    let mut lines = Vec::new();
+    for (item, marker) in list.children.iter().zip(markers) {
+      let item = self.render_block(item, width.saturating_sub(indent).max(10));
+      for (index, line) in item.into_iter().enumerate() {
+        let prefix = match index {
+          0 => format!("{:<indent$}", marker, indent = indent),
+          _ => " ".repeat(indent),
+        };
+        let prefix = if index == 0 {
+          self.paint("\x1b[33m", &prefix)
+        } else {
+          prefix
+        };
+        lines.push(format!("{}{}", prefix, line).trim_end().to_string());
+      }
+    }
+    lines
+  }
+
+  // ┌─ rust ─┐ with the code colored by the highlighter, the lines marked by the notation transformers
+  // keep their own color
+  fn render_code_block(&self, code_block: &ast::CodeBlock, width: usize) -> Vec<String> {
+    let code: Vec<&str> = code_block.code.lines().collect();
+    let focused = code_block.classes.iter().any(|class| class == "has-focused");
+    let gutter = if code_block.classes.iter().any(|class| class == "has-diff") {
+      2
+    } else {
+      0
+    };
+    let highlighted = match &self.highlighter {
+      Some(highlighter) if self.options.color => highlighter.highlight(&code_block.code, &code_block.language),
+      _ => Vec::new(),
+    };
+    let longest = code.iter().map(|line| display_width(line)).max().unwrap_or(0) + gutter;
+    let label = match code_block.language.is_empty() {
+      true => String::new(),
+      false => format!(" {} ", code_block.language),
+    };
+    let inner = longest
+      .max(display_width(&label) + 1)
+      .min(width.saturating_sub(4).max(10));
+    let mut lines = Vec::new();
+    let top = format!(
+      "┌─{}{}┐",
+      label,
+      "─".repeat((inner + 1).saturating_sub(display_width(&label)))
+    );
+    lines.push(self.paint(DIM, &top));
+    for (index, line) in code.iter().enumerate() {
+      let classes = code_block
+        .lines
+        .get(index)
+        .map(|line| line.classes.as_slice())
+        .unwrap_or_default();
+      let has = |class: &str| classes.iter().any(|name| name == class);
+      let (marker, codes) = match () {
+        _ if has("add") => ("+ ", "\x1b[32m"),
+        _ if has("remove") => ("- ", "\x1b[31m"),
+        _ if has("error") => ("  ", "\x1b[1;31m"),
+        _ if has("warning") => ("  ", "\x1b[1;33m"),
+        _ if has("highlighted") => ("  ", "\x1b[7m"),
+        _ if focused && !has("focused") => ("  ", DIM),
+        _ => ("  ", ""),
+      };
+      let marker = &marker[..gutter];
+      let mut cells: Vec<Cell> = marker.chars().map(|character| (character, None, false)).collect();
+      match highlighted.get(index) {
+        // the colors of diff, error and warning lines carry their meaning, the syntax ones would hide it
+        Some(tokens) if matches!(codes, "" | DIM | "\x1b[7m") => {
+          for token in tokens {
+            let color = token.color.as_deref().and_then(truecolor);
+            cells.extend(token.text.chars().map(|character| (character, color.clone(), false)));
+          }
+        }
+        _ => cells.extend(line.chars().map(|character| (character, None, false))),
+      }
+      if let Some(words) = code_block.lines.get(index).map(|line| line.words.as_slice()) {
+        self.underline_words(&mut cells, words);
+      }
+      for chunk in chunks(&cells, inner) {
+        let padding =
+          " ".repeat(inner.saturating_sub(chunk.iter().map(|(character, ..)| char_width(*character)).sum()));
+        lines.push(format!(
+          "{} {}{} {}",
+          self.paint(DIM, "│"),
+          self.paint(codes, &styled(chunk)),
+          padding,
+          self.paint(DIM, "│")
+        ));
+      }
+    }
+    lines.push(self.paint(DIM, &format!("└{}┘", "─".repeat(inner + 2))));
+    lines
+  }
+
+  // the words marked by the word highlight transformer are underlined
+  fn underline_words(&self, cells: &mut [Cell], words: &[String]) {
+    if !self.options.color {
+      return;
+    }
+    let characters: Vec<char> = cells.iter().map(|(character, ..)| *character).collect();
+    for word in words.iter().filter(|word| !word.is_empty()) {
+      let word: Vec<char> = word.chars().collect();
+      let mut index = 0;
+      while index + word.len() <= characters.len() {
+        if characters[index..index + word.len()] == word[..] {
+          cells[index..index + word.len()]
+            .iter_mut()
+            .for_each(|cell| cell.2 = true);
+          index += word.len();
+        } else {
+          index += 1;
+        }
+      }
+    }
+  }
+
+  // ┌───┬───┐ with the header in bold, the columns shrink to fit `width`
+  fn render_table(&self, table: &ast::Table, width: usize) -> Vec<String> {
+    let rows: Vec<&Vec<String>> = std::iter::once(&table.header).chain(table.rows.iter()).collect();
+    let count = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    if count == 0 {
+      return Vec::new();
+    }
+    let mut widths: Vec<usize> = (0..count)
+      .map(|column| {
+        rows
+          .iter()
+          .map(|row| row.get(column).map_or(0, |cell| display_width(cell.trim())))
+          .max()
+          .unwrap_or(0)
+          .max(1)
+      })
+      .collect();
+    let available = width.saturating_sub(count * 3 + 1);
+    while widths.iter().sum::<usize>() > available && widths.iter().any(|width| *width > 3) {
+      if let Some(widest) = widths.iter_mut().max() {
+        *widest -= 1;
+      }
+    }
+    let rule = |left: &str, middle: &str, right: &str| {
+      let parts: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+      self.paint(DIM, &format!("{}{}{}", left, parts.join(middle), right))
+    };
+    let mut lines = vec![rule("┌", "┬", "┐")];
+    for (index, row) in rows.iter().enumerate() {
+      let cells: Vec<String> = widths
+        .iter()
+        .enumerate()
+        .map(|(column, width)| {
+          let cell = truncate(row.get(column).map_or("", |cell| cell.trim()), *width);
+          let padding = " ".repeat(width.saturating_sub(display_width(&cell)));
+          let cell = if index == 0 { self.paint("\x1b[1m", &cell) } else { cell };
+          format!(" {}{} ", cell, padding)
+        })
+        .collect();
+      let bar = self.paint(DIM, "│");
+      lines.push(format!("{}{}{}", bar, cells.join(&bar), bar));
+      if index == 0 && rows.len() > 1 {
+        lines.push(rule("├", "┼", "┤"));
+      }
+    }
+    lines.push(rule("└", "┴", "┘"));
+    lines
+  }
+
+  // ==================
+  // inline nodes, written word by word and wrapped at `width`
+  //
+  fn render_paragraph(&self, nodes: &[ast::Node], width: usize) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut space = false;
+    for node in nodes {
+      self.render_inline(node, Style::default(), &mut words, &mut space);
+    }
+    wrap(&words, width)
+  }
+
+  fn render_inline(&self, node: &ast::Node, style: Style, words: &mut Vec<Word>, space: &mut bool) {
+    match node {
+      ast::Node::Text(text) => self.push_text(&text.text, style, None, words, space),
+      ast::Node::InlineCode(code) => {
+        let text = if self.options.color {
+          code.code.clone()
+        } else {
+          format!("`{}`", code.code)
+        };
+        self.push_text(&text, Style { code: true, ..style }, None, words, space);
+      }
+      ast::Node::Bold(bold) => {
+        for child in bold.children.iter() {
+          self.render_inline(child, Style { bold: true, ..style }, words, space);
+        }
+      }
+      ast::Node::Italic(italic) => {
+        for child in italic.children.iter() {
+          self.render_inline(child, Style { italic: true, ..style }, words, space);
+        }
+      }
+      ast::Node::Link(link) | ast::Node::Image(link) => {
+        let text = match node {
+          ast::Node::Image(_) => format!("[image: {}]", link.alt),
+          _ => link.alt.clone(),
+        };
+        let style = Style { link: true, ..style };
+        if self.options.hyperlinks {
+          self.push_text(&text, style, Some(&link.url), words, space);
+        } else {
+          self.push_text(&text, style, None, words, space);
+          if link.url != link.alt {
+            self.push_text(&format!(" ({})", link.url), Style::default(), None, words, space);
+          }
+        }
+      }
+      ast::Node::Html(html) => self.push_text(html, style, None, words, space),
+      ast::Node::SoftBreak => *space = true,
+      ast::Node::HardBreak => {
+        words.push(Word { text: String::new(), width: usize::MAX });
+        *space = false;
+      }
+      _ => {}
+    }
+  }
+
+  // a word that touches the one before it without whitespace is glued to it e.g. **bold**.
+  fn push_text(&self, text: &str, style: Style, url: Option<&str>, words: &mut Vec<Word>, space: &mut bool) {
+    for (index, piece) in text.split(char::is_whitespace).enumerate() {
+      if index > 0 {
+        *space = true;
+      }
+      if piece.is_empty() {
+        continue;
+      }
+      let mut styled = self.paint(&style_codes(style), piece);
+      if let Some(url) = url {
+        styled = format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, styled);
+      }
+      let width = display_width(piece);
+      match words.last_mut() {
+        Some(last) if !*space && last.width != usize::MAX => {
+          last.text.push_str(&styled);
+          last.width += width;
+        }
+        _ => words.push(Word { text: styled, width }),
+      }
+      *space = false;
+    }
+  }
+}
+
+fn style_codes(style: Style) -> String {
+  let mut codes = Vec::new();
+  if style.bold {
+    codes.push("1");
+  }
+  if style.italic {
+    codes.push("3");
+  }
+  if style.code {
+    codes.push("33");
+  }
+  if style.link {
+    codes.extend(["4", "34"]);
+  }
+  match codes.is_empty() {
+    true => String::new(),
+    false => format!("\x1b[{}m", codes.join(";")),
+  }
+}
+
+// a word of usize::MAX width is a hard break
+fn wrap(words: &[Word], width: usize) -> Vec<String> {
+  let mut lines = Vec::new();
+  let mut line = String::new();
+  let mut length = 0;
+  for word in words {
+    if word.width == usize::MAX {
+      lines.push(std::mem::take(&mut line));
+      length = 0;
+      continue;
+    }
+    if length > 0 && length + 1 + word.width > width {
+      lines.push(std::mem::take(&mut line));
+      length = 0;
+    }
+    if length > 0 {
+      line.push(' ');
+      length += 1;
+    }
+    line.push_str(&word.text);
+    length += word.width;
+  }
+  if length > 0 {
+    lines.push(line);
+  }
+  lines
+}
+
+fn wrap_plain(text: &str, width: usize) -> Vec<String> {
+  let words: Vec<Word> = text
+    .split_whitespace()
+    .map(|word| Word { text: word.to_string(), width: display_width(word) })
+    .collect();
+  wrap(&words, width)
+}
+
+// a character of a code line with its escape code for the syntax color and whether it is underlined
+type Cell = (char, Option<String>, bool);
+
+// a code line cut into pieces of `width` columns, a wide character that doesn't fit starts the next piece
+fn chunks(cells: &[Cell], width: usize) -> Vec<&[Cell]> {
+  let mut pieces = Vec::new();
+  let (mut start, mut length) = (0, 0);
+  for (index, (character, ..)) in cells.iter().enumerate() {
+    let character_width = char_width(*character);
+    if length > 0 && length + character_width > width {
+      pieces.push(&cells[start..index]);
+      (start, length) = (index, 0);
+    }
+    length += character_width;
+  }
+  if start < cells.len() || pieces.is_empty() {
+    pieces.push(&cells[start..]);
+  }
+  pieces
+}
+
+// the cells with their colors and underlines, ending with the default ones so the line's own style goes on
+fn styled(cells: &[Cell]) -> String {
+  let mut text = String::new();
+  let (mut color, mut underline): (Option<&str>, bool) = (None, false);
+  for (character, cell_color, cell_underline) in cells {
+    if cell_color.as_deref() != color {
+      color = cell_color.as_deref();
+      text.push_str(color.unwrap_or("\x1b[39m"));
+    }
+    if *cell_underline != underline {
+      underline = *cell_underline;
+      text.push_str(if underline { "\x1b[4m" } else { "\x1b[24m" });
+    }
+    text.push(*character);
+  }
+  if color.is_some() {
+    text.push_str("\x1b[39m");
+  }
+  if underline {
+    text.push_str("\x1b[24m");
+  }
+  text
+}
+
+// `#rrggbb` as a 24-bit foreground color
+fn truecolor(color: &str) -> Option<String> {
+  let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+  let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+  Some(format!("\x1b[38;2;{};{};{}m", channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn truncate(text: &str, width: usize) -> String {
+  if display_width(text) <= width {
+    return text.to_string();
+  }
+  let mut truncated = String::new();
+  let mut length = 0;
+  for character in text.chars() {
+    length += char_width(character);
+    if length > width.saturating_sub(1) {
+      break;
+    }
+    truncated.push(character);
+  }
+  truncated.push('…');
+  truncated
+}
+
+// the columns `text` takes in a terminal
+fn display_width(text: &str) -> usize {
+  text.chars().map(char_width).sum()
+}
+
+// 2 for the wide east asian characters and emoji, 0 for combining marks, joiners, variation selectors and
+// control characters, 1 for the rest
+fn char_width(character: char) -> usize {
+  match character as u32 {
+    0x00..=0x1f | 0x7f..=0x9f => 0,
+    0x0300..=0x036f | 0x200b..=0x200f | 0x20d0..=0x20ff | 0xfe00..=0xfe0f | 0xfe20..=0xfe2f => 0,
+    0x1100..=0x115f
+    | 0x231a..=0x231b
+    | 0x2329..=0x232a
+    | 0x23e9..=0x23ec
+    | 0x23f0
+    | 0x23f3
+    | 0x25fd..=0x25fe
+    | 0x2614..=0x2615
+    | 0x2648..=0x2653
+    | 0x267f
+    | 0x2693
+    | 0x26a1
+    | 0x26aa..=0x26ab
+    | 0x26bd..=0x26be
+    | 0x26c4..=0x26c5
+    | 0x26ce
+    | 0x26d4
+    | 0x26ea
+    | 0x26f2..=0x26f3
+    | 0x26f5
+    | 0x26fa
+    | 0x26fd
+    | 0x2705
+    | 0x270a..=0x270b
+    | 0x2728
+    | 0x274c
+    | 0x274e
+    | 0x2753..=0x2755
+    | 0x2757
+    | 0x2795..=0x2797
+    | 0x27b0
+    | 0x27bf
+    | 0x2b1b..=0x2b1c
+    | 0x2b50
+    | 0x2b55
+    | 0x2e80..=0x303e
+    | 0x3041..=0x33ff
+    | 0x3400..=0x4dbf
+    | 0x4e00..=0x9fff
+    | 0xa000..=0xa4cf
+    | 0xa960..=0xa97f
+    | 0xac00..=0xd7a3
+    | 0xf900..=0xfaff
+    | 0xfe10..=0xfe19
+    | 0xfe30..=0xfe6f
+    | 0xff00..=0xff60
+    | 0xffe0..=0xffe6
+    | 0x1f004
+    | 0x1f0cf
+    | 0x1f18e
+    | 0x1f191..=0x1f19a
+    | 0x1f200..=0x1f251
+    | 0x1f300..=0x1f320
+    | 0x1f32d..=0x1f335
+    | 0x1f337..=0x1f37c
+    | 0x1f37e..=0x1f393
+    | 0x1f3a0..=0x1f3ca
+    | 0x1f3cf..=0x1f3d3
+    | 0x1f3e0..=0x1f3f0
+    | 0x1f3f4
+    | 0x1f3f8..=0x1f43e
+    | 0x1f440
+    | 0x1f442..=0x1f4fc
+    | 0x1f4ff..=0x1f53d
+    | 0x1f54b..=0x1f54e
+    | 0x1f550..=0x1f567
+    | 0x1f57a
+    | 0x1f595..=0x1f596
+    | 0x1f5a4
+    | 0x1f5fb..=0x1f64f
+    | 0x1f680..=0x1f6c5
+    | 0x1f6cc
+    | 0x1f6d0..=0x1f6d2
+    | 0x1f6d5..=0x1f6d7
+    | 0x1f6eb..=0x1f6ec
+    | 0x1f6f4..=0x1f6fc
+    | 0x1f7e0..=0x1f7eb
+    | 0x1f90c..=0x1f93a
+    | 0x1f93c..=0x1f945
+    | 0x1f947..=0x1f9ff
+    | 0x1fa70..=0x1faff
+    | 0x20000..=0x2fffd
+    | 0x30000..=0x3fffd => 2,
+    _ => 1,
+  }
+}
+
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Link(_)
+      | ast::Node::Image(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{AnsiCompiler, AnsiOptions};
+  use crate::{ast, highlighter::Highlighter, parser::MdxParser};
+
+  #[test]
+  fn renders_plain_without_color() {
+    let source = "# Title\n\nSome **bold** text with a [link](https://example.com) that wraps.\n\n- one\n- two\n\n```rs\nfn main() {}\n```\n\n| a | b |\n|---|---|\n| 1 | long cell |\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let options = AnsiOptions { width: 30, color: false, hyperlinks: false };
+    let expected = "Title\n═════\n\nSome bold text with a link\n(https://example.com) that\nwraps.\n\n• one\n• two\n\n┌─ rs ─────────┐\n│ fn main() {} │\n└──────────────┘\n\n┌───┬───────────┐\n│ a │ b         │\n├───┼───────────┤\n│ 1 │ long cell │\n└───┴───────────┘\n";
+    assert_eq!(AnsiCompiler::with_options(options).compile(&mut ast), expected);
+  }
+
+  #[test]
+  fn styles_words_and_links() {
+    let mut ast = MdxParser::parse("**bold**. [a b](x.md)\n").unwrap();
+    let output = AnsiCompiler::new().compile(&mut ast);
+    let link = |text: &str| format!("\x1b]8;;x.md\x1b\\\x1b[4;34m{}\x1b[0m\x1b]8;;\x1b\\", text);
+    assert_eq!(output, format!("\x1b[1mbold\x1b[0m. {} {}\n", link("a"), link("b")));
+  }
+
+  #[test]
+  fn highlights_code_blocks() {
+    let mut ast = MdxParser::parse("```rs\nlet x = 1; // x\n```\n").unwrap();
+    let ast::Ast::Root(root) = &mut ast;
+    let ast::Node::CodeBlock(code_block) = &mut root.children[0] else {
+      panic!("expected a code block");
+    };
+    code_block.lines = vec![ast::CodeLine { words: vec!["x".to_string()], ..ast::CodeLine::default() }];
+    let options = AnsiOptions { width: 40, color: true, hyperlinks: false };
+    let output = AnsiCompiler::with_options(options)
+      .with_highlighter(Some(Highlighter::default()))
+      .compile(&mut ast);
+    let line = output.lines().nth(1).unwrap();
+    assert!(line.contains("\x1b[38;2;198;120;221mlet\x1b[39m "), "{:?}", line);
+    assert!(line.contains("\x1b[38;2;209;154;102m1\x1b[39m"), "{:?}", line);
+    // the marked word is underlined inside the comment too, without ending its color
+    assert!(line.contains(" \x1b[4mx\x1b[24m = "), "{:?}", line);
+    assert!(line.contains("// \x1b[4mx\x1b[39m\x1b[24m"), "{:?}", line);
+    // without color the highlighter is not used
+    let options = AnsiOptions { color: false, ..options };
+    let output = AnsiCompiler::with_options(options)
+      .with_highlighter(Some(Highlighter::default()))
+      .compile(&mut ast);
+    assert!(!output.contains('\x1b'));
+  }
+
+  #[test]
+  fn measures_wide_characters_in_columns() {
+    let source = "日本語の文章はとても長いです 🎉🎉\n\n```\n漢字🎉 x\n```\n\n| 名前 | b |\n|---|---|\n| é | 1 |\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let options = AnsiOptions { width: 20, color: false, hyperlinks: false };
+    let expected = "日本語の文章はとても長いです\n🎉🎉\n\n┌──────────┐\n│ 漢字🎉 x │\n└──────────┘\n\n┌──────┬───┐\n│ 名前 │ b │\n├──────┼───┤\n│ é    │ 1 │\n└──────┴───┘\n";
+    assert_eq!(AnsiCompiler::with_options(options).compile(&mut ast), expected);
+  }
+}
@@ -1,19 +1,77 @@
-use crate::ast;
-use crate::transformer::Transformer;
+use crate::{
+  ast,
+  highlighter::{Highlighter, Token},
+  parser::MdxParser,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HtmlMode {
+  #[default]
+  Pretty, // one block per line, children indented by two spaces
+  Minified, // no whitespace between tags
+}
+
+impl HtmlMode {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "pretty" => Some(HtmlMode::Pretty),
+      "minified" => Some(HtmlMode::Minified),
+      _ => None,
+    }
+  }
+}
 
 pub struct HtmlCompiler {
   output: String,
+  mode: HtmlMode,
+  depth: usize,
+  keep_components: bool, // write components as tags, for targets like vue where they mean something
+  escape_braces: bool,   // write the braces of text and code as &#123; and &#125;, `{` starts an expression in svelte
+  highlighter: Option<Highlighter>, // colors the code of the languages it knows with inline styles
+}
+
+impl Default for HtmlCompiler {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl HtmlCompiler {
   pub fn new() -> Self {
-    Self { output: String::new() }
+    Self::with_mode(HtmlMode::Pretty)
+  }
+
+  pub fn with_mode(mode: HtmlMode) -> Self {
+    Self {
+      output: String::new(),
+      mode,
+      depth: 0,
+      keep_components: false,
+      escape_braces: false,
+      highlighter: None,
+    }
+  }
+
+  pub fn keep_components(mut self) -> Self {
+    self.keep_components = true;
+    self
+  }
+
+  pub fn escape_braces(mut self) -> Self {
+    self.escape_braces = true;
+    self
   }
+
+  pub fn with_highlighter(mut self, highlighter: Option<Highlighter>) -> Self {
+    self.highlighter = highlighter;
+    self
+  }
+
   pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
     match ast {
       ast::Ast::Root(root) => self.compile_root(root),
     }
-    return self.output.trim().to_string();
+    self.output.trim_end().to_string()
   }
 
   fn compile_root(&mut self, root: &mut ast::Root) {
@@ -22,31 +80,306 @@ impl HtmlCompiler {
     }
   }
 
+  // ==================
+  // block nodes, each one is written on its own line in pretty mode
+  //
   pub fn compile_node(&mut self, node: &mut ast::Node) {
     match node {
       ast::Node::Heading(heading) => self.compile_heading(heading),
-      ast::Node::Text(text) => self.compile_text(text),
-      ast::Node::InlineCode(inline_code) => self.compile_inline_code(inline_code),
       ast::Node::CodeBlock(code_block) => self.compile_code_block(code_block),
+      ast::Node::Html(html) => self.line(html.trim()),
       ast::Node::List(list) => self.compile_list(list),
-      ast::Node::Blockquote(blockquote) => self.compile_blockquote(blockquote),
-      ast::Node::Link(link) => self.compile_link(link),
-      ast::Node::Image(image) => self.compile_image(image),
+      ast::Node::ListItem(item) => self.compile_list_item(item),
+      ast::Node::Blockquote(blockquote) => {
+        self.compile_block("<blockquote>", &mut blockquote.children, "</blockquote>")
+      }
       ast::Node::Paragraph(paragraph) => self.compile_paragraph(paragraph),
-      _ => println!("Unknown node"),
+      ast::Node::Table(table) => self.compile_table(table),
+      ast::Node::ThematicBreak => self.line("<hr />"),
+      // components have no html equivalent, only their content is kept
+      ast::Node::ReactComponent(component) if self.keep_components => {
+        self.compile_component(&component.name, &component.props, &mut component.children)
+      }
+      ast::Node::VueComponent(component) if self.keep_components => {
+        self.compile_component(&component.name, &component.props, &mut component.children)
+      }
+      ast::Node::ReactComponent(component) => self.compile_nodes(&mut component.children),
+      ast::Node::VueComponent(component) => self.compile_nodes(&mut component.children),
+      ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+      | ast::Node::Link(_)
+      | ast::Node::Image(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak => {
+        let inline = self.compile_inline(std::slice::from_mut(node));
+        self.line(&inline);
+      }
+    }
+  }
+
+  fn compile_nodes(&mut self, nodes: &mut [ast::Node]) {
+    for node in nodes {
+      self.compile_node(node);
+    }
+  }
+
+  fn compile_block(&mut self, open: &str, children: &mut [ast::Node], close: &str) {
+    self.line(open);
+    self.depth += 1;
+    self.compile_nodes(children);
+    self.depth -= 1;
+    self.line(close);
+  }
+
+  fn compile_component(&mut self, name: &str, props: &[String], children: &mut [ast::Node]) {
+    let mut open = format!("<{}", name);
+    for prop in props {
+      open.push(' ');
+      open.push_str(prop);
     }
+    if children.is_empty() {
+      return self.line(&format!("{} />", open));
+    }
+    self.compile_block(&format!("{}>", open), children, &format!("</{}>", name));
+  }
+
+  fn compile_heading(&mut self, heading: &mut ast::Heading) {
+    let id = match &heading.id {
+      Some(id) => id,
+      None => {
+        let text = self.compile_source(heading.text.trim());
+        return self.line(&format!("<h{}>{}</h{}>", heading.level, text, heading.level));
+      }
+    };
+    let id = escape_html(id);
+    let text = self.compile_source(heading.text.trim());
+    let anchor = |text: &str| format!("<a class=\"anchor\" href=\"#{}\">{}</a>", id, text);
+    let content = match heading.anchor {
+      Some(ast::AnchorPosition::Before) => format!("{}{}", anchor("#"), text),
+      Some(ast::AnchorPosition::After) => format!("{}{}", text, anchor("#")),
+      Some(ast::AnchorPosition::Wrap) => anchor(&text),
+      None => text.clone(),
+    };
+    self.line(&format!(
+      "<h{} id=\"{}\">{}</h{}>",
+      heading.level, id, content, heading.level
+    ));
+  }
+
+  // the content of <pre> is whitespace sensitive, it is written as is in both modes
+  fn compile_code_block(&mut self, code_block: &mut ast::CodeBlock) {
+    let mut code = String::new();
+    if code_block.classes.is_empty() {
+      code.push_str("<pre>");
+    } else {
+      code.push_str(&format!("<pre class=\"{}\">", code_block.classes.join(" ")));
+    }
+    if code_block.language.is_empty() {
+      code.push_str("<code>");
+    } else {
+      code.push_str(&format!(
+        "<code class=\"language-{}\">",
+        escape_html(&code_block.language)
+      ));
+    }
+    let tokens = match &self.highlighter {
+      Some(highlighter) if highlighter.supports(&code_block.language) => {
+        highlighter.highlight(&code_block.code, &code_block.language)
+      }
+      _ => Vec::new(),
+    };
+    let lines: Vec<String> = code_block
+      .code
+      .split('\n')
+      .enumerate()
+      .map(|(index, line)| {
+        let code_line = code_block.lines.get(index).cloned().unwrap_or_default();
+        let mut classes = vec!["line".to_string()];
+        classes.extend(code_line.classes.iter().cloned());
+        let mut content = String::new();
+        let mut start = 0;
+        for (segment, highlighted) in code_line.segments(line) {
+          let end = start + segment.len();
+          let segment = match tokens.get(index) {
+            Some(tokens) => self.braces(colored(tokens, start, end)),
+            None => self.escape(segment),
+          };
+          if highlighted {
+            content.push_str(&format!("<span class=\"highlighted-word\">{}</span>", segment));
+          } else {
+            content.push_str(&segment);
+          }
+          start = end;
+        }
+        format!("<span class=\"{}\">{}</span>", classes.join(" "), content)
+      })
+      .collect();
+    code.push_str(&lines.join("\n"));
+    code.push_str("</code></pre>");
+    self.line(&code);
+  }
+
+  fn compile_list(&mut self, list: &mut ast::List) {
+    let (open, close) = match (list.ordered, list.start) {
+      (true, Some(start)) if start != 1 => (format!("<ol start=\"{}\">", start), "</ol>"),
+      (true, _) => ("<ol>".to_string(), "</ol>"),
+      (false, _) => ("<ul>".to_string(), "</ul>"),
+    };
+    self.line(&open);
+    self.depth += 1;
+    for item in &mut *list.children {
+      match item {
+        ast::Node::ListItem(item) => self.compile_list_item(item),
+        _ => {
+          let inline = self.compile_inline(std::slice::from_mut(item));
+          self.line(&format!("<li>{}</li>", inline));
+        }
+      }
+    }
+    self.depth -= 1;
+    self.line(close);
+  }
+
+  // items with only inline content stay on one line, e.g. <li><a href="#api">API</a></li>
+  fn compile_list_item(&mut self, item: &mut ast::ListItem) {
+    if item.children.iter().all(is_inline) {
+      let inline = self.compile_inline(&mut item.children);
+      return self.line(&format!("<li>{}</li>", inline));
+    }
+    self.line("<li>");
+    self.depth += 1;
+    let mut inline = Vec::new();
+    for node in item.children.iter_mut() {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      if !inline.is_empty() {
+        let content = self.compile_inline(&mut inline);
+        self.line(&content);
+        inline.clear();
+      }
+      self.compile_node(node);
+    }
+    if !inline.is_empty() {
+      let content = self.compile_inline(&mut inline);
+      self.line(&content);
+    }
+    self.depth -= 1;
+    self.line("</li>");
+  }
+
+  fn compile_paragraph(&mut self, paragraph: &mut ast::Paragraph) {
+    let inline = self.compile_inline(&mut paragraph.children);
+    self.line(&format!("<p>{}</p>", inline));
+  }
+
+  fn compile_table(&mut self, table: &mut ast::Table) {
+    self.line("<table>");
+    self.depth += 1;
+    self.line("<thead>");
+    self.depth += 1;
+    let align = |index: usize| match table.align.get(index).copied().flatten() {
+      Some(ast::Align::Left) => " align=\"left\"",
+      Some(ast::Align::Center) => " align=\"center\"",
+      Some(ast::Align::Right) => " align=\"right\"",
+      None => "",
+    };
+    let header: Vec<String> = table
+      .header
+      .iter()
+      .enumerate()
+      .map(|(index, cell)| format!("<th{}>{}</th>", align(index), self.compile_source(cell)))
+      .collect();
+    self.line(&format!("<tr>{}</tr>", header.concat()));
+    self.depth -= 1;
+    self.line("</thead>");
+    self.line("<tbody>");
+    self.depth += 1;
+    for row in &table.rows {
+      let cells: Vec<String> = row
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| format!("<td{}>{}</td>", align(index), self.compile_source(cell)))
+        .collect();
+      self.line(&format!("<tr>{}</tr>", cells.concat()));
+    }
+    self.depth -= 1;
+    self.line("</tbody>");
+    self.depth -= 1;
+    self.line("</table>");
+  }
+
+  // headings and cells are kept as written in the ast, their inline markdown is parsed here and a source that
+  // doesn't parse is written as text
+  fn compile_source(&mut self, source: &str) -> String {
+    let text = || vec![ast::Node::Text(ast::Text { text: source.to_string() })];
+    self.compile_inline(&mut MdxParser::parse_inline(source).unwrap_or_else(|_| text()))
+  }
+
+  // ==================
+  // inline nodes, written without any whitespace of their own
+  //
+  fn compile_inline(&mut self, nodes: &mut [ast::Node]) -> String {
+    let block = std::mem::take(&mut self.output);
+    for node in nodes {
+      self.compile_line_node(node);
+    }
+    let inline = std::mem::replace(&mut self.output, block);
+    inline.trim().to_string()
   }
 
   fn compile_line_node(&mut self, node: &mut ast::Node) {
     match node {
       ast::Node::Text(text) => self.compile_text(text),
       ast::Node::InlineCode(inline_code) => self.compile_inline_code(inline_code),
-      ast::Node::CodeBlock(code_block) => self.compile_code_block(code_block),
       ast::Node::Bold(bold) => self.compile_bold(bold),
       ast::Node::Italic(italic) => self.compile_italic(italic),
       ast::Node::Link(link) => self.compile_link(link),
       ast::Node::Image(image) => self.compile_image(image),
-      _ => println!("Unknown node"),
+      ast::Node::SoftBreak => self.push(" "),
+      ast::Node::HardBreak => self.push("<br />"),
+      ast::Node::Html(html) => self.push(html.trim()),
+      ast::Node::ReactComponent(component) => {
+        self.compile_inline_component(&component.name, &component.props, &mut component.children)
+      }
+      ast::Node::VueComponent(component) => {
+        self.compile_inline_component(&component.name, &component.props, &mut component.children)
+      }
+      ast::Node::Paragraph(paragraph) => {
+        for node in &mut *paragraph.children {
+          self.compile_line_node(node);
+        }
+      }
+      // blocks inside inline content, e.g. from a tree built elsewhere, are written as blocks
+      ast::Node::Heading(_)
+      | ast::Node::CodeBlock(_)
+      | ast::Node::List(_)
+      | ast::Node::ListItem(_)
+      | ast::Node::Blockquote(_)
+      | ast::Node::Table(_)
+      | ast::Node::ThematicBreak => self.compile_node(node),
+    }
+  }
+
+  // like `compile_component` without line breaks, only the content is kept unless components are
+  fn compile_inline_component(&mut self, name: &str, props: &[String], children: &mut [ast::Node]) {
+    if self.keep_components {
+      let open: String = std::iter::once(format!("<{}", name))
+        .chain(props.iter().map(|prop| format!(" {}", prop)))
+        .collect();
+      if children.is_empty() {
+        return self.push(&format!("{} />", open));
+      }
+      self.push(&format!("{}>", open));
+    }
+    for node in &mut *children {
+      self.compile_line_node(node);
+    }
+    if self.keep_components {
+      self.push(&format!("</{}>", name));
     }
   }
 
@@ -65,89 +398,279 @@ impl HtmlCompiler {
     }
     self.push("</i>");
   }
-  fn compile_heading(&mut self, heading: &mut ast::Heading) {
-    self.push(&format!("<h{}>{}</h{}>", heading.level, heading.text, heading.level));
-    self.double_new_line();
-  }
 
+  // runs of whitespace, newlines included, are insignificant in html
   fn compile_text(&mut self, text: &mut ast::Text) {
-    self.output.push_str(&text.text);
+    let text = self.braces(escape_text(&collapse(&text.text)));
+    self.push(&text);
   }
 
   fn compile_inline_code(&mut self, inline_code: &mut ast::InlineCode) {
-    self.output.push_str(&format!("<code>{}</code>", inline_code.code));
+    let code = self.escape(&inline_code.code);
+    self.push(&format!("<code>{}</code>", code));
   }
 
-  fn compile_code_block(&mut self, code_block: &mut ast::CodeBlock) {
-    // TODO: add language
-    let code = format!(
-      r#"
-      <pre>
-        <code>{}</code>
-      </pre>"#,
-      code_block.code,
-    );
-    self.push(code.as_str())
+  // the text of a link is inline markdown like a heading, e.g. [**bold**](url)
+  fn compile_link(&mut self, link: &mut ast::Link) {
+    let text = self.compile_source(&link.alt);
+    let (url, title) = (self.escape(&link.url), self.braces(title(&link.title)));
+    self.push(&format!("<a href=\"{}\"{}>{}</a>", url, title, text));
   }
 
-  fn compile_list(&mut self, list: &mut ast::List) {
-    let mut list_items = String::new();
-    for item in &*list.children {
-      list_items.push_str(&format!("<li>{}</li>", item));
-    }
-    let tag = if list.ordered { "ol" } else { "ul" };
-    let text = format!(
-      r#"
-    <{}>
-      {}
-    </{}>
-    "#,
-      tag, list_items, tag
+  fn compile_image(&mut self, image: &mut ast::Link) {
+    let (url, alt, title) = (
+      self.escape(&image.url),
+      self.escape(&image.alt),
+      self.braces(title(&image.title)),
     );
-    self.new_line();
-    self.push(&text.trim());
-    self.double_new_line();
+    self.push(&format!("<img src=\"{}\" alt=\"{}\"{} />", url, alt, title));
+  }
+
+  fn escape(&self, text: &str) -> String {
+    self.braces(escape_html(text))
   }
 
-  fn compile_blockquote(&mut self, blockquote: &mut ast::Blockquote) {
-    let mut blockquote_items = String::new();
-    for item in &*blockquote.children {
-      blockquote_items.push_str(&format!("<p>{}</p>", item));
+  fn braces(&self, html: String) -> String {
+    match self.escape_braces {
+      true => html.replace('{', "&#123;").replace('}', "&#125;"),
+      false => html,
     }
-    self.push(&format!("<blockquote>{}</blockquote>", blockquote_items));
   }
 
-  fn compile_link(&mut self, link: &mut ast::Link) {
-    // TODO: add title
-    self.push(&format!("<a href=\"{}\">{}</a>", link.url, link.alt));
+  // writes a block line, indented and followed by a newline in pretty mode
+  fn line(&mut self, text: &str) {
+    if self.mode == HtmlMode::Pretty {
+      self.output.push_str(&"  ".repeat(self.depth));
+    }
+    self.output.push_str(text);
+    if self.mode == HtmlMode::Pretty {
+      self.output.push('\n');
+    }
   }
 
-  fn compile_image(&mut self, image: &mut ast::Link) {
-    // TODO: add title
-    self.push(&format!("<img src=\"{}\" alt=\"{}\" />", image.url, image.alt));
-    self.double_new_line();
+  pub fn push(&mut self, text: &str) {
+    self.output.push_str(text);
   }
+}
 
-  fn compile_paragraph(&mut self, paragraph: &mut ast::Paragraph) {
-    self.push("<p>");
-    for node in &mut *paragraph.children {
-      self.compile_line_node(node);
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+      | ast::Node::Link(_)
+      | ast::Node::Image(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+  )
+}
+
+fn title(title: &Option<String>) -> String {
+  match title {
+    Some(title) => format!(" title=\"{}\"", escape_html(title)),
+    None => String::new(),
+  }
+}
+
+fn collapse(text: &str) -> String {
+  let mut collapsed = String::with_capacity(text.len());
+  let mut in_whitespace = false;
+  for character in text.chars() {
+    if character.is_whitespace() {
+      if !in_whitespace {
+        collapsed.push(' ');
+      }
+      in_whitespace = true;
+    } else {
+      collapsed.push(character);
+      in_whitespace = false;
     }
-    self.push("</p>");
-    self.double_new_line();
   }
+  collapsed
+}
 
-  pub fn new_line(&mut self) {
-    self.output.push_str("\n");
+// like `escape_html`, but inline tags e.g. `a <kbd>b</kbd>` and entities e.g. `&copy;` are kept, since markdown
+// text can hold them
+pub fn escape_text(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(character) = rest.chars().next() {
+    let markup = match character {
+      '<' => tag_length(rest),
+      '&' => entity_length(rest),
+      _ => 0,
+    };
+    if markup > 0 {
+      escaped.push_str(&rest[..markup]);
+      rest = &rest[markup..];
+      continue;
+    }
+    escaped.push_str(&escape_html(&rest[..character.len_utf8()]));
+    rest = &rest[character.len_utf8()..];
   }
+  escaped
+}
+
+// the bytes `start..end` of a highlighted line, a span per colored token
+fn colored(tokens: &[Token], start: usize, end: usize) -> String {
+  let mut html = String::new();
+  let mut offset = 0;
+  for token in tokens {
+    let (token_start, token_end) = (offset.max(start), (offset + token.text.len()).min(end));
+    if token_start < token_end {
+      let text = escape_html(&token.text[token_start - offset..token_end - offset]);
+      match &token.color {
+        Some(color) => html.push_str(&format!("<span style=\"color:{}\">{}</span>", color, text)),
+        None => html.push_str(&text),
+      }
+    }
+    offset += token.text.len();
+  }
+  html
+}
 
-  pub fn double_new_line(&mut self) {
-    self.output.push_str("\n\n");
+// the length of `<tag ...>`, `</tag>` or `<!-- -->` at the start of `text`, 0 when it isn't one
+fn tag_length(text: &str) -> usize {
+  if text.starts_with("<!--") {
+    return text.find("-->").map_or(0, |end| end + 3);
   }
-  pub fn white_space(&mut self) {
-    self.output.push_str(" ");
+  let name = text.strip_prefix("</").unwrap_or(&text[1..]);
+  if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+    return 0;
   }
-  pub fn push(&mut self, text: &str) {
-    self.output.push_str(text);
+  let mut quote = None;
+  for (index, character) in text.char_indices() {
+    match (quote, character) {
+      (None, '"' | '\'') => quote = Some(character),
+      (Some(open), _) if open == character => quote = None,
+      (None, '>') => return index + 1,
+      (None, '<') if index > 0 => return 0,
+      _ => {}
+    }
+  }
+  0
+}
+
+// the length of `&name;`, `&#123;` or `&#x1f;` at the start of `text`, 0 when it isn't one
+fn entity_length(text: &str) -> usize {
+  let Some(end) = text.find(';') else {
+    return 0;
+  };
+  let body = &text[1..end];
+  let valid = match body.strip_prefix('#') {
+    Some(number) => match number.strip_prefix(['x', 'X']) {
+      Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+      None => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+    },
+    None => body.starts_with(|c: char| c.is_ascii_alphabetic()) && body.chars().all(|c| c.is_ascii_alphanumeric()),
+  };
+  if valid {
+    end + 1
+  } else {
+    0
+  }
+}
+
+pub fn escape_html(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    match character {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(character),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs, path::Path};
+
+  use super::{HtmlCompiler, HtmlMode};
+  use crate::{ast, core::Core, transformer::TransformerHeadingSlug, transformer::TransformerNotationDiff};
+
+  // compiles every tests/fixtures/html/*.md and compares with the file next to it,
+  // run with UPDATE_GOLDEN=1 to rewrite the expected files.
+  fn assert_golden(mode: HtmlMode, extension: &str) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/html");
+    let mut paths: Vec<_> = fs::read_dir(&fixtures)
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .collect();
+    paths.sort();
+    for path in paths
+      .iter()
+      .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+    {
+      let source = fs::read_to_string(path).unwrap();
+      let mut core = Core::default()
+        .use_transformer(Box::new(TransformerHeadingSlug::new()))
+        .use_transformer(Box::new(TransformerNotationDiff::default()));
+      let mut output = core.compile(&source).unwrap();
+      let actual = HtmlCompiler::with_mode(mode).compile(&mut output.ast);
+      let expected_path = path.with_extension(extension);
+      if env::var("UPDATE_GOLDEN").is_ok() {
+        fs::write(&expected_path, format!("{}\n", actual)).unwrap();
+        continue;
+      }
+      let expected = fs::read_to_string(&expected_path).unwrap();
+      assert_eq!(actual, expected.trim_end(), "{}", expected_path.display());
+    }
+  }
+
+  #[test]
+  fn pretty_golden() {
+    assert_golden(HtmlMode::Pretty, "html");
+  }
+
+  #[test]
+  fn minified_golden() {
+    assert_golden(HtmlMode::Minified, "min.html");
+  }
+
+  fn text(text: &str) -> ast::Node {
+    ast::Node::Text(ast::Text { text: text.to_string() })
+  }
+
+  // what the parser doesn't produce but a tree built elsewhere can hold, nothing is dropped
+  #[test]
+  fn writes_components_and_blocks_inside_inline_content() {
+    let badge = ast::Node::ReactComponent(ast::ReactComponent {
+      name: "Badge".to_string(),
+      props: vec!["kind=\"new\"".to_string()],
+      children: Box::new(vec![text("New")]),
+      position: None,
+    });
+    let paragraph = ast::Node::Paragraph(ast::Paragraph {
+      children: Box::new(vec![
+        text("Text "),
+        badge,
+        ast::Node::Bold(ast::Bold {
+          children: Box::new(vec![ast::Node::Paragraph(ast::Paragraph {
+            children: Box::new(vec![text("inner")]),
+            position: None,
+          })]),
+        }),
+        ast::Node::ThematicBreak,
+      ]),
+      position: None,
+    });
+    let mut ast = ast::Ast::Root(ast::Root { children: Box::new(vec![paragraph]), ..ast::Root::default() });
+    assert_eq!(
+      HtmlCompiler::with_mode(HtmlMode::Minified).compile(&mut ast),
+      "<p>Text New<b>inner</b><hr /></p>"
+    );
+    assert_eq!(
+      HtmlCompiler::with_mode(HtmlMode::Minified)
+        .keep_components()
+        .compile(&mut ast),
+      "<p>Text <Badge kind=\"new\">New</Badge><b>inner</b><hr /></p>"
+    );
   }
 }
@@ -0,0 +1,346 @@
+use crate::ast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatexOptions {
+  pub standalone: bool, // a whole document with the preamble, title, author and date from the frontmatter
+  pub listings: bool,   // code blocks with a language use the listings package, verbatim otherwise
+}
+
+impl Default for LatexOptions {
+  fn default() -> Self {
+    Self { standalone: false, listings: true }
+  }
+}
+
+// the languages the listings package knows, by the names used after ```
+const LISTINGS_LANGUAGES: [(&str, &str); 20] = [
+  ("c", "C"),
+  ("cpp", "C++"),
+  ("c++", "C++"),
+  ("java", "Java"),
+  ("py", "Python"),
+  ("python", "Python"),
+  ("rb", "Ruby"),
+  ("ruby", "Ruby"),
+  ("sh", "bash"),
+  ("bash", "bash"),
+  ("shell", "bash"),
+  ("sql", "SQL"),
+  ("html", "HTML"),
+  ("xml", "XML"),
+  ("php", "PHP"),
+  ("perl", "Perl"),
+  ("lua", "Lua"),
+  ("haskell", "Haskell"),
+  ("tex", "TeX"),
+  ("latex", "TeX"),
+];
+
+const PREAMBLE: &str = r"\documentclass{article}
+\usepackage[utf8]{inputenc}
+\usepackage[T1]{fontenc}
+\usepackage{graphicx}
+\usepackage{listings}
+\usepackage{hyperref}
+\lstset{basicstyle=\ttfamily\small,breaklines=true,columns=fullflexible}
+";
+
+// headings become \section..\subparagraph labelled with their id, so `[text](#id)` links to them
+#[derive(Default)]
+pub struct LatexCompiler {
+  options: LatexOptions,
+}
+
+impl LatexCompiler {
+  pub fn new() -> Self {
+    Self::with_options(LatexOptions::default())
+  }
+
+  pub fn with_options(options: LatexOptions) -> Self {
+    Self { options }
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let ast::Ast::Root(root) = ast;
+    let body = self.compile_blocks(&root.children);
+    if !self.options.standalone {
+      return body;
+    }
+    let mut output = PREAMBLE.to_string();
+    let title = root.frontmatter.get("title");
+    for (field, command) in [("title", "title"), ("author", "author"), ("date", "date")] {
+      if let Some(value) = root.frontmatter.get(field) {
+        output.push_str(&format!("\\{}{{{}}}\n", command, escape_latex(value)));
+      }
+    }
+    output.push_str("\n\\begin{document}\n");
+    if title.is_some() {
+      output.push_str("\\maketitle\n");
+    }
+    output.push('\n');
+    output.push_str(&body);
+    output.push_str("\n\n\\end{document}");
+    output
+  }
+
+  // ==================
+  // block nodes, separated by a blank line which is also the end of a paragraph in latex
+  //
+  fn compile_blocks(&self, nodes: &[ast::Node]) -> String {
+    let mut blocks = Vec::new();
+    let mut inline = Vec::new();
+    for node in nodes {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      if !inline.is_empty() {
+        blocks.push(self.compile_inline(&std::mem::take(&mut inline)));
+      }
+      blocks.push(self.compile_block(node));
+    }
+    if !inline.is_empty() {
+      blocks.push(self.compile_inline(&inline));
+    }
+    blocks.retain(|block| !block.trim().is_empty());
+    blocks.join("\n\n")
+  }
+
+  fn compile_block(&self, node: &ast::Node) -> String {
+    match node {
+      ast::Node::Heading(heading) => {
+        let command = match heading.level {
+          1 => "section",
+          2 => "subsection",
+          3 => "subsubsection",
+          4 => "paragraph",
+          _ => "subparagraph",
+        };
+        let label = match &heading.id {
+          Some(id) => format!("\\label{{{}}}", escape_label(id)),
+          None => String::new(),
+        };
+        format!("\\{}{{{}}}{}", command, escape_latex(heading.text.trim()), label)
+      }
+      ast::Node::Paragraph(paragraph) => self.compile_inline(&paragraph.children),
+      ast::Node::CodeBlock(code_block) => self.compile_code_block(code_block),
+      ast::Node::Html(html) => html.trim_end().lines().map(|line| format!("% {}", line)).collect::<Vec<_>>().join("\n"),
+      ast::Node::List(list) => self.compile_list(list),
+      ast::Node::ListItem(item) => format!("\\item {}", self.compile_blocks(&item.children)),
+      ast::Node::Blockquote(blockquote) => {
+        format!("\\begin{{quote}}\n{}\n\\end{{quote}}", self.compile_blocks(&blockquote.children))
+      }
+      ast::Node::Table(table) => compile_table(table),
+      ast::Node::ThematicBreak => "\\begin{center}\\rule{0.5\\linewidth}{0.4pt}\\end{center}".to_string(),
+      // a paragraph of only an image is a figure with the alt text as its caption
+      ast::Node::Image(image) => format!(
+        "\\begin{{figure}}[h]\n\\centering\n\\includegraphics[width=\\linewidth]{{{}}}\n\\caption{{{}}}\n\\end{{figure}}",
+        escape_url(&image.url),
+        escape_latex(&image.alt)
+      ),
+      ast::Node::ReactComponent(ast::ReactComponent { name, children, .. })
+      | ast::Node::VueComponent(ast::VueComponent { name, children, .. }) => {
+        format!("% <{}>\n{}\n% </{}>", name, self.compile_blocks(children), name)
+      }
+      _ => String::new(),
+    }
+  }
+
+  fn compile_list(&self, list: &ast::List) -> String {
+    let environment = if list.ordered { "enumerate" } else { "itemize" };
+    let mut lines = vec![format!("\\begin{{{}}}", environment)];
+    if let Some(start) = list.start.filter(|start| list.ordered && *start != 1) {
+      lines.push(format!("\\setcounter{{enumi}}{{{}}}", start.saturating_sub(1)));
+    }
+    for item in list.children.iter() {
+      let item = match item {
+        ast::Node::ListItem(item) => format!("\\item {}", self.compile_blocks(&item.children)),
+        node => format!("\\item {}", self.compile_block(node)),
+      };
+      lines.push(item.trim_end().to_string());
+    }
+    lines.push(format!("\\end{{{}}}", environment));
+    lines.join("\n")
+  }
+
+  fn compile_code_block(&self, code_block: &ast::CodeBlock) -> String {
+    let code = code_block.code.trim_end_matches('\n');
+    if !self.options.listings || code_block.language.is_empty() {
+      return format!("\\begin{{verbatim}}\n{}\n\\end{{verbatim}}", code);
+    }
+    let language = code_block.language.to_lowercase();
+    let options = match LISTINGS_LANGUAGES.iter().find(|(name, _)| *name == language) {
+      Some((_, language)) => format!("[language={}]", language),
+      None => String::new(),
+    };
+    format!("\\begin{{lstlisting}}{}\n{}\n\\end{{lstlisting}}", options, code)
+  }
+
+  // ==================
+  // inline nodes
+  //
+  fn compile_inline(&self, nodes: &[ast::Node]) -> String {
+    let mut output = String::new();
+    for node in nodes {
+      match node {
+        ast::Node::Text(text) => output.push_str(&escape_latex(&text.text)),
+        ast::Node::Bold(bold) => output.push_str(&format!("\\textbf{{{}}}", self.compile_inline(&bold.children))),
+        ast::Node::Italic(italic) => output.push_str(&format!("\\emph{{{}}}", self.compile_inline(&italic.children))),
+        ast::Node::InlineCode(code) => output.push_str(&format!("\\texttt{{{}}}", escape_latex(&code.code))),
+        ast::Node::Link(link) => match link.url.strip_prefix('#') {
+          Some(id) => output.push_str(&format!(
+            "\\hyperref[{}]{{{}}}",
+            escape_label(id),
+            escape_latex(&link.alt)
+          )),
+          None => output.push_str(&format!(
+            "\\href{{{}}}{{{}}}",
+            escape_url(&link.url),
+            escape_latex(&link.alt)
+          )),
+        },
+        ast::Node::Image(image) => output.push_str(&format!("\\includegraphics{{{}}}", escape_url(&image.url))),
+        ast::Node::Html(html) => output.push_str(&escape_latex(html)),
+        ast::Node::SoftBreak => output.push('\n'),
+        ast::Node::HardBreak => output.push_str("\\\\\n"),
+        _ => {}
+      }
+    }
+    output.trim().to_string()
+  }
+}
+
+// header row in bold, every column left aligned
+fn compile_table(table: &ast::Table) -> String {
+  let count = std::iter::once(&table.header)
+    .chain(table.rows.iter())
+    .map(|row| row.len())
+    .max()
+    .unwrap_or(0);
+  let row = |cells: &[String], bold: bool| {
+    let cells: Vec<String> = (0..count)
+      .map(|column| {
+        let cell = escape_latex(cells.get(column).map_or("", |cell| cell.trim()));
+        if bold && !cell.is_empty() {
+          format!("\\textbf{{{}}}", cell)
+        } else {
+          cell
+        }
+      })
+      .collect();
+    format!("{} \\\\", cells.join(" & "))
+  };
+  let mut lines = vec![
+    format!("\\begin{{tabular}}{{|{}}}", "l|".repeat(count)),
+    "\\hline".to_string(),
+  ];
+  lines.push(row(&table.header, true));
+  lines.push("\\hline".to_string());
+  for cells in &table.rows {
+    lines.push(row(cells, false));
+  }
+  if !table.rows.is_empty() {
+    lines.push("\\hline".to_string());
+  }
+  lines.push("\\end{tabular}".to_string());
+  lines.join("\n")
+}
+
+// the ten special characters, written so they print as themselves
+pub fn escape_latex(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    match character {
+      '\\' => escaped.push_str("\\textbackslash{}"),
+      '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+        escaped.push('\\');
+        escaped.push(character);
+      }
+      '~' => escaped.push_str("\\textasciitilde{}"),
+      '^' => escaped.push_str("\\textasciicircum{}"),
+      _ => escaped.push(character),
+    }
+  }
+  escaped
+}
+
+// \href and \includegraphics read the url verbatim except for these
+fn escape_url(url: &str) -> String {
+  url.replace('\\', "/").replace('%', "\\%").replace('#', "\\#")
+}
+
+// labels can't hold the characters that end or escape them
+fn escape_label(id: &str) -> String {
+  id.chars().filter(|c| !"\\{}%#$&^~ ".contains(*c)).collect()
+}
+
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Link(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{escape_latex, LatexCompiler, LatexOptions};
+  use crate::{core::Core, transformer::TransformerHeadingSlug};
+
+  #[test]
+  fn compiles_nodes_to_latex() {
+    let source = "# Costs & 100% of $x_1\n\nSee **[the intro](#costs--100-of-x_1)** and [site](https://a.com/#b).\n\n1. one\n2. `a{b}`\n\n> quoted\n\n```py\nprint('\\\\')\n```\n\n```\nplain\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+    let mut document = Core::default()
+      .use_transformer(Box::new(TransformerHeadingSlug::new()))
+      .compile(source)
+      .unwrap();
+    let expected = r"\section{Costs \& 100\% of \$x\_1}\label{costs--100-of-x_1}
+
+See \textbf{\hyperref[costs--100-of-x_1]{the intro}} and \href{https://a.com/\#b}{site}.
+
+\begin{enumerate}
+\item one
+\item \texttt{a\{b\}}
+\end{enumerate}
+
+\begin{quote}
+quoted
+\end{quote}
+
+\begin{lstlisting}[language=Python]
+print('\\')
+\end{lstlisting}
+
+\begin{verbatim}
+plain
+\end{verbatim}
+
+\begin{tabular}{|l|l|}
+\hline
+\textbf{a} & \textbf{b} \\
+\hline
+1 & 2 \\
+\hline
+\end{tabular}";
+    assert_eq!(LatexCompiler::new().compile(&mut document.ast), expected);
+  }
+
+  #[test]
+  fn standalone_documents_have_a_preamble() {
+    let mut document = Core::default()
+      .compile("---\ntitle: A ~ B\nauthor: Me\n---\nHi\n")
+      .unwrap();
+    let output = LatexCompiler::with_options(LatexOptions { standalone: true, ..LatexOptions::default() })
+      .compile(&mut document.ast);
+    assert!(output.starts_with("\\documentclass{article}\n"));
+    assert!(output.contains(
+      "\\title{A \\textasciitilde{} B}\n\\author{Me}\n\n\\begin{document}\n\\maketitle\n\nHi\n\n\\end{document}"
+    ));
+    assert_eq!(escape_latex("\\^"), "\\textbackslash{}\\textasciicircum{}");
+  }
+}
@@ -0,0 +1,474 @@
+use crate::ast;
+
+// how the markdown is written, the defaults follow remark-stringify
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownOptions {
+  pub emphasis: char,            // `*` or `_` around italic text, bold is always `**`
+  pub bullet: char,              // `-`, `*` or `+`
+  pub fence: char,               // ` or ~, the fence grows when the code contains it
+  pub line_width: Option<usize>, // paragraphs are reflowed to fit, None keeps the lines as written
+  pub table_padding: bool,       // pad the cells so the columns line up
+}
+
+impl Default for MarkdownOptions {
+  fn default() -> Self {
+    Self { emphasis: '*', bullet: '-', fence: '`', line_width: None, table_padding: true }
+  }
+}
+
+// prints the tree back as markdown, text is written as the parser read it so nothing is escaped
+#[derive(Default)]
+pub struct MarkdownCompiler {
+  options: MarkdownOptions,
+}
+
+impl MarkdownCompiler {
+  pub fn new() -> Self {
+    Self::with_options(MarkdownOptions::default())
+  }
+
+  pub fn with_options(options: MarkdownOptions) -> Self {
+    Self { options }
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let ast::Ast::Root(root) = ast;
+    let mut output = String::new();
+    if !root.frontmatter.is_empty() {
+      output.push_str("---\n");
+      for (key, value) in &root.frontmatter {
+        output.push_str(&format!("{}: {}\n", key, frontmatter_value(value)));
+      }
+      output.push_str("---\n\n");
+    }
+    output.push_str(&self.compile_blocks(&root.children, "\n\n"));
+    output.trim_end().to_string()
+  }
+
+  // ==================
+  // block nodes, separated by a blank line except inside list items where it would end the item
+  //
+  fn compile_blocks(&self, nodes: &[ast::Node], separator: &str) -> String {
+    let mut blocks = Vec::new();
+    let mut inline = Vec::new();
+    for node in nodes {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      if !inline.is_empty() {
+        blocks.push(self.compile_inline(&std::mem::take(&mut inline)));
+      }
+      blocks.push(self.compile_block(node));
+    }
+    if !inline.is_empty() {
+      blocks.push(self.compile_inline(&inline));
+    }
+    blocks.join(separator)
+  }
+
+  fn compile_block(&self, node: &ast::Node) -> String {
+    match node {
+      ast::Node::Heading(heading) => format!("{} {}", "#".repeat(heading.level), heading.text.trim()),
+      ast::Node::Paragraph(paragraph) => self.compile_paragraph(&paragraph.children, self.options.line_width),
+      ast::Node::CodeBlock(code_block) => self.compile_code_block(code_block),
+      ast::Node::Html(html) => html.trim().to_string(),
+      ast::Node::List(list) => self.compile_list(list),
+      ast::Node::ListItem(item) => self.compile_list_item(&item.children, &format!("{} ", self.options.bullet), false),
+      ast::Node::Blockquote(blockquote) => {
+        let width = self.options.line_width.map(|width| width.saturating_sub(2));
+        let inner = Self::with_options(MarkdownOptions { line_width: width, ..self.options });
+        prefix_lines(&inner.compile_blocks(&blockquote.children, "\n\n"), "> ", ">")
+      }
+      ast::Node::Table(table) => self.compile_table(table),
+      ast::Node::ThematicBreak => "***".to_string(),
+      ast::Node::ReactComponent(component) => {
+        self.compile_component(&component.name, &component.props, &component.children)
+      }
+      ast::Node::VueComponent(component) => {
+        self.compile_component(&component.name, &component.props, &component.children)
+      }
+      _ => self.compile_inline(std::slice::from_ref(node)),
+    }
+  }
+
+  fn compile_paragraph(&self, children: &[ast::Node], line_width: Option<usize>) -> String {
+    match line_width {
+      Some(width) => {
+        let mut words = Words::default();
+        self.collect_words(children, &mut words);
+        words.fill(width)
+      }
+      None => self.compile_inline(children),
+    }
+  }
+
+  fn compile_code_block(&self, code_block: &ast::CodeBlock) -> String {
+    let fence = fence(&code_block.code, self.options.fence);
+    let mut info = code_block.language.clone();
+    for meta in code_block.meta.iter().flatten() {
+      info.push(' ');
+      info.push_str(meta);
+    }
+    format!("{}{}\n{}\n{}", fence, info.trim(), code_block.code, fence)
+  }
+
+  // the items of loose lists and the blocks inside them are separated by a blank line
+  fn compile_list(&self, list: &ast::List) -> String {
+    let start = list.start.unwrap_or(1);
+    let items: Vec<String> = list
+      .children
+      .iter()
+      .enumerate()
+      .map(|(index, item)| {
+        let marker = match list.ordered {
+          true => format!("{}. ", start + index),
+          false => format!("{} ", self.options.bullet),
+        };
+        match item {
+          ast::Node::ListItem(item) => self.compile_list_item(&item.children, &marker, list.loose),
+          _ => self.compile_list_item(std::slice::from_ref(item), &marker, list.loose),
+        }
+      })
+      .collect();
+    items.join(if list.loose { "\n\n" } else { "\n" })
+  }
+
+  // the first line follows the marker, the rest is indented under it
+  fn compile_list_item(&self, children: &[ast::Node], marker: &str, loose: bool) -> String {
+    let inner = Self::with_options(MarkdownOptions { line_width: None, ..self.options });
+    let content = inner.compile_blocks(children, if loose { "\n\n" } else { "\n" });
+    let indent = " ".repeat(marker.len());
+    let mut lines = content.lines();
+    let mut item = format!("{}{}", marker, lines.next().unwrap_or_default())
+      .trim_end()
+      .to_string();
+    for line in lines {
+      item.push('\n');
+      if !line.is_empty() {
+        item.push_str(&indent);
+        item.push_str(line);
+      }
+    }
+    item
+  }
+
+  fn compile_table(&self, table: &ast::Table) -> String {
+    let columns = table.header.len();
+    let rows: Vec<Vec<&str>> = std::iter::once(&table.header)
+      .chain(&table.rows)
+      .map(|row| {
+        (0..columns)
+          .map(|index| row.get(index).map_or("", |cell| cell.trim()))
+          .collect()
+      })
+      .collect();
+    let widths: Vec<usize> = (0..columns)
+      .map(|index| match self.options.table_padding {
+        true => rows
+          .iter()
+          .map(|row| row[index].chars().count())
+          .max()
+          .unwrap_or(0)
+          .max(3),
+        false => 3,
+      })
+      .collect();
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let pad = |cell: &str, width: usize| match self.options.table_padding {
+      true => format!("{}{}", cell, " ".repeat(width - cell.chars().count())),
+      false => cell.to_string(),
+    };
+    let mut lines = vec![line(
+      rows[0]
+        .iter()
+        .zip(&widths)
+        .map(|(cell, width)| pad(cell, *width))
+        .collect(),
+    )];
+    let delimiter = |index: usize, width: usize| match table.align.get(index).copied().flatten() {
+      Some(ast::Align::Left) => format!(":{}", "-".repeat(width - 1)),
+      Some(ast::Align::Center) => format!(":{}:", "-".repeat(width - 2)),
+      Some(ast::Align::Right) => format!("{}:", "-".repeat(width - 1)),
+      None => "-".repeat(width),
+    };
+    lines.push(line(
+      widths
+        .iter()
+        .enumerate()
+        .map(|(index, width)| delimiter(index, *width))
+        .collect(),
+    ));
+    for row in &rows[1..] {
+      lines.push(line(
+        row.iter().zip(&widths).map(|(cell, width)| pad(cell, *width)).collect(),
+      ));
+    }
+    lines.join("\n")
+  }
+
+  // jsx and vue blocks are written as tags around their children
+  fn compile_component(&self, name: &str, props: &[String], children: &[ast::Node]) -> String {
+    let open = component_open(name, props);
+    if children.is_empty() {
+      return format!("{} />", open);
+    }
+    format!("{}>\n{}\n</{}>", open, self.compile_blocks(children, "\n\n"), name)
+  }
+
+  // ==================
+  // inline nodes, the text keeps its line breaks
+  //
+  fn compile_inline(&self, nodes: &[ast::Node]) -> String {
+    let mut output = String::new();
+    for node in nodes {
+      self.compile_line_node(node, &mut output);
+    }
+    output.trim().to_string()
+  }
+
+  fn compile_line_node(&self, node: &ast::Node, output: &mut String) {
+    match node {
+      ast::Node::Text(text) => output.push_str(&text.text),
+      ast::Node::SoftBreak => output.push('\n'),
+      ast::Node::HardBreak => output.push_str("\\\n"),
+      ast::Node::Bold(bold) => {
+        output.push_str("**");
+        bold
+          .children
+          .iter()
+          .for_each(|node| self.compile_line_node(node, output));
+        output.push_str("**");
+      }
+      ast::Node::Italic(italic) => {
+        output.push(self.options.emphasis);
+        italic
+          .children
+          .iter()
+          .for_each(|node| self.compile_line_node(node, output));
+        output.push(self.options.emphasis);
+      }
+      ast::Node::ReactComponent(component) => {
+        self.compile_inline_component(&component.name, &component.props, &component.children, output)
+      }
+      ast::Node::VueComponent(component) => {
+        self.compile_inline_component(&component.name, &component.props, &component.children, output)
+      }
+      ast::Node::Paragraph(paragraph) => paragraph
+        .children
+        .iter()
+        .for_each(|node| self.compile_line_node(node, output)),
+      _ => output.push_str(&self.compile_atom(node)),
+    }
+  }
+
+  fn compile_inline_component(&self, name: &str, props: &[String], children: &[ast::Node], output: &mut String) {
+    let open = component_open(name, props);
+    if children.is_empty() {
+      return output.push_str(&format!("{} />", open));
+    }
+    output.push_str(&format!("{}>", open));
+    children.iter().for_each(|node| self.compile_line_node(node, output));
+    output.push_str(&format!("</{}>", name));
+  }
+
+  // nodes that are never split across lines
+  fn compile_atom(&self, node: &ast::Node) -> String {
+    match node {
+      ast::Node::InlineCode(inline_code) => format!("`{}`", inline_code.code),
+      ast::Node::Link(link) => format!("[{}]({}{})", link.alt, link.url, title(&link.title)),
+      ast::Node::Image(image) => format!("![{}]({}{})", image.alt, image.url, title(&image.title)),
+      ast::Node::Html(html) => html.trim().to_string(),
+      _ => self.compile_block(node),
+    }
+  }
+
+  // the words of a paragraph for `line_width`, emphasis markers stick to the words they wrap
+  fn collect_words(&self, nodes: &[ast::Node], words: &mut Words) {
+    for node in nodes {
+      match node {
+        ast::Node::Text(text) => words.text(&text.text),
+        ast::Node::SoftBreak => words.space(),
+        ast::Node::HardBreak => {
+          words.atom("\\");
+          words.words.push(String::from("\n"));
+        }
+        ast::Node::Bold(bold) => {
+          words.atom("**");
+          self.collect_words(&bold.children, words);
+          words.atom("**");
+        }
+        ast::Node::Italic(italic) => {
+          words.atom(&self.options.emphasis.to_string());
+          self.collect_words(&italic.children, words);
+          words.atom(&self.options.emphasis.to_string());
+        }
+        _ => {
+          let mut output = String::new();
+          self.compile_line_node(node, &mut output);
+          words.atom(&output);
+        }
+      }
+    }
+  }
+}
+
+// ==================
+// reflowing paragraphs to `line_width`
+//
+#[derive(Default)]
+struct Words {
+  words: Vec<String>, // "\n" forces a line break
+  open: bool,         // the next text is glued to the last word
+}
+
+impl Words {
+  fn text(&mut self, text: &str) {
+    for character in text.chars() {
+      if character.is_whitespace() {
+        self.space();
+        continue;
+      }
+      self.atom(&character.to_string());
+    }
+  }
+
+  fn atom(&mut self, text: &str) {
+    if !self.open {
+      self.words.push(String::new());
+      self.open = true;
+    }
+    if let Some(word) = self.words.last_mut() {
+      word.push_str(text);
+    }
+  }
+
+  fn space(&mut self) {
+    self.open = false;
+  }
+
+  // a word that would start a heading, list, quote or table stays on the line before it
+  fn fill(&self, width: usize) -> String {
+    let mut lines: Vec<String> = vec![String::new()];
+    for word in &self.words {
+      let line = lines.last_mut().expect("there is always a line");
+      if word == "\n" {
+        lines.push(String::new());
+        continue;
+      }
+      if line.is_empty() {
+        line.push_str(word);
+        continue;
+      }
+      if line.chars().count() + 1 + word.chars().count() > width && !starts_block(word) {
+        lines.push(word.clone());
+        continue;
+      }
+      line.push(' ');
+      line.push_str(word);
+    }
+    lines.join("\n").trim().to_string()
+  }
+}
+
+fn starts_block(word: &str) -> bool {
+  let digits = word.trim_start_matches(|c: char| c.is_ascii_digit());
+  matches!(word, "-" | "+" | "*")
+    || word.starts_with(['#', '>', '|', '<'])
+    || word.starts_with("```")
+    || word.starts_with("~~~")
+    || (digits.len() < word.len() && digits.starts_with('.'))
+}
+
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+      | ast::Node::Link(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+  )
+}
+
+// a fence longer than any run of `marker` at the start of a line of `code`
+fn fence(code: &str, marker: char) -> String {
+  let longest = code
+    .lines()
+    .map(|line| line.trim_start().chars().take_while(|c| *c == marker).count())
+    .max()
+    .unwrap_or(0);
+  marker.to_string().repeat(longest.max(2) + 1)
+}
+
+// in the first quotes the title doesn't contain
+fn title(title: &Option<String>) -> String {
+  match title {
+    Some(title) if !title.contains('"') => format!(" \"{}\"", title),
+    Some(title) if !title.contains('\'') => format!(" '{}'", title),
+    Some(title) => format!(" ({})", title),
+    None => String::new(),
+  }
+}
+
+fn component_open(name: &str, props: &[String]) -> String {
+  let mut open = format!("<{}", name);
+  for prop in props {
+    open.push(' ');
+    open.push_str(prop);
+  }
+  open
+}
+
+// the parser strips one pair of quotes, values that need them get them back
+fn frontmatter_value(value: &str) -> String {
+  let needs_quotes =
+    value != value.trim() || value.contains(": ") || value.contains(" #") || value.starts_with(['"', '\'']);
+  match needs_quotes {
+    true => format!("\"{}\"", value),
+    false => value.to_string(),
+  }
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty: &str) -> String {
+  let lines: Vec<String> = text
+    .lines()
+    .map(|line| match line.is_empty() {
+      true => empty.to_string(),
+      false => format!("{}{}", prefix, line),
+    })
+    .collect();
+  lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{MarkdownCompiler, MarkdownOptions};
+  use crate::parser::MdxParser;
+
+  fn format(source: &str, options: MarkdownOptions) -> String {
+    MarkdownCompiler::with_options(options).compile(&mut MdxParser::parse(source).unwrap())
+  }
+
+  #[test]
+  fn prints_canonical_markdown() {
+    let source = "---\ntitle: Hi\n---\n# Hi  \n\n+ one _a_\n+ two\n  1. x\n\n> quote\n> **b**\n\n---\n\n|a|b|\n|-|-|\n|long|2|\n\n```ts {1}\nlet a\n```\n\n<Note kind=\"x\">\ninner\n</Note>\n";
+    let expected = "---\ntitle: Hi\n---\n\n# Hi\n\n- one *a*\n- two\n  1. x\n\n> quote\n> **b**\n\n***\n\n| a    | b   |\n| ---- | --- |\n| long | 2   |\n\n```ts {1}\nlet a\n```\n\n<Note kind=\"x\">\ninner\n</Note>";
+    let markdown = format(source, MarkdownOptions::default());
+    assert_eq!(markdown, expected);
+    assert_eq!(format(&markdown, MarkdownOptions::default()), expected);
+  }
+
+  #[test]
+  fn follows_the_style_options() {
+    let options =
+      MarkdownOptions { emphasis: '_', bullet: '*', fence: '~', line_width: Some(20), table_padding: false };
+    let source = "- *a*\n\nsome **bold words** and a [link](./x.md) that wrap\n\n| a | b |\n|---|---|\n| long | 2 |\n\n```\n~~~\n```\n";
+    let expected = "* _a_\n\nsome **bold words**\nand a [link](./x.md)\nthat wrap\n\n| a | b |\n| --- | --- |\n| long | 2 |\n\n~~~~\n~~~\n~~~~";
+    let markdown = format(source, options);
+    assert_eq!(markdown, expected);
+    assert_eq!(format(&markdown, options), expected);
+  }
+}
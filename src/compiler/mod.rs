@@ -1,2 +1,90 @@
+mod ansi;
+mod html;
+mod latex;
+mod markdown;
+mod roff;
+mod svelte_compiler;
+mod text;
 mod tsx_compiler;
 mod vue_compiler;
+
+use std::collections::BTreeMap;
+
+use crate::ast;
+
+pub use ansi::{AnsiCompiler, AnsiOptions};
+pub use html::{escape_html, HtmlCompiler, HtmlMode};
+pub use latex::{escape_latex, LatexCompiler, LatexOptions};
+pub use markdown::{MarkdownCompiler, MarkdownOptions};
+pub use roff::{escape_roff, is_man_section, man_section, RoffCompiler};
+pub use svelte_compiler::SvelteCompiler;
+pub use text::{TextCompiler, TextOptions, TextStats};
+pub use tsx_compiler::TsxCompiler;
+pub use vue_compiler::VueCompiler;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+  Html,
+  Jsx,
+  Tsx, // the jsx output, typed modules only differ in the extension
+  Vue,
+  Svelte,
+  Markdown, // normalized markdown/mdx, e.g. to write a transformed document back
+  Text,     // plain text without markup, e.g. for search indexes
+  Latex,    // a latex fragment, or a whole document with a preamble
+  Roff,     // man pages, the extension is the `section` of the frontmatter
+}
+
+impl Target {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "html" => Some(Target::Html),
+      "jsx" => Some(Target::Jsx),
+      "tsx" => Some(Target::Tsx),
+      "vue" => Some(Target::Vue),
+      "svelte" => Some(Target::Svelte),
+      "markdown" => Some(Target::Markdown),
+      "text" => Some(Target::Text),
+      "latex" => Some(Target::Latex),
+      "roff" => Some(Target::Roff),
+      _ => None,
+    }
+  }
+
+  // the extension of the documents without frontmatter, see `output_extension`
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Target::Html => "html",
+      Target::Jsx => "jsx",
+      Target::Tsx => "tsx",
+      Target::Vue => "vue",
+      Target::Svelte => "svelte",
+      Target::Markdown => "md",
+      Target::Text => "txt",
+      Target::Latex => "tex",
+      Target::Roff => "1",
+    }
+  }
+
+  // a man page is named after its section e.g. `section: 5` writes foo.5
+  pub fn output_extension(&self, frontmatter: &BTreeMap<String, String>) -> String {
+    match (self, man_section(frontmatter)) {
+      (Target::Roff, Some(section)) => section.to_string(),
+      _ => self.extension().to_string(),
+    }
+  }
+
+  // `mode` is only used by the html target
+  pub fn compile(&self, ast: &mut ast::Ast, mode: HtmlMode) -> String {
+    match self {
+      Target::Html => HtmlCompiler::with_mode(mode).compile(ast),
+      Target::Jsx | Target::Tsx => TsxCompiler::new().compile(ast),
+      Target::Vue => VueCompiler::new().compile(ast),
+      Target::Svelte => SvelteCompiler::new().compile(ast),
+      Target::Markdown => MarkdownCompiler::new().compile(ast),
+      Target::Text => TextCompiler::new().compile(ast),
+      Target::Latex => LatexCompiler::new().compile(ast),
+      Target::Roff => RoffCompiler::new().compile(ast),
+    }
+  }
+}
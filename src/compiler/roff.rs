@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+
+use crate::ast;
+
+// ==================
+// man pages with the man macros e.g.
+//
+// ---
+// title: scc
+// section: 1
+// date: 2024-05-01
+// ---
+// # Name
+// scc - compile **mdx**
+//
+// .TH "SCC" "1" "2024-05-01"
+// .SH "NAME"
+// .PP
+// scc \- compile \fBmdx\fP
+//
+// level 1 headings are sections and level 2 subsections, the deeper ones are bold paragraphs. `source` and
+// `manual` in the frontmatter fill the last two .TH fields. anchors, images and thematic breaks are dropped
+// and tables use tbl, which `man` runs when the first line is `'\" t`. `scc build` names the page after the
+// section e.g. scc.1, see `man_section`.
+//
+#[derive(Default)]
+pub struct RoffCompiler {}
+
+impl RoffCompiler {
+  pub fn new() -> Self {
+    Self {}
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let ast::Ast::Root(root) = ast;
+    let mut output = String::new();
+    if root.children.iter().any(|node| matches!(node, ast::Node::Table(_))) {
+      output.push_str("'\\\" t\n");
+    }
+    if let Some(title) = root.frontmatter.get("title") {
+      let mut fields = vec![title.to_uppercase()];
+      fields.push(root.frontmatter.get("section").cloned().unwrap_or("1".to_string()));
+      for field in ["date", "source", "manual"] {
+        fields.push(root.frontmatter.get(field).cloned().unwrap_or_default());
+      }
+      while fields.last().is_some_and(String::is_empty) {
+        fields.pop();
+      }
+      let fields: Vec<String> = fields.iter().map(|field| argument(field)).collect();
+      output.push_str(&format!(".TH {}\n", fields.join(" ")));
+    }
+    output.push_str(&self.compile_blocks(&root.children, ".PP"));
+    output
+  }
+
+  // ==================
+  // block nodes, `paragraph` is the macro that starts a paragraph, .IP inside list items keeps the indent
+  //
+  fn compile_blocks(&self, nodes: &[ast::Node], paragraph: &str) -> String {
+    let mut output = String::new();
+    let mut inline = Vec::new();
+    for node in nodes {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      if !inline.is_empty() {
+        output.push_str(&self.compile_paragraph(&std::mem::take(&mut inline), paragraph));
+      }
+      output.push_str(&self.compile_block(node, paragraph));
+    }
+    if !inline.is_empty() {
+      output.push_str(&self.compile_paragraph(&inline, paragraph));
+    }
+    output
+  }
+
+  fn compile_block(&self, node: &ast::Node, paragraph: &str) -> String {
+    match node {
+      ast::Node::Heading(heading) => {
+        let text = heading.text.trim();
+        match heading.level {
+          1 => format!(".SH {}\n", argument(&text.to_uppercase())),
+          2 => format!(".SS {}\n", argument(text)),
+          _ => format!(".PP\n\\fB{}\\fP\n", escape_roff(text)),
+        }
+      }
+      ast::Node::Paragraph(node) => self.compile_paragraph(&node.children, paragraph),
+      ast::Node::CodeBlock(code_block) => {
+        let code = escape_roff(code_block.code.trim_end_matches('\n'));
+        format!(".PP\n.RS 4\n.nf\n{}\n.fi\n.RE\n", code)
+      }
+      ast::Node::Html(html) => html
+        .trim_end()
+        .lines()
+        .map(|line| format!(".\\\" {}\n", line))
+        .collect(),
+      // nested lists are indented so their tags don't line up with the parent's
+      ast::Node::List(list) if paragraph == ".IP" => format!(".RS 4\n{}.RE\n", self.compile_list(list)),
+      ast::Node::List(list) => self.compile_list(list),
+      ast::Node::ListItem(item) => format!(".IP \\(bu 2\n{}", self.compile_item(&item.children)),
+      ast::Node::Blockquote(blockquote) => format!(".RS 4\n{}.RE\n", self.compile_blocks(&blockquote.children, ".PP")),
+      ast::Node::Table(table) => compile_table(table),
+      ast::Node::ReactComponent(_) | ast::Node::VueComponent(_) => self.compile_blocks(node.children(), paragraph),
+      _ => String::new(),
+    }
+  }
+
+  fn compile_list(&self, list: &ast::List) -> String {
+    let mut output = String::new();
+    let start = list.start.unwrap_or(1);
+    for (index, item) in list.children.iter().enumerate() {
+      let tag = if list.ordered {
+        format!(".IP \"{}.\" 4\n", start + index)
+      } else {
+        ".IP \\(bu 2\n".to_string()
+      };
+      output.push_str(&tag);
+      match item {
+        ast::Node::ListItem(item) => output.push_str(&self.compile_item(&item.children)),
+        node => output.push_str(&self.compile_block(node, ".IP")),
+      }
+    }
+    output
+  }
+
+  // the first paragraph follows the .IP tag, the next ones are .IP paragraphs without a tag
+  fn compile_item(&self, children: &[ast::Node]) -> String {
+    let output = self.compile_blocks(children, ".IP");
+    output.strip_prefix(".IP\n").map(str::to_string).unwrap_or(output)
+  }
+
+  fn compile_paragraph(&self, nodes: &[ast::Node], paragraph: &str) -> String {
+    let mut text = String::new();
+    for node in nodes {
+      self.compile_inline(node, &mut text);
+    }
+    let lines: Vec<&str> = text
+      .lines()
+      .map(str::trim_end)
+      .filter(|line| !line.is_empty())
+      .collect();
+    if lines.is_empty() {
+      return String::new();
+    }
+    format!("{}\n{}\n", paragraph, lines.join("\n"))
+  }
+
+  // macros must start a line, so links are written on lines of their own
+  fn compile_inline(&self, node: &ast::Node, output: &mut String) {
+    match node {
+      ast::Node::Text(text) => push_text(output, &text.text),
+      ast::Node::Bold(_) | ast::Node::Italic(_) => {
+        let font = if matches!(node, ast::Node::Bold(_)) {
+          "\\fB"
+        } else {
+          "\\fI"
+        };
+        output.push_str(font);
+        for child in node.children() {
+          self.compile_inline(child, output);
+        }
+        output.push_str("\\fP");
+      }
+      ast::Node::InlineCode(code) => {
+        output.push_str("\\fB");
+        push_text(output, &code.code);
+        output.push_str("\\fP");
+      }
+      ast::Node::Link(link) if link.url.starts_with('#') || link.url.is_empty() => push_text(output, &link.alt),
+      ast::Node::Link(link) => {
+        let (start, end, url) = match link.url.strip_prefix("mailto:") {
+          Some(address) => (".MT", ".ME", address),
+          None => (".UR", ".UE", link.url.as_str()),
+        };
+        output.push_str(&format!("\n{} {}\n", start, url.replace('\\', "\\e")));
+        push_text(output, &link.alt);
+        output.push_str(&format!("\n{}\n", end));
+      }
+      ast::Node::HardBreak => output.push_str("\n.br\n"),
+      ast::Node::SoftBreak => output.push('\n'),
+      _ => {}
+    }
+  }
+}
+
+// tab separated cells, the header in bold and a rule under it
+fn compile_table(table: &ast::Table) -> String {
+  let count = std::iter::once(&table.header)
+    .chain(table.rows.iter())
+    .map(|row| row.len())
+    .max()
+    .unwrap_or(0);
+  let row = |cells: &[String]| {
+    let cells: Vec<String> = (0..count)
+      .map(|column| escape_roff(cells.get(column).map_or("", |cell| cell.trim())))
+      .collect();
+    cells.join("\t")
+  };
+  let mut output = format!(".PP\n.TS\ntab(\t);\n{}.\n", vec!["lb"; count].join(" "));
+  output.push_str(&format!("{}\n_\n", row(&table.header)));
+  if !table.rows.is_empty() {
+    output.push_str(&format!(".T&\n{}.\n", vec!["l"; count].join(" ")));
+  }
+  for cells in &table.rows {
+    output.push_str(&format!("{}\n", row(cells)));
+  }
+  output.push_str(".TE\n");
+  output
+}
+
+// leading whitespace would start an indented line, and \& before a `.` is harmless in the middle of one
+fn push_text(output: &mut String, text: &str) {
+  for (index, line) in text.split('\n').enumerate() {
+    if index > 0 {
+      output.push('\n');
+    }
+    let line = match output.is_empty() || output.ends_with('\n') {
+      true => line.trim_start(),
+      false => line,
+    };
+    output.push_str(&escape_roff(line));
+  }
+}
+
+// the backslash and hyphen, which roff would otherwise print as an escape and a typographic dash, and the
+// control characters at the start of a line
+pub fn escape_roff(text: &str) -> String {
+  let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+  let lines: Vec<String> = escaped
+    .split('\n')
+    .map(|line| match line.starts_with(['.', '\'']) {
+      true => format!("\\&{}", line),
+      false => line.to_string(),
+    })
+    .collect();
+  lines.join("\n")
+}
+
+// a quoted macro argument, `"` is written as \(dq
+fn argument(text: &str) -> String {
+  format!("\"{}\"", escape_roff(text).replace('"', "\\(dq"))
+}
+
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Link(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+  )
+}
+
+// the `section` of the frontmatter when it names a file e.g. 1, 3p or 8, None without one
+pub fn man_section(frontmatter: &BTreeMap<String, String>) -> Option<&str> {
+  let section = frontmatter.get("section")?.trim();
+  is_man_section(section).then_some(section)
+}
+
+// a digit and an optional suffix, so foo.png is never taken for a page of foo.md
+pub fn is_man_section(section: &str) -> bool {
+  section.starts_with(|c: char| c.is_ascii_digit()) && section.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{escape_roff, man_section, RoffCompiler};
+  use crate::parser::MdxParser;
+
+  #[test]
+  fn compiles_a_man_page() {
+    let source = "---\ntitle: scc\nsection: 1\ndate: 2024-05-01\n---\n# Name\n\nscc - compile **mdx** to *html*\n\n## Options\n\n- `--watch`\n- see [the site](https://scc.dev)\n\n```\n.hidden \\n\n```\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let expected = ".TH \"SCC\" \"1\" \"2024\\-05\\-01\"
+.SH \"NAME\"
+.PP
+scc \\- compile \\fBmdx\\fP to \\fIhtml\\fP
+.SS \"Options\"
+.IP \\(bu 2
+\\fB\\-\\-watch\\fP
+.IP \\(bu 2
+see
+.UR https://scc.dev
+the site
+.UE
+.PP
+.RS 4
+.nf
+\\&.hidden \\en
+.fi
+.RE
+";
+    assert_eq!(RoffCompiler::new().compile(&mut ast), expected);
+    assert_eq!(escape_roff("a\n'b"), "a\n\\&'b");
+  }
+
+  #[test]
+  fn reads_the_man_section() {
+    let section = |value: &str| {
+      let frontmatter = [("section".to_string(), value.to_string())].into();
+      man_section(&frontmatter).map(str::to_string)
+    };
+    assert_eq!(section(" 5 "), Some("5".to_string()));
+    assert_eq!(section("3p"), Some("3p".to_string()));
+    assert_eq!(section("../5"), None);
+    assert_eq!(section("png"), None);
+    assert_eq!(section(""), None);
+    assert_eq!(man_section(&Default::default()), None);
+  }
+}
@@ -0,0 +1,192 @@
+use crate::{ast, highlighter::Highlighter};
+
+use super::HtmlCompiler;
+
+// svelte component, top level paragraphs of esm e.g. `import Chart from "./Chart.svelte"` and the content of
+// <script> blocks are hoisted into one <script>, the markdown becomes the markup and components are kept as tags.
+// the attributes of the first <script> that has some are kept e.g. lang="ts", and the module scripts
+// (context="module", or `module` in svelte 5) are hoisted into their own <script> before it.
+// `{` starts an expression in svelte so the braces of text and code are written as &#123; and &#125;,
+// the props of components and html blocks are left as written.
+#[derive(Default)]
+pub struct SvelteCompiler {
+  highlighter: Option<Highlighter>,
+}
+
+impl SvelteCompiler {
+  pub fn new() -> Self {
+    Self { highlighter: None }
+  }
+
+  pub fn with_highlighter(mut self, highlighter: Option<Highlighter>) -> Self {
+    self.highlighter = highlighter;
+    self
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let mut ast = ast.clone();
+    let ast::Ast::Root(root) = &mut ast;
+    let (mut instance, mut module) = (Script::default(), Script::default());
+    root.children.retain(|node| match script_source(node) {
+      Some((attributes, source)) => {
+        let script = match is_module(&attributes) {
+          true => &mut module,
+          false => &mut instance,
+        };
+        if script.attributes.is_empty() {
+          script.attributes = attributes;
+        }
+        script.sources.push(source);
+        false
+      }
+      None => true,
+    });
+    let html = HtmlCompiler::new()
+      .keep_components()
+      .escape_braces()
+      .with_highlighter(self.highlighter.clone())
+      .compile(&mut ast);
+    format!("{}{}{}\n", module, instance, html)
+  }
+}
+
+#[derive(Default)]
+struct Script {
+  attributes: String, // as written after `<script`, with the leading space
+  sources: Vec<String>,
+}
+
+// `<script lang="ts">\n...\n</script>` and a blank line, nothing without sources
+impl std::fmt::Display for Script {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.sources.is_empty() {
+      return Ok(());
+    }
+    write!(
+      f,
+      "<script{}>\n{}\n</script>\n\n",
+      self.attributes,
+      self.sources.join("\n")
+    )
+  }
+}
+
+fn is_module(attributes: &str) -> bool {
+  attributes
+    .split_whitespace()
+    .any(|attribute| attribute == "module" || attribute.starts_with("context="))
+}
+
+// the attributes and javascript of an esm paragraph or a <script> block, esm has no attributes
+fn script_source(node: &ast::Node) -> Option<(String, String)> {
+  match node {
+    ast::Node::Paragraph(paragraph) => {
+      let mut source = String::new();
+      for child in paragraph.children.iter() {
+        match child {
+          ast::Node::Text(text) => source.push_str(&text.text),
+          ast::Node::SoftBreak => source.push('\n'),
+          _ => return None,
+        }
+      }
+      let source = source.trim();
+      is_esm(source).then(|| (String::new(), source.to_string()))
+    }
+    ast::Node::Html(html) => {
+      let html = html.trim();
+      let rest = html.strip_prefix("<script")?;
+      let (attributes, rest) = rest.split_once('>')?;
+      // `<scripts>` is not a script
+      if !attributes.is_empty() && !attributes.starts_with(char::is_whitespace) {
+        return None;
+      }
+      let source = rest.strip_suffix("</script>")?;
+      Some((attributes.trim_end().to_string(), source.trim().to_string()))
+    }
+    _ => None,
+  }
+}
+
+// `import x from "y"`, `import "y"` or an exported declaration, prose that starts with the same words e.g.
+// "export your data as CSV" stays markdown
+fn is_esm(source: &str) -> bool {
+  if let Some(rest) = source.strip_prefix("import ") {
+    let from = rest.split_once(" from ").map_or(rest, |(_, from)| from);
+    return is_specifier(from.trim_start());
+  }
+  let Some(rest) = source.strip_prefix("export ") else {
+    return false;
+  };
+  match rest.split_whitespace().next().unwrap_or_default() {
+    "const" | "let" | "var" => rest.contains('='),
+    "function" | "async" => rest.contains('('),
+    "class" | "default" => true,
+    keyword => keyword.starts_with(['{', '*']),
+  }
+}
+
+// a quoted module that ends the statement e.g. `"./Chart.svelte";`
+fn is_specifier(text: &str) -> bool {
+  let Some(quote) = text.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+    return false;
+  };
+  let rest = &text[1..];
+  rest.find(quote).is_some_and(|end| {
+    let after = rest[end + 1..].lines().next().unwrap_or_default();
+    after.trim().trim_end_matches(';').is_empty()
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::SvelteCompiler;
+  use crate::{highlighter::Highlighter, parser::MdxParser};
+
+  #[test]
+  fn hoists_the_script_and_escapes_braces() {
+    let source = "import Chart from \"./Chart.svelte\"\n\n<script>\nlet count = 0;\n</script>\n\n# Sets {a}\n\nUse `{b}` here.\n\n<Chart data={[count]} />\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let expected = "<script>\nimport Chart from \"./Chart.svelte\"\nlet count = 0;\n</script>\n\n<h1>Sets &#123;a&#125;</h1>\n<p>Use <code>&#123;b&#125;</code> here.</p>\n<Chart data={[count]} />\n";
+    assert_eq!(SvelteCompiler::new().compile(&mut ast), expected);
+    // titles and highlighted code are escaped too, the styles of the highlighter are left alone
+    let mut ast = MdxParser::parse("[a](/b \"{c}\")\n\n```js\nlet d = {};\n```\n").unwrap();
+    let html = SvelteCompiler::new()
+      .with_highlighter(Some(Highlighter::default()))
+      .compile(&mut ast);
+    assert!(
+      html.starts_with("<p><a href=\"/b\" title=\"&#123;c&#125;\">a</a></p>"),
+      "{}",
+      html
+    );
+    assert!(html.contains("= &#123;&#125;;</span></code></pre>"), "{}", html);
+    assert!(html.contains("<span style=\"color:#c678dd\">let</span>"), "{}", html);
+  }
+
+  #[test]
+  fn hoists_only_esm() {
+    let source = "export your data as CSV from the menu.\n\nimport the rows from 'legacy' files.\n\nexport const rows = [];\n\nexport function total(a) {\n  return a;\n}\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let expected = "<script>\nexport const rows = [];\nexport function total(a) {\n  return a;\n}\n</script>\n\n<p>export your data as CSV from the menu.</p>\n<p>import the rows from &#39;legacy&#39; files.</p>\n";
+    assert_eq!(SvelteCompiler::new().compile(&mut ast), expected);
+  }
+
+  #[test]
+  fn keeps_the_attributes_of_the_scripts() {
+    let source = "<script lang=\"ts\">\nlet count: number = 0;\n</script>\n\nimport Chart from \"./Chart.svelte\"\n\n<script context=\"module\">\nexport const prerender = true;\n</script>\n\n<script>\nlet more = 1;\n</script>\n\nText\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let expected = "<script context=\"module\">\nexport const prerender = true;\n</script>\n\n<script lang=\"ts\">\nlet count: number = 0;\nimport Chart from \"./Chart.svelte\"\nlet more = 1;\n</script>\n\n<p>Text</p>\n";
+    assert_eq!(SvelteCompiler::new().compile(&mut ast), expected);
+    // svelte 5 module scripts
+    let mut ast = MdxParser::parse("<script module lang=\"ts\">\nexport const a = 1;\n</script>\n\nText\n").unwrap();
+    let expected = "<script module lang=\"ts\">\nexport const a = 1;\n</script>\n\n<p>Text</p>\n";
+    assert_eq!(SvelteCompiler::new().compile(&mut ast), expected);
+  }
+
+  #[test]
+  fn escapes_markup_in_text() {
+    let source = "# a < b & {c}\n\nText a < b && c > d, `<div>{x}</div>` and [x < y](/a?b=1&c=2).\n\n| a < b |\n|---|\n| 1 & 2 |\n\n```\n<p>&amp;</p>\n```\n";
+    let mut ast = MdxParser::parse(source).unwrap();
+    let expected = "<h1>a &lt; b &amp; &#123;c&#125;</h1>\n<p>Text a &lt; b &amp;&amp; c &gt; d, <code>&lt;div&gt;&#123;x&#125;&lt;/div&gt;</code> and <a href=\"/a?b=1&amp;c=2\">x &lt; y</a>.</p>\n<table>\n  <thead>\n    <tr><th>a &lt; b</th></tr>\n  </thead>\n  <tbody>\n    <tr><td>1 &amp; 2</td></tr>\n  </tbody>\n</table>\n<pre><code><span class=\"line\">&lt;p&gt;&amp;amp;&lt;/p&gt;</span></code></pre>\n";
+    assert_eq!(SvelteCompiler::new().compile(&mut ast), expected);
+  }
+}
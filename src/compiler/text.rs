@@ -0,0 +1,237 @@
+use serde::Serialize;
+
+use crate::{ast, parser::MdxParser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextOptions {
+  pub code_blocks: bool,       // keep the code of code blocks
+  pub components: bool,        // keep the text inside jsx/vue components
+  pub words_per_minute: usize, // for the reading time
+}
+
+impl Default for TextOptions {
+  fn default() -> Self {
+    Self { code_blocks: true, components: true, words_per_minute: 200 }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TextStats {
+  pub words: usize,        // of the text, so without the code blocks or components that are dropped
+  pub characters: usize,   // of the text, whitespace included
+  pub reading_time: usize, // minutes rounded up, 0 without words
+  pub headings: usize,     // the counts are of the whole document
+  pub links: usize,
+  pub images: usize,
+  pub code_blocks: usize,
+}
+
+// the text of the document without markup, blocks are separated by a blank line and list items and table
+// rows by a newline. images, html comments, tags and the frontmatter are dropped.
+#[derive(Default)]
+pub struct TextCompiler {
+  options: TextOptions,
+}
+
+impl TextCompiler {
+  pub fn new() -> Self {
+    Self::with_options(TextOptions::default())
+  }
+
+  pub fn with_options(options: TextOptions) -> Self {
+    Self { options }
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    self.compile_with_stats(ast).0
+  }
+
+  pub fn compile_with_stats(&mut self, ast: &ast::Ast) -> (String, TextStats) {
+    let ast::Ast::Root(root) = ast;
+    let text = self.compile_blocks(&root.children, "\n\n");
+    let words = text.split_whitespace().count();
+    let mut stats = TextStats {
+      words,
+      characters: text.chars().count(),
+      reading_time: words.div_ceil(self.options.words_per_minute.max(1)),
+      ..TextStats::default()
+    };
+    count_nodes(&root.children, &mut stats);
+    (text, stats)
+  }
+
+  fn compile_blocks(&self, nodes: &[ast::Node], separator: &str) -> String {
+    let mut blocks = Vec::new();
+    let mut inline = Vec::new();
+    for node in nodes {
+      if is_inline(node) {
+        inline.push(node.clone());
+        continue;
+      }
+      blocks.push(self.compile_paragraph(&std::mem::take(&mut inline)));
+      blocks.push(self.compile_block(node));
+    }
+    blocks.push(self.compile_paragraph(&inline));
+    blocks.retain(|block| !block.is_empty());
+    blocks.join(separator)
+  }
+
+  fn compile_block(&self, node: &ast::Node) -> String {
+    match node {
+      ast::Node::Heading(heading) => collapse(&strip_tags(&self.compile_paragraph(&inline(&heading.text)))),
+      ast::Node::Paragraph(paragraph) => self.compile_paragraph(&paragraph.children),
+      ast::Node::CodeBlock(code_block) if self.options.code_blocks => code_block.code.trim_end().to_string(),
+      ast::Node::Html(html) => collapse(&strip_tags(html)),
+      ast::Node::List(list) => self.compile_blocks(&list.children, "\n"),
+      ast::Node::ListItem(item) => self.compile_blocks(&item.children, "\n"),
+      ast::Node::Blockquote(blockquote) => self.compile_blocks(&blockquote.children, "\n\n"),
+      ast::Node::Table(table) => {
+        let rows = std::iter::once(&table.header).chain(table.rows.iter());
+        let rows: Vec<String> = rows
+          .map(|row| {
+            let cells: Vec<String> = row.iter().map(|cell| self.compile_paragraph(&inline(cell))).collect();
+            collapse(&cells.join(" "))
+          })
+          .collect();
+        rows.join("\n")
+      }
+      ast::Node::ReactComponent(_) | ast::Node::VueComponent(_) if self.options.components => {
+        self.compile_blocks(node.children(), "\n\n")
+      }
+      _ => String::new(),
+    }
+  }
+
+  // whitespace is collapsed, hard breaks are kept as newlines
+  fn compile_paragraph(&self, nodes: &[ast::Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+      self.compile_inline(node, &mut text);
+    }
+    let lines: Vec<String> = text.split('\n').map(collapse).filter(|line| !line.is_empty()).collect();
+    lines.join("\n")
+  }
+
+  fn compile_inline(&self, node: &ast::Node, text: &mut String) {
+    match node {
+      ast::Node::Text(node) => text.push_str(&node.text.replace('\n', " ")),
+      ast::Node::InlineCode(code) => text.push_str(&code.code),
+      ast::Node::Link(link) => text.push_str(&link.alt),
+      ast::Node::Html(html) => text.push_str(&strip_tags(html).replace('\n', " ")),
+      ast::Node::SoftBreak => text.push(' '),
+      ast::Node::HardBreak => text.push('\n'),
+      ast::Node::Bold(_) | ast::Node::Italic(_) => {
+        for child in node.children() {
+          self.compile_inline(child, text);
+        }
+      }
+      ast::Node::ReactComponent(_) | ast::Node::VueComponent(_) if self.options.components => {
+        text.push(' ');
+        text.push_str(&self.compile_blocks(node.children(), " "));
+        text.push(' ');
+      }
+      _ => {}
+    }
+  }
+}
+
+fn count_nodes(nodes: &[ast::Node], stats: &mut TextStats) {
+  for node in nodes {
+    match node {
+      ast::Node::Heading(heading) => {
+        stats.headings += 1;
+        count_nodes(&inline(&heading.text), stats);
+      }
+      ast::Node::Table(table) => {
+        for cell in table.header.iter().chain(table.rows.iter().flatten()) {
+          count_nodes(&inline(cell), stats);
+        }
+      }
+      ast::Node::Link(_) => stats.links += 1,
+      ast::Node::Image(_) => stats.images += 1,
+      ast::Node::CodeBlock(_) => stats.code_blocks += 1,
+      _ => {}
+    }
+    count_nodes(node.children(), stats);
+  }
+}
+
+// headings and cells keep their inline markdown in the ast, one that doesn't parse is text
+fn inline(source: &str) -> Vec<ast::Node> {
+  MdxParser::parse_inline(source.trim())
+    .unwrap_or_else(|_| vec![ast::Node::Text(ast::Text { text: source.to_string() })])
+}
+
+fn is_inline(node: &ast::Node) -> bool {
+  matches!(
+    node,
+    ast::Node::Text(_)
+      | ast::Node::InlineCode(_)
+      | ast::Node::Link(_)
+      | ast::Node::Image(_)
+      | ast::Node::SoftBreak
+      | ast::Node::HardBreak
+      | ast::Node::Bold(_)
+      | ast::Node::Italic(_)
+  )
+}
+
+fn collapse(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// `<b>a</b> <!-- b -->` -> `a `
+fn strip_tags(html: &str) -> String {
+  let mut text = String::with_capacity(html.len());
+  let mut rest = html;
+  while let Some(start) = rest.find('<') {
+    text.push_str(&rest[..start]);
+    let end = if rest[start..].starts_with("<!--") { "-->" } else { ">" };
+    rest = match rest[start..].find(end) {
+      Some(index) => &rest[start + index + end.len()..],
+      None => "",
+    };
+  }
+  text.push_str(rest);
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{TextCompiler, TextOptions, TextStats};
+  use crate::parser::MdxParser;
+
+  #[test]
+  fn strips_markup_and_counts() {
+    let source = "---\ntitle: A\n---\n# Hello <!-- no-toc -->\n\nSome **bold** and [a link](x.md)\nnext `line`.\n\n![logo](logo.png)\n\n- one\n- two\n\n```js\nlet a = 1;\n```\n\n<Note>\n  inside *here*\n</Note>\n";
+    let ast = MdxParser::parse(source).unwrap();
+    let (text, stats) = TextCompiler::new().compile_with_stats(&ast);
+    assert_eq!(
+      text,
+      "Hello\n\nSome bold and a link next line.\n\none\ntwo\n\nlet a = 1;\n\ninside here"
+    );
+    let expected = TextStats {
+      words: 16,
+      characters: text.chars().count(),
+      reading_time: 1,
+      headings: 1,
+      links: 1,
+      images: 1,
+      code_blocks: 1,
+    };
+    assert_eq!(stats, expected);
+    let options = TextOptions { code_blocks: false, components: false, ..TextOptions::default() };
+    let (text, stats) = TextCompiler::with_options(options).compile_with_stats(&ast);
+    assert_eq!(text, "Hello\n\nSome bold and a link next line.\n\none\ntwo");
+    assert_eq!((stats.words, stats.code_blocks), (10, 1));
+  }
+
+  #[test]
+  fn strips_the_markup_of_headings_and_cells() {
+    let source = "# Hello **world** [docs](a.md)\n\n| **x** | [a](b) ![i](i.png) |\n|---|---|\n| `y` | _z_ |\n";
+    let ast = MdxParser::parse(source).unwrap();
+    let (text, stats) = TextCompiler::new().compile_with_stats(&ast);
+    assert_eq!(text, "Hello world docs\n\nx a\ny z");
+    assert_eq!((stats.headings, stats.links, stats.images), (1, 2, 1));
+  }
+}
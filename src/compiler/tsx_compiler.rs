@@ -1,7 +1,264 @@
-pub struct TsxCompiler {}
+use crate::{ast, parser::MdxParser, transformer::toc_to_json};
+
+use super::html::escape_html;
+
+pub struct TsxCompiler {
+  output: String,
+}
+
+impl Default for TsxCompiler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
 
 impl TsxCompiler {
   pub fn new() -> Self {
-    Self {}
+    Self { output: String::new() }
+  }
+
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    self.push("/* @jsxRuntime automatic */\n");
+    self.push("/* @jsxImportSource react */\n\n");
+    match ast {
+      ast::Ast::Root(root) if !root.toc.is_empty() => {
+        self.push(&format!("export const toc = {};\n\n", toc_to_json(&root.toc)));
+      }
+      _ => {}
+    }
+    self.push("export default function MDXContent() {\n");
+    self.push("  return (\n");
+    self.push("    <>\n");
+    match ast {
+      ast::Ast::Root(root) => self.compile_root(root),
+    }
+    self.push("    </>\n");
+    self.push("  );\n");
+    self.push("}\n");
+    self.output.clone()
+  }
+
+  fn compile_root(&mut self, root: &mut ast::Root) {
+    for node in &mut *root.children {
+      self.push("      ");
+      self.compile_node(node);
+      self.new_line();
+    }
+  }
+
+  pub fn compile_node(&mut self, node: &mut ast::Node) {
+    match node {
+      ast::Node::Heading(heading) => self.compile_heading(heading),
+      ast::Node::Text(text) => self.push(&escape_jsx_text(&text.text)),
+      ast::Node::InlineCode(inline_code) => self.push(&format!("<code>{}</code>", to_jsx_string(&inline_code.code))),
+      ast::Node::CodeBlock(code_block) => self.compile_code_block(code_block),
+      ast::Node::Html(html) => self.push(&format!(
+        "<div dangerouslySetInnerHTML={{{{ __html: {} }}}} />",
+        serde_json::Value::String(html.clone())
+      )),
+      ast::Node::Paragraph(paragraph) => self.compile_children("p", &mut paragraph.children),
+      ast::Node::List(list) => self.compile_list(list),
+      ast::Node::ListItem(item) => self.compile_children("li", &mut item.children),
+      ast::Node::Blockquote(blockquote) => self.compile_children("blockquote", &mut blockquote.children),
+      ast::Node::Table(table) => self.compile_table(table),
+      ast::Node::ThematicBreak => self.push("<hr />"),
+      ast::Node::SoftBreak => self.push("{\"\\n\"}"),
+      ast::Node::HardBreak => self.push("<br />"),
+      ast::Node::Bold(bold) => self.compile_children("b", &mut bold.children),
+      ast::Node::Italic(italic) => self.compile_children("i", &mut italic.children),
+      ast::Node::Link(link) => self.compile_link(link),
+      ast::Node::Image(image) => self.compile_image(image),
+      ast::Node::ReactComponent(component) => self.compile_component(component),
+      ast::Node::VueComponent(_) => {}
+    }
+  }
+
+  fn compile_children(&mut self, tag: &str, children: &mut [ast::Node]) {
+    self.push(&format!("<{}>", tag));
+    for node in children {
+      self.compile_node(node);
+    }
+    self.push(&format!("</{}>", tag));
+  }
+
+  fn compile_heading(&mut self, heading: &mut ast::Heading) {
+    let text = self.compile_source(heading.text.trim());
+    let id = match &heading.id {
+      Some(id) => id,
+      None => return self.push(&format!("<h{}>{}</h{}>", heading.level, text, heading.level)),
+    };
+    let anchor = |text: &str| format!("<a className=\"anchor\" href=\"#{}\">{}</a>", id, text);
+    let content = match heading.anchor {
+      Some(ast::AnchorPosition::Before) => format!("{}{}", anchor("#"), text),
+      Some(ast::AnchorPosition::After) => format!("{}{}", text, anchor("#")),
+      Some(ast::AnchorPosition::Wrap) => anchor(&text),
+      None => text,
+    };
+    self.push(&format!(
+      "<h{} id=\"{}\">{}</h{}>",
+      heading.level, id, content, heading.level
+    ));
+  }
+
+  fn compile_code_block(&mut self, code_block: &mut ast::CodeBlock) {
+    if code_block.classes.is_empty() {
+      self.push("<pre>");
+    } else {
+      self.push(&format!("<pre className=\"{}\">", code_block.classes.join(" ")));
+    }
+    if code_block.language.is_empty() {
+      self.push("<code>");
+    } else {
+      self.push(&format!(
+        "<code className=\"language-{}\">",
+        escape_html(&code_block.language)
+      ));
+    }
+    let lines: Vec<String> = code_block
+      .code
+      .split('\n')
+      .enumerate()
+      .map(|(index, line)| {
+        let code_line = code_block.lines.get(index).cloned().unwrap_or_default();
+        let mut classes = vec!["line".to_string()];
+        classes.extend(code_line.classes.iter().cloned());
+        let mut content = String::new();
+        for (segment, highlighted) in code_line.segments(line) {
+          if highlighted {
+            content.push_str(&format!(
+              "<span className=\"highlighted-word\">{}</span>",
+              to_jsx_string(segment)
+            ));
+          } else {
+            content.push_str(&to_jsx_string(segment));
+          }
+        }
+        format!("<span className=\"{}\">{}</span>", classes.join(" "), content)
+      })
+      .collect();
+    self.push(&lines.join("{\"\\n\"}"));
+    self.push("</code></pre>");
+  }
+
+  fn compile_list(&mut self, list: &mut ast::List) {
+    let tag = if list.ordered { "ol" } else { "ul" };
+    match list.start {
+      Some(start) if list.ordered && start != 1 => self.push(&format!("<{} start={{{}}}>", tag, start)),
+      _ => self.push(&format!("<{}>", tag)),
+    }
+    for item in &mut *list.children {
+      match item {
+        ast::Node::ListItem(_) => self.compile_node(item),
+        _ => {
+          self.push("<li>");
+          self.compile_node(item);
+          self.push("</li>");
+        }
+      }
+    }
+    self.push(&format!("</{}>", tag));
   }
+
+  fn compile_table(&mut self, table: &mut ast::Table) {
+    let align = |index: usize| match table.align.get(index).copied().flatten() {
+      Some(ast::Align::Left) => " style={{ textAlign: \"left\" }}",
+      Some(ast::Align::Center) => " style={{ textAlign: \"center\" }}",
+      Some(ast::Align::Right) => " style={{ textAlign: \"right\" }}",
+      None => "",
+    };
+    let mut header = String::new();
+    for (index, cell) in table.header.iter().enumerate() {
+      header.push_str(&format!("<th{}>{}</th>", align(index), self.compile_source(cell)));
+    }
+    self.push(&format!("<table><thead><tr>{}</tr></thead><tbody>", header));
+    for row in &table.rows {
+      let mut cells = String::new();
+      for (index, cell) in row.iter().enumerate() {
+        cells.push_str(&format!("<td{}>{}</td>", align(index), self.compile_source(cell)));
+      }
+      self.push(&format!("<tr>{}</tr>", cells));
+    }
+    self.push("</tbody></table>");
+  }
+
+  // headings, cells and the text of links are kept as written in the ast, their inline markdown is parsed here
+  // and a source that doesn't parse is written as text
+  fn compile_source(&mut self, source: &str) -> String {
+    let text = || vec![ast::Node::Text(ast::Text { text: source.to_string() })];
+    let block = std::mem::take(&mut self.output);
+    for node in &mut MdxParser::parse_inline(source).unwrap_or_else(|_| text()) {
+      self.compile_node(node);
+    }
+    std::mem::replace(&mut self.output, block)
+  }
+
+  fn compile_link(&mut self, link: &mut ast::Link) {
+    let title = link
+      .title
+      .as_ref()
+      .map(|title| format!(" title=\"{}\"", escape_html(title)))
+      .unwrap_or_default();
+    let alt = self.compile_source(&link.alt);
+    self.push(&format!("<a href=\"{}\"{}>{}</a>", escape_html(&link.url), title, alt));
+  }
+
+  fn compile_image(&mut self, image: &mut ast::Link) {
+    let title = image
+      .title
+      .as_ref()
+      .map(|title| format!(" title=\"{}\"", escape_html(title)))
+      .unwrap_or_default();
+    self.push(&format!(
+      "<img src=\"{}\" alt=\"{}\"{} />",
+      escape_html(&image.url),
+      escape_html(&image.alt),
+      title
+    ));
+  }
+
+  fn compile_component(&mut self, component: &mut ast::ReactComponent) {
+    let mut open = component.name.clone();
+    for prop in &component.props {
+      open.push(' ');
+      open.push_str(prop);
+    }
+    if component.children.is_empty() {
+      self.push(&format!("<{} />", open));
+      return;
+    }
+    self.push(&format!("<{}>", open));
+    for node in &mut *component.children {
+      self.compile_node(node);
+    }
+    self.push(&format!("</{}>", component.name));
+  }
+
+  pub fn new_line(&mut self) {
+    self.output.push('\n');
+  }
+
+  pub fn push(&mut self, text: &str) {
+    self.output.push_str(text);
+  }
+}
+
+// `{`, `}`, `<` and `>` have meaning in jsx text
+pub fn escape_jsx_text(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    match character {
+      '{' => escaped.push_str("{\"{\"}"),
+      '}' => escaped.push_str("{\"}\"}"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '&' => escaped.push_str("&amp;"),
+      _ => escaped.push(character),
+    }
+  }
+  escaped
+}
+
+// code is emitted as a js string expression so whitespace and braces survive
+pub fn to_jsx_string(text: &str) -> String {
+  format!("{{{}}}", serde_json::Value::String(text.to_string()))
 }
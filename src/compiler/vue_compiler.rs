@@ -1,7 +1,30 @@
-pub struct VueCompiler {}
+use crate::{ast, highlighter::Highlighter};
+
+use super::HtmlCompiler;
+
+// single file component, the markdown becomes the <template> and components are kept as tags
+// so `{{ count }}` and `<Counter />` keep working inside the template.
+#[derive(Default)]
+pub struct VueCompiler {
+  highlighter: Option<Highlighter>,
+}
 
 impl VueCompiler {
   pub fn new() -> Self {
-    Self {}
+    Self { highlighter: None }
+  }
+
+  pub fn with_highlighter(mut self, highlighter: Option<Highlighter>) -> Self {
+    self.highlighter = highlighter;
+    self
+  }
+
+  // the html is not indented, lines inside <pre> are whitespace sensitive
+  pub fn compile(&mut self, ast: &mut ast::Ast) -> String {
+    let html = HtmlCompiler::new()
+      .keep_components()
+      .with_highlighter(self.highlighter.clone())
+      .compile(ast);
+    format!("<template>\n{}\n</template>\n", html)
   }
 }
@@ -0,0 +1,510 @@
+// ==================
+// scc.toml, found in the current directory or the closest parent e.g.
+//
+// target = "html"
+// src = "content"
+// out = "dist"
+// html-mode = "minified"
+// transformers = ["heading-slug", "table-of-contents", "notation-diff"]
+//
+// [extensions]
+// gfm = true
+// mdx = true
+// frontmatter = true
+//
+// [transformer.table-of-contents]
+// min-depth = 2
+// max-depth = 3
+//
+// [highlighter]
+// theme = "themes/nord.json"
+//
+// [layout]
+// template = "layouts/page.html"
+// stylesheets = ["/style.css"]
+//
+// [format]
+// bullet = "*"
+// line-width = 80
+//
+// [lint]
+// bare-url = "off"
+// code-language = "error"
+//
+// [search]
+// enabled = true
+// output = "search.json"
+// inverted-index = false
+//
+// relative paths are resolved from the directory of scc.toml, cli flags win over the file.
+//
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+  ast::AnchorPosition,
+  compiler::{HtmlMode, MarkdownOptions, Target},
+  highlighter::Highlighter,
+  lint::{self, Level},
+  parser::ParserOptions,
+  search::SearchOptions,
+  transformer::{
+    Transformer, TransformerHeadingSlug, TransformerNotationDiff, TransformerNotationErrorLevel,
+    TransformerNotationFocus, TransformerNotationHighlight, TransformerNotationWordHighlight, TransformerRewriteLinks,
+    TransformerTableOfContents,
+  },
+  Options,
+};
+
+pub const CONFIG_FILE: &str = "scc.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+  pub target: Option<String>,
+  pub src: Option<PathBuf>,
+  pub out: Option<PathBuf>,
+  pub html_mode: Option<String>,
+  pub transformers: Option<Vec<String>>, // in the order they run, the default list when missing
+  pub transformer: BTreeMap<String, toml::Table>, // options of each transformer
+  pub extensions: Extensions,
+  pub highlighter: HighlighterConfig,
+  pub layout: LayoutConfig,
+  pub format: FormatConfig,           // the style of `scc fmt` and the markdown target
+  pub lint: BTreeMap<String, String>, // rule -> off, warn or error
+  pub search: SearchConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Extensions {
+  pub gfm: bool,
+  pub mdx: bool,
+  pub frontmatter: bool,
+}
+
+impl Default for Extensions {
+  fn default() -> Self {
+    let options = ParserOptions::default();
+    Self { gfm: options.gfm, mdx: options.mdx, frontmatter: options.frontmatter }
+  }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlighterConfig {
+  pub theme: Option<PathBuf>, // vscode or zed theme
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+  pub template: Option<PathBuf>,
+  pub standalone: Option<bool>,
+  pub layouts: Option<PathBuf>,
+  pub stylesheets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatConfig {
+  pub emphasis: Option<String>,
+  pub bullet: Option<String>,
+  pub fence: Option<String>,
+  pub line_width: Option<usize>,
+  pub table_padding: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SearchConfig {
+  pub enabled: bool,           // write a search index with `scc build` and `scc serve`
+  pub output: Option<PathBuf>, // relative to `out`
+  pub inverted_index: Option<bool>,
+}
+
+// the names of `transformer::default_transformers`
+const DEFAULT_TRANSFORMERS: [&str; 7] = [
+  "heading-slug",
+  "table-of-contents",
+  "notation-diff",
+  "notation-highlight",
+  "notation-focus",
+  "notation-error-level",
+  "notation-word-highlight",
+];
+
+impl Config {
+  // the closest scc.toml from `dir` upwards, None when there is none
+  pub fn discover(dir: &Path) -> Result<Option<Self>, String> {
+    let dir = fs::canonicalize(dir).unwrap_or(dir.to_path_buf());
+    match dir
+      .ancestors()
+      .map(|dir| dir.join(CONFIG_FILE))
+      .find(|path| path.is_file())
+    {
+      Some(path) => Self::load(&path).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let mut config: Config = toml::from_str(&source).map_err(|err| format!("{}: {}", path.display(), err))?;
+    config.resolve_paths(path.parent().unwrap_or(Path::new(".")));
+    config
+      .validate()
+      .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(config)
+  }
+
+  fn resolve_paths(&mut self, dir: &Path) {
+    let paths = [
+      &mut self.src,
+      &mut self.out,
+      &mut self.highlighter.theme,
+      &mut self.layout.template,
+      &mut self.layout.layouts,
+    ];
+    for path in paths.into_iter().flatten() {
+      *path = dir.join(&*path);
+    }
+  }
+
+  // reports mistakes once when loading instead of on every document
+  fn validate(&self) -> Result<(), String> {
+    self.target()?;
+    self.html_mode()?;
+    self.transformers()?;
+    self.markdown_options()?;
+    self.lint_levels()?;
+    for name in self.transformer.keys() {
+      if !self.transformer_names().contains(&name.as_str()) {
+        return Err(format!("options for `{}`, which is not in `transformers`", name));
+      }
+    }
+    self.highlighter()?;
+    Ok(())
+  }
+
+  pub fn target(&self) -> Result<Option<Target>, String> {
+    match &self.target {
+      Some(name) => Target::from_name(name).map(Some).ok_or(format!(
+        "unknown target `{}`, expected html, jsx, tsx, vue, svelte, markdown, text, latex or roff",
+        name
+      )),
+      None => Ok(None),
+    }
+  }
+
+  pub fn html_mode(&self) -> Result<Option<HtmlMode>, String> {
+    match &self.html_mode {
+      Some(name) => HtmlMode::from_name(name)
+        .map(Some)
+        .ok_or(format!("unknown html-mode `{}`, expected pretty or minified", name)),
+      None => Ok(None),
+    }
+  }
+
+  // None without a theme, code blocks are not colored then
+  pub fn highlighter(&self) -> Result<Option<Highlighter>, String> {
+    self.highlighter.theme.as_deref().map(Highlighter::load).transpose()
+  }
+
+  pub fn parser_options(&self) -> ParserOptions {
+    ParserOptions { gfm: self.extensions.gfm, mdx: self.extensions.mdx, frontmatter: self.extensions.frontmatter }
+  }
+
+  pub fn markdown_options(&self) -> Result<MarkdownOptions, String> {
+    let default = MarkdownOptions::default();
+    let format = &self.format;
+    Ok(MarkdownOptions {
+      emphasis: marker_option(&format.emphasis, "emphasis", &["*", "_"])?.unwrap_or(default.emphasis),
+      bullet: marker_option(&format.bullet, "bullet", &["-", "*", "+"])?.unwrap_or(default.bullet),
+      fence: marker_option(&format.fence, "fence", &["`", "~"])?.unwrap_or(default.fence),
+      line_width: format.line_width.or(default.line_width),
+      table_padding: format.table_padding.unwrap_or(default.table_padding),
+    })
+  }
+
+  pub fn lint_levels(&self) -> Result<BTreeMap<&'static str, Level>, String> {
+    lint::levels(&self.lint)
+  }
+
+  // None unless [search] is enabled, `force` enables it e.g. for --search-index
+  pub fn search_options(&self, force: bool) -> Option<SearchOptions> {
+    if !self.search.enabled && !force {
+      return None;
+    }
+    let default = SearchOptions::default();
+    Some(SearchOptions {
+      output: self.search.output.clone().unwrap_or(default.output),
+      inverted_index: self.search.inverted_index.unwrap_or(default.inverted_index),
+    })
+  }
+
+  fn transformer_names(&self) -> Vec<&str> {
+    match &self.transformers {
+      Some(names) => names.iter().map(String::as_str).collect(),
+      None => DEFAULT_TRANSFORMERS.to_vec(),
+    }
+  }
+
+  pub fn transformers(&self) -> Result<Vec<Box<dyn Transformer>>, String> {
+    let empty = toml::Table::new();
+    let names = self.transformer_names();
+    let options = names.iter().map(|name| self.transformer.get(*name).unwrap_or(&empty));
+    names
+      .iter()
+      .zip(options)
+      .map(|(name, options)| create_transformer(name, options))
+      .collect()
+  }
+
+  // compile options for `target` with the configured parser extensions and transformers
+  pub fn options(&self, target: Target) -> Result<Options, String> {
+    let mut options = Options::new(target);
+    options.parser = self.parser_options();
+    options.transformers = self.transformers()?;
+    options.html_mode = self.html_mode()?.unwrap_or_default();
+    options.markdown = self.markdown_options()?;
+    options.highlighter = self.highlighter()?;
+    Ok(options)
+  }
+}
+
+fn create_transformer(name: &str, options: &toml::Table) -> Result<Box<dyn Transformer>, String> {
+  let known: &[&str] = match name {
+    "heading-slug" => &["anchor"],
+    "table-of-contents" => &["min-depth", "max-depth", "exclude-marker"],
+    "notation-diff" => &["old", "new"],
+    "rewrite-links" => &["extension"],
+    "notation-highlight" | "notation-focus" | "notation-error-level" | "notation-word-highlight" => &[],
+    _ => return Err(format!("unknown transformer `{}`", name)),
+  };
+  if let Some(option) = options.keys().find(|option| !known.contains(&option.as_str())) {
+    return Err(format!("unknown option `{}` for transformer `{}`", option, name));
+  }
+  let transformer: Box<dyn Transformer> = match name {
+    "heading-slug" => {
+      let mut transformer = TransformerHeadingSlug::new();
+      if let Some(anchor) = string_option(options, "anchor")? {
+        transformer = transformer.with_anchor(match anchor {
+          "before" => AnchorPosition::Before,
+          "after" => AnchorPosition::After,
+          "wrap" => AnchorPosition::Wrap,
+          _ => return Err(format!("unknown anchor `{}`, expected before, after or wrap", anchor)),
+        });
+      }
+      Box::new(transformer)
+    }
+    "table-of-contents" => {
+      let mut transformer = TransformerTableOfContents::new();
+      let min_depth = integer_option(options, "min-depth")?.unwrap_or(1);
+      let max_depth = integer_option(options, "max-depth")?.unwrap_or(6);
+      if min_depth > max_depth {
+        return Err("min-depth is greater than max-depth".to_string());
+      }
+      transformer = transformer.with_depth(min_depth, max_depth);
+      if let Some(marker) = string_option(options, "exclude-marker")? {
+        transformer = transformer.with_exclude_marker(marker);
+      }
+      Box::new(transformer)
+    }
+    "notation-diff" => {
+      let old = string_option(options, "old")?.unwrap_or("--");
+      let new = string_option(options, "new")?.unwrap_or("++");
+      Box::new(TransformerNotationDiff::new(old, new))
+    }
+    "rewrite-links" => Box::new(TransformerRewriteLinks::new(
+      string_option(options, "extension")?.unwrap_or("html"),
+    )),
+    "notation-highlight" => Box::new(TransformerNotationHighlight::new()),
+    "notation-focus" => Box::new(TransformerNotationFocus::new()),
+    "notation-error-level" => Box::new(TransformerNotationErrorLevel::new()),
+    _ => Box::new(TransformerNotationWordHighlight::new()),
+  };
+  Ok(transformer)
+}
+
+fn string_option<'t>(options: &'t toml::Table, name: &str) -> Result<Option<&'t str>, String> {
+  match options.get(name) {
+    Some(value) => value.as_str().map(Some).ok_or(format!("`{}` must be a string", name)),
+    None => Ok(None),
+  }
+}
+
+// one of `markers` e.g. bullet = "*"
+fn marker_option(value: &Option<String>, name: &str, markers: &[&str]) -> Result<Option<char>, String> {
+  match value {
+    Some(value) if markers.contains(&value.as_str()) => Ok(value.chars().next()),
+    Some(value) => Err(format!(
+      "unknown {} `{}`, expected {}",
+      name,
+      value,
+      markers.join(" or ")
+    )),
+    None => Ok(None),
+  }
+}
+
+fn integer_option(options: &toml::Table, name: &str) -> Result<Option<usize>, String> {
+  match options.get(name) {
+    Some(value) => value
+      .as_integer()
+      .and_then(|value| usize::try_from(value).ok())
+      .map(Some)
+      .ok_or(format!("`{}` must be a positive integer", name)),
+    None => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(source: &str) -> Result<Config, String> {
+    let config: Config = toml::from_str(source).map_err(|err| err.message().to_string())?;
+    config.validate()?;
+    Ok(config)
+  }
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scc-config-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn parses_every_section() {
+    let config = parse(
+      r#"
+target = "vue"
+html-mode = "minified"
+transformers = ["heading-slug", "table-of-contents"]
+
+[extensions]
+gfm = false
+
+[transformer.table-of-contents]
+min-depth = 2
+max-depth = 3
+
+[layout]
+stylesheets = ["/style.css"]
+
+[format]
+bullet = "*"
+line-width = 80
+
+[lint]
+bare-url = "off"
+
+[search]
+enabled = true
+"#,
+    )
+    .unwrap();
+    assert!(matches!(config.target(), Ok(Some(Target::Vue))));
+    assert!(matches!(config.html_mode(), Ok(Some(HtmlMode::Minified))));
+    assert_eq!(
+      config
+        .transformers()
+        .unwrap()
+        .iter()
+        .map(|transformer| transformer.name())
+        .collect::<Vec<_>>(),
+      ["TransformerHeadingSlug", "TransformerTableOfContents"]
+    );
+    assert!(!config.parser_options().gfm);
+    assert!(config.parser_options().mdx);
+    assert_eq!(config.layout.stylesheets, ["/style.css"]);
+    let markdown = config.markdown_options().unwrap();
+    assert_eq!((markdown.bullet, markdown.line_width), ('*', Some(80)));
+    assert_eq!(config.lint_levels().unwrap()["bare-url"], Level::Off);
+    assert!(config.search_options(false).is_some());
+  }
+
+  #[test]
+  fn defaults_without_a_file() {
+    let config = parse("").unwrap();
+    assert!(matches!(config.target(), Ok(None)));
+    assert_eq!(config.transformers().unwrap().len(), DEFAULT_TRANSFORMERS.len());
+    assert!(config.search_options(false).is_none());
+    assert!(config.search_options(true).is_some());
+  }
+
+  #[test]
+  fn reports_invalid_values() {
+    let errors = [
+      ("target = \"pdf\"", "unknown target `pdf`"),
+      ("html-mode = \"ugly\"", "unknown html-mode `ugly`"),
+      ("transformers = [\"nope\"]", "unknown transformer `nope`"),
+      (
+        "[transformer.heading-slug]\ndepth = 1",
+        "unknown option `depth` for transformer `heading-slug`",
+      ),
+      (
+        "transformers = []\n[transformer.heading-slug]\nanchor = \"wrap\"",
+        "options for `heading-slug`, which is not in `transformers`",
+      ),
+      (
+        "[transformer.table-of-contents]\nmin-depth = 4\nmax-depth = 2",
+        "min-depth is greater than max-depth",
+      ),
+      (
+        "[transformer.table-of-contents]\nmax-depth = -1",
+        "`max-depth` must be a positive integer",
+      ),
+      ("[format]\nbullet = \"x\"", "unknown bullet `x`, expected - or * or +"),
+      ("[lint]\nbare-url = \"loud\"", "unknown level `loud` for `bare-url`"),
+      ("[lint]\nnope = \"off\"", "unknown lint rule `nope`"),
+      ("[highlighter]\ntheme = \"missing.json\"", "could not read missing.json"),
+    ];
+    for (source, expected) in errors {
+      let err = parse(source).err().unwrap_or_default();
+      assert!(err.starts_with(expected), "{:?}: {}", source, err);
+    }
+    assert!(parse("unknown = 1").unwrap_err().starts_with("unknown field `unknown`"));
+  }
+
+  #[test]
+  fn discovers_the_closest_file_and_resolves_paths() {
+    let dir = temp_dir("discover");
+    let nested = dir.join("content/posts");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(dir.join(CONFIG_FILE), "target = \"text\"\nsrc = \"content\"\n").unwrap();
+    let config = Config::discover(&nested).unwrap().unwrap();
+    assert!(matches!(config.target(), Ok(Some(Target::Text))));
+    assert_eq!(config.src, Some(fs::canonicalize(&dir).unwrap().join("content")));
+
+    // the theme is read relative to scc.toml and colors the code of the compile options
+    fs::write(
+      dir.join("theme.json"),
+      r##"{ "tokenColors": [{ "scope": "keyword", "settings": { "foreground": "#ff0000" } }] }"##,
+    )
+    .unwrap();
+    fs::write(dir.join(CONFIG_FILE), "[highlighter]\ntheme = \"theme.json\"\n").unwrap();
+    let config = Config::discover(&nested).unwrap().unwrap();
+    let output = crate::compile("```rust\nfn main() {}\n```\n", config.options(Target::Html).unwrap()).unwrap();
+    assert!(
+      output.code.contains("<span style=\"color:#ff0000\">fn</span>"),
+      "{}",
+      output.code
+    );
+
+    fs::write(dir.join(CONFIG_FILE), "target = \"pdf\"\n").unwrap();
+    let err = Config::discover(&nested).unwrap_err();
+    assert!(
+      err.contains(CONFIG_FILE) && err.contains("unknown target `pdf`"),
+      "{}",
+      err
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
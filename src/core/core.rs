@@ -1,20 +1,42 @@
-use crate::{ast, parser::MdxParser, transformer::Transformer};
+use std::collections::BTreeMap;
 
-pub struct Core<'a> {
+use crate::{
+  ast,
+  diagnostics::Diagnostic,
+  parser::{MdxParser, ParserOptions},
+  transformer::{default_transformers, Transformer},
+};
+
+// the transformed ast, before it is compiled to a target
+pub struct Document {
+  pub ast: ast::Ast,
+  pub toc: Vec<ast::TocEntry>,
+  pub frontmatter: BTreeMap<String, String>,
+}
+
+pub struct Core {
   ast: Option<ast::Ast>,
   transformers: Vec<Box<dyn Transformer>>,
-  raw: &'a str,
+  debug: bool, // print the ast after parsing and after every transformer on stderr
+  parser_options: ParserOptions,
 }
 
-impl<'a> Default for Core<'a> {
+impl Default for Core {
   fn default() -> Self {
     Self::new()
   }
 }
 
-impl<'a> Core<'a> {
+impl Core {
   pub fn new() -> Self {
-    Self { transformers: vec![], raw: "", ast: None }
+    Self { transformers: vec![], ast: None, debug: false, parser_options: ParserOptions::default() }
+  }
+
+  // the transformers the cli runs on every document without a scc.toml
+  pub fn with_default_transformers() -> Self {
+    default_transformers()
+      .into_iter()
+      .fold(Self::new(), Self::use_transformer)
   }
 
   pub fn use_transformer(mut self, transformer: Box<dyn Transformer>) -> Self {
@@ -22,21 +44,57 @@ impl<'a> Core<'a> {
     self
   }
 
-  fn transform(&mut self) {
+  pub fn with_parser_options(mut self, options: ParserOptions) -> Self {
+    self.parser_options = options;
+    self
+  }
+
+  pub fn with_debug(mut self, debug: bool) -> Self {
+    self.debug = debug;
+    self
+  }
+
+  // `inspect` sees the ast once parsed and again after every transformer
+  fn transform(&mut self, mut inspect: impl FnMut(&str, &ast::Ast)) {
     if let Some(ast) = &mut self.ast {
-      for transformer in &mut self.transformers {
-        let mut ast = ast.clone();
-        self.ast = Some(transformer.transform(&mut ast).clone());
+      inspect("parse", ast);
+      for transformer in &self.transformers {
+        *ast = transformer.transform(ast);
+        inspect(transformer.name(), ast);
       }
     }
   }
 
-  pub fn compile(&mut self, raw: &str) {
-    self.ast = Some(self.parse(raw));
-    self.transform();
+  pub fn compile(&mut self, raw: &str) -> Result<Document, Diagnostic> {
+    let ast = self.parse(raw)?;
+    Ok(self.compile_ast(ast))
+  }
+
+  // an ast that was not parsed from markdown, e.g. from `ast::mdast::from_mdast`
+  pub fn compile_ast(&mut self, ast: ast::Ast) -> Document {
+    self.ast = Some(ast);
+    let debug = self.debug;
+    self.transform(|stage, ast| {
+      if debug {
+        eprintln!("== {}\n{}", stage, ast);
+      }
+    });
+    let ast = self.ast.clone().expect("ast is parsed before compiling");
+    let (toc, frontmatter) = match &ast {
+      ast::Ast::Root(root) => (root.toc.clone(), root.frontmatter.clone()),
+    };
+    Document { ast, toc, frontmatter }
+  }
+
+  // the ast after each stage, e.g. [("parse", ..), ("TransformerHeadingSlug", ..)]
+  pub fn stages(&mut self, raw: &str) -> Result<Vec<(String, ast::Ast)>, Diagnostic> {
+    self.ast = Some(self.parse(raw)?);
+    let mut stages = Vec::new();
+    self.transform(|stage, ast| stages.push((stage.to_string(), ast.clone())));
+    Ok(stages)
   }
 
-  pub fn parse(&self, raw: &str) -> ast::Ast {
-    return MdxParser::parse(raw);
+  pub fn parse(&self, raw: &str) -> Result<ast::Ast, Diagnostic> {
+    MdxParser::parse_with(raw, self.parser_options)
   }
 }
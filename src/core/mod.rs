@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 mod core;
-pub use core::Core;
+pub use core::{Core, Document};
@@ -0,0 +1,151 @@
+// ==================
+// errors reported to the user, printed on stderr e.g.
+//
+//   error: docs/intro.md:3:1: Expected '</Note>' to close '<Note>'
+//   warning[code-language]: docs/intro.md:9:1: code block without a language
+//   {"severity":"error","message":"Expected '</Note>' to close '<Note>'","file":"docs/intro.md","line":3,"column":1}
+//
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning, // reported without failing the command
+}
+
+impl Severity {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub message: String,
+  pub file: Option<String>,
+  pub line: Option<usize>,
+  pub column: Option<usize>,
+  pub code: Option<String>, // what reported it e.g. the lint rule
+}
+
+impl Diagnostic {
+  pub fn error(message: &str) -> Self {
+    Self {
+      severity: Severity::Error,
+      message: message.to_string(),
+      file: None,
+      line: None,
+      column: None,
+      code: None,
+    }
+  }
+
+  pub fn warning(message: &str) -> Self {
+    Self { severity: Severity::Warning, ..Self::error(message) }
+  }
+
+  pub fn in_file(mut self, file: &str) -> Self {
+    self.file = Some(file.to_string());
+    self
+  }
+
+  pub fn with_code(mut self, code: &str) -> Self {
+    self.code = Some(code.to_string());
+    self
+  }
+
+  pub fn at(mut self, line: usize, column: usize) -> Self {
+    self.line = Some(line);
+    self.column = Some(column);
+    self
+  }
+
+  // error[code]: file:line:column: message
+  pub fn to_human(&self) -> String {
+    let severity = match &self.code {
+      Some(code) => format!("{}[{}]", self.severity.name(), code),
+      None => self.severity.name().to_string(),
+    };
+    let mut location = self.file.clone().unwrap_or_default();
+    if let (Some(line), Some(column)) = (self.line, self.column) {
+      location = format!("{}:{}:{}", location, line, column);
+    }
+    if location.is_empty() {
+      return format!("{}: {}", severity, self.message);
+    }
+    format!("{}: {}: {}", severity, location, self.message)
+  }
+
+  pub fn to_json(&self) -> serde_json::Value {
+    let mut value = json!({
+      "severity": self.severity.name(),
+      "message": self.message,
+      "file": self.file,
+      "line": self.line,
+      "column": self.column,
+    });
+    if let Some(code) = &self.code {
+      value["code"] = json!(code);
+    }
+    value
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagnosticFormat {
+  #[default]
+  Human,
+  Json, // one json object per line
+}
+
+impl DiagnosticFormat {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "human" => Some(DiagnosticFormat::Human),
+      "json" => Some(DiagnosticFormat::Json),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+  pub items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+  pub fn new() -> Self {
+    Self { items: Vec::new() }
+  }
+
+  pub fn push(&mut self, diagnostic: Diagnostic) {
+    self.items.push(diagnostic);
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self.items.iter().any(|item| item.severity == Severity::Error)
+  }
+
+  pub fn in_file(self, file: &str) -> Self {
+    Self { items: self.items.into_iter().map(|item| item.in_file(file)).collect() }
+  }
+
+  pub fn print(&self, format: DiagnosticFormat) {
+    for item in &self.items {
+      match format {
+        DiagnosticFormat::Human => eprintln!("{}", item.to_human()),
+        DiagnosticFormat::Json => eprintln!("{}", item.to_json()),
+      }
+    }
+  }
+}
+
+impl From<Diagnostic> for Diagnostics {
+  fn from(diagnostic: Diagnostic) -> Self {
+    Self { items: vec![diagnostic] }
+  }
+}
@@ -0,0 +1,205 @@
+// ==================
+// scc fmt [files...], rewrites markdown and mdx in place in one style e.g.
+//
+//   scc fmt docs/ README.md    every .md/.mdx under docs and README.md
+//   scc fmt --check            fails and lists what is not formatted, for ci
+//   cat intro.md | scc fmt -   formats stdin to stdout
+//
+// the style comes from [format] in scc.toml. the frontmatter, jsx/vue blocks and code are kept as written,
+// only ```md code blocks are formatted as well.
+//
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  ast::{
+    self,
+    visit::{Action, VisitorMut},
+  },
+  build::source_files,
+  compiler::{MarkdownCompiler, MarkdownOptions},
+  diagnostics::{Diagnostic, Diagnostics},
+  parser::{MdxParser, ParserOptions},
+};
+
+const MARKDOWN_LANGUAGES: [&str; 3] = ["md", "mdx", "markdown"];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FmtOptions {
+  pub markdown: MarkdownOptions,
+  pub parser: ParserOptions,
+  pub check: bool, // report the files instead of rewriting them
+}
+
+#[derive(Default)]
+pub struct FmtSummary {
+  pub unformatted: Vec<(PathBuf, usize)>, // with the first line that changes
+  pub errors: Vec<(PathBuf, Diagnostics)>,
+}
+
+impl FmtSummary {
+  // unformatted files are only an error with --check, otherwise they were rewritten
+  pub fn diagnostics(&self, check: bool) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    for (path, errors) in &self.errors {
+      diagnostics
+        .items
+        .extend(errors.clone().in_file(&path.display().to_string()).items);
+    }
+    if check {
+      for (path, line) in &self.unformatted {
+        diagnostics.push(
+          Diagnostic::error("not formatted")
+            .in_file(&path.display().to_string())
+            .at(*line, 1),
+        );
+      }
+    }
+    diagnostics
+  }
+}
+
+// `paths` are files or directories, directories are searched for .md and .mdx files
+pub fn fmt(paths: &[PathBuf], options: &FmtOptions) -> Result<FmtSummary, String> {
+  let files = source_files(paths)?;
+  let mut summary = FmtSummary::default();
+  for path in files {
+    match fmt_file(&path, options) {
+      Ok(Some(line)) => summary.unformatted.push((path, line)),
+      Ok(None) => {}
+      Err(errors) => summary.errors.push((path, errors)),
+    }
+  }
+  Ok(summary)
+}
+
+// the first line that changes when the file is not formatted
+fn fmt_file(path: &Path, options: &FmtOptions) -> Result<Option<usize>, Diagnostics> {
+  let source = fs::read_to_string(path).map_err(|err| Diagnostic::error(&format!("could not read file: {}", err)))?;
+  let formatted = format_source(&source, options.markdown, options.parser)?;
+  if formatted == source {
+    return Ok(None);
+  }
+  if !options.check {
+    fs::write(path, &formatted).map_err(|err| Diagnostic::error(&format!("could not write file: {}", err)))?;
+  }
+  let line = source
+    .lines()
+    .zip(formatted.lines())
+    .position(|(source, formatted)| source != formatted)
+    .unwrap_or(source.lines().count().min(formatted.lines().count()));
+  Ok(Some(line + 1))
+}
+
+pub fn format_source(source: &str, markdown: MarkdownOptions, parser: ParserOptions) -> Result<String, Diagnostics> {
+  // components are read as html, which is written back as is
+  let parser = ParserOptions { mdx: false, ..parser };
+  let mut ast = MdxParser::parse_with(source, parser)?;
+  let ast::Ast::Root(root) = &mut ast;
+  root.frontmatter.clear();
+  (|node: &mut ast::Node| {
+    if let ast::Node::CodeBlock(code_block) = node {
+      format_code(code_block, markdown, parser);
+    }
+    Action::Continue
+  })
+  .visit_ast(&mut ast);
+  let mut formatted = String::new();
+  if let Some(frontmatter) = raw_frontmatter(source).filter(|_| parser.frontmatter) {
+    formatted.push_str(frontmatter.trim_end());
+    formatted.push_str("\n\n");
+  }
+  formatted.push_str(&MarkdownCompiler::with_options(markdown).compile(&mut ast));
+  Ok(format!("{}\n", formatted.trim_end()))
+}
+
+// other languages are left alone, whitespace can matter e.g. in a diff or a markdown line break.
+// markdown that does not parse is left alone too, it may be an example of a mistake
+fn format_code(code_block: &mut ast::CodeBlock, markdown: MarkdownOptions, parser: ParserOptions) {
+  if MARKDOWN_LANGUAGES.contains(&code_block.language.as_str()) {
+    if let Ok(formatted) = format_source(&code_block.code, markdown, parser) {
+      code_block.code = formatted.trim_end().to_string();
+    }
+  }
+}
+
+// the `---` block at the start, as the parser finds it
+fn raw_frontmatter(source: &str) -> Option<&str> {
+  if !source.starts_with("---\n") && !source.starts_with("---\r\n") {
+    return None;
+  }
+  let mut end = source.find('\n')? + 1;
+  for line in source[end..].split_inclusive('\n') {
+    end += line.len();
+    if line.trim_end() == "---" {
+      return Some(&source[..end]);
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::format_source;
+  use crate::{compiler::MarkdownOptions, parser::MdxParser, parser::ParserOptions};
+
+  // the tree without the positions, which formatting moves
+  fn parse(source: &str) -> serde_json::Value {
+    let parser = ParserOptions { mdx: false, ..ParserOptions::default() };
+    let mut tree = serde_json::to_value(MdxParser::parse_with(source, parser).unwrap()).unwrap();
+    without_positions(&mut tree);
+    tree
+  }
+
+  fn without_positions(value: &mut serde_json::Value) {
+    match value {
+      serde_json::Value::Object(map) => {
+        map.remove("position");
+        map.values_mut().for_each(without_positions);
+      }
+      serde_json::Value::Array(items) => items.iter_mut().for_each(without_positions),
+      _ => {}
+    }
+  }
+
+  // formatting twice changes nothing and the tree is the one of the source
+  #[test]
+  fn round_trips() {
+    let sources = [
+      "| a | b | c | d |\n|:--|:-:|--:|---|\n| 1 | **2** | 3 | 4 |\n",
+      "1. one\n\n   continued\n2. two\n\n3. three\n",
+      "* loose a\n\n* loose b\n\n  more b\n\n  - nested\n  - tight\n",
+      "- a\n  ```sh\n  ls\n\n  pwd\n  ```\n- b\n",
+      "```diff\n- old   \n+ new\t\n\n```\n\nText  \nbreak\n",
+      "# Title\n\nSome *text* and __bold__.\n\n> quote\n> more\n\n***\n",
+    ];
+    for source in sources {
+      let formatted = format_source(source, MarkdownOptions::default(), ParserOptions::default()).unwrap();
+      let again = format_source(&formatted, MarkdownOptions::default(), ParserOptions::default()).unwrap();
+      assert_eq!(again, formatted, "not idempotent: {:?}", source);
+      assert_eq!(parse(&formatted), parse(source), "{:?} -> {:?}", source, formatted);
+    }
+  }
+
+  #[test]
+  fn keeps_table_alignment_loose_lists_and_code() {
+    let source = "|a|b|c|\n|:-|:-:|-:|\n|1|2|3|\n\n1. one\n\n   more\n2. two\n\n```py\nx = 1   \n```\n";
+    let expected = "| a   | b   | c   |\n| :-- | :-: | --: |\n| 1   | 2   | 3   |\n\n1. one\n\n   more\n\n2. two\n\n```py\nx = 1   \n```\n";
+    let formatted = format_source(source, MarkdownOptions::default(), ParserOptions::default()).unwrap();
+    assert_eq!(formatted, expected);
+  }
+
+  #[test]
+  fn keeps_frontmatter_and_components_as_written() {
+    let source = "---\nb: 1\na: \"x: y\"\n---\n#  Hi\n\n<Note  kind=\"x\">\n  **keep   this**\n</Note>\n\n````md\n* a\n```js   \nlet a;   \n```\n````\n";
+    let expected = "---\nb: 1\na: \"x: y\"\n---\n\n# Hi\n\n<Note  kind=\"x\">\n  **keep   this**\n</Note>\n\n````md\n- a\n\n```js\nlet a;   \n```\n````\n";
+    let formatted = format_source(source, MarkdownOptions::default(), ParserOptions::default()).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(
+      format_source(&formatted, MarkdownOptions::default(), ParserOptions::default()).unwrap(),
+      expected
+    );
+  }
+}
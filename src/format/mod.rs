@@ -1,28 +1,95 @@
-use std::fmt::Display;
+// ==================
+// debug output of the ast, used by `scc inspect` and `--debug`
+//
+// root
+// ├─ heading h1 "Hello" #hello
+// └─ paragraph
+//    ├─ text "Hi, "
+//    └─ bold
+//       └─ text "there"
+//
+use std::fmt::{Display, Write};
+
+use serde_json::Value;
 
 use crate::ast;
 
 impl Display for ast::Node {
+  // the node on its own, without the children
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      ast::Node::Heading(heading) => write!(f, "h{} {}", heading.level, heading.text),
-      ast::Node::Text(text) => write!(f, "p {}", text.text),
-      ast::Node::InlineCode(inline_code) => write!(f, "`{}`", inline_code.code),
-      ast::Node::CodeBlock(code_block) => write!(f, "```{}\n{}\n```", code_block.language, code_block.code),
-      ast::Node::List(list) => write!(f, "list {:?}", list.children),
-      ast::Node::Blockquote(blockquote) => write!(f, "> {:?}", blockquote.children),
-      ast::Node::Link(link) => write!(f, "[{}]({})", link.alt, link.url),
-      ast::Node::Image(image) => write!(f, "![{}]({})", image.alt, image.url),
-      ast::Node::Paragraph(paragraph) => write!(f, "p {:?}", paragraph.children),
-      _ => write!(f, "Unknown node"),
+      ast::Node::Heading(heading) => {
+        write!(f, "heading h{} {:?}", heading.level, heading.text)?;
+        if let Some(id) = &heading.id {
+          write!(f, " #{}", id)?;
+        }
+        Ok(())
+      }
+      ast::Node::Text(text) => write!(f, "text {:?}", text.text),
+      ast::Node::InlineCode(inline_code) => write!(f, "inline_code {:?}", inline_code.code),
+      ast::Node::CodeBlock(code_block) => {
+        write!(f, "code_block {:?}", code_block.language)?;
+        if let Some(meta) = &code_block.meta {
+          write!(f, " meta={:?}", meta)?;
+        }
+        if !code_block.classes.is_empty() {
+          write!(f, " classes={:?}", code_block.classes)?;
+        }
+        write!(f, " lines={}", code_block.code.split('\n').count())
+      }
+      ast::Node::Html(html) => write!(f, "html {:?}", html),
+      ast::Node::Paragraph(_) => write!(f, "paragraph"),
+      ast::Node::List(list) => {
+        write!(f, "list")?;
+        if list.ordered {
+          write!(f, " ordered start={}", list.start.unwrap_or(1))?;
+        }
+        if list.loose {
+          write!(f, " loose")?;
+        }
+        Ok(())
+      }
+      ast::Node::ListItem(_) => write!(f, "list_item"),
+      ast::Node::Blockquote(_) => write!(f, "blockquote"),
+      ast::Node::Table(table) => write!(f, "table columns={} rows={}", table.header.len(), table.rows.len()),
+      ast::Node::ThematicBreak => write!(f, "thematic_break"),
+      ast::Node::SoftBreak => write!(f, "soft_break"),
+      ast::Node::HardBreak => write!(f, "hard_break"),
+      ast::Node::Bold(_) => write!(f, "bold"),
+      ast::Node::Italic(_) => write!(f, "italic"),
+      ast::Node::Link(link) => write!(f, "link {:?} -> {:?}", link.alt, link.url),
+      ast::Node::Image(image) => write!(f, "image {:?} -> {:?}", image.alt, image.url),
+      ast::Node::ReactComponent(component) => write!(f, "react_component <{}> {:?}", component.name, component.props),
+      ast::Node::VueComponent(component) => write!(f, "vue_component <{}> {:?}", component.name, component.props),
     }
   }
 }
 
 impl Display for ast::Ast {
+  // the whole tree, one node per line
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match self {
-      ast::Ast::Root(root) => write!(f, "{:?}", root.children),
+    let ast::Ast::Root(root) = self;
+    let mut output = String::from("root\n");
+    for (key, value) in &root.frontmatter {
+      let _ = writeln!(output, "│  {}: {:?}", key, value);
     }
+    write_nodes(&mut output, &root.children, "");
+    write!(f, "{}", output)
   }
 }
+
+fn write_nodes(output: &mut String, nodes: &[ast::Node], indent: &str) {
+  for (index, node) in nodes.iter().enumerate() {
+    let last = index + 1 == nodes.len();
+    let _ = writeln!(output, "{}{}{}", indent, if last { "└─ " } else { "├─ " }, node);
+    let indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+    write_nodes(output, node.children(), &indent);
+  }
+}
+
+// ==================
+// the tree as json, `scc inspect --json`, `ast::mdast::to_mdast` for `--json=mdast`
+//
+pub fn ast_to_json(ast: &ast::Ast) -> Value {
+  serde_json::to_value(ast).expect("the ast is always valid json")
+}
@@ -1,32 +1,277 @@
-use std::{fs, path::Path};
+use std::{collections::BTreeMap, fs, path::Path};
 
-pub struct Highlighter<'a> {
-  path_name: &'a Path,
+use serde_json::Value;
+
+use super::languages::{self, TokenKind};
+
+// a colored piece of a line of code, `color` is a `#rrggbb` hex color or None for the default text color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+  pub text: String,
+  pub color: Option<String>,
+}
+
+// colors code with the token colors of a vscode or zed json theme, or of the built-in palette by default
+#[derive(Debug, Clone)]
+pub struct Highlighter {
+  colors: BTreeMap<TokenKind, String>,
+}
+
+// the scopes of a vscode theme read for each kind, the first one the theme colors wins
+const VSCODE_SCOPES: [(TokenKind, &[&str]); 7] = [
+  (TokenKind::Comment, &["comment"]),
+  (TokenKind::String, &["string"]),
+  (TokenKind::Keyword, &["keyword.control", "storage.type", "keyword"]),
+  (TokenKind::Number, &["constant.numeric"]),
+  (TokenKind::Constant, &["constant.language"]),
+  (TokenKind::Function, &["entity.name.function", "support.function"]),
+  (
+    TokenKind::Type,
+    &["entity.name.type", "support.type", "entity.name.class"],
+  ),
+];
+
+// the keys of `style.syntax` in a zed theme
+const ZED_SYNTAX: [(TokenKind, &[&str]); 7] = [
+  (TokenKind::Comment, &["comment"]),
+  (TokenKind::String, &["string"]),
+  (TokenKind::Keyword, &["keyword"]),
+  (TokenKind::Number, &["number"]),
+  (TokenKind::Constant, &["boolean", "constant"]),
+  (TokenKind::Function, &["function"]),
+  (TokenKind::Type, &["type"]),
+];
+
+// one dark
+const DEFAULT_COLORS: [(TokenKind, &str); 7] = [
+  (TokenKind::Comment, "#7f848e"),
+  (TokenKind::String, "#98c379"),
+  (TokenKind::Keyword, "#c678dd"),
+  (TokenKind::Number, "#d19a66"),
+  (TokenKind::Constant, "#d19a66"),
+  (TokenKind::Function, "#61afef"),
+  (TokenKind::Type, "#e5c07b"),
+];
+
+impl Default for Highlighter {
+  fn default() -> Self {
+    let colors = DEFAULT_COLORS
+      .into_iter()
+      .map(|(kind, color)| (kind, color.to_string()))
+      .collect();
+    Self { colors }
+  }
+}
+
+impl Highlighter {
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    Self::from_json(&source).map_err(|err| format!("theme {}: {}", path.display(), err))
+  }
+
+  // a vscode theme has `tokenColors`, a zed theme family has `themes`, the first theme of the family is used
+  pub fn from_json(source: &str) -> Result<Self, String> {
+    let theme: Value = serde_json::from_str(source).map_err(|err| err.to_string())?;
+    let colors = if let Some(rules) = theme["tokenColors"].as_array() {
+      vscode_colors(rules)
+    } else if let Some(syntax) = theme["themes"][0]["style"]["syntax"].as_object() {
+      zed_colors(syntax)
+    } else {
+      return Err("expected a vscode theme with `tokenColors` or a zed theme with `themes`".to_string());
+    };
+    Ok(Self { colors })
+  }
+
+  pub fn supports(&self, language: &str) -> bool {
+    languages::language(language).is_some()
+  }
+
+  // the tokens of each line of `code`, split at '\n' like `ast::CodeBlock::lines`
+  pub fn highlight(&self, code: &str, language: &str) -> Vec<Vec<Token>> {
+    let tokens = match languages::language(language) {
+      Some(language) => languages::tokenize(code, language),
+      None => vec![(None, code)],
+    };
+    let mut lines: Vec<Vec<Token>> = vec![Vec::new()];
+    for (kind, text) in tokens {
+      let color = kind.and_then(|kind| self.colors.get(&kind)).cloned();
+      for (index, part) in text.split('\n').enumerate() {
+        if index > 0 {
+          lines.push(Vec::new());
+        }
+        let line = lines.last_mut().unwrap();
+        match line.last_mut() {
+          _ if part.is_empty() => {}
+          Some(last) if last.color == color => last.text.push_str(part),
+          _ => line.push(Token { text: part.to_string(), color: color.clone() }),
+        }
+      }
+    }
+    lines
+  }
 }
 
-// suport vscode theme file and zed theme file
-impl<'a> Highlighter<'a> {
-  pub fn new(path_name: &Path) -> Self {
-    Self { path_name }
+fn vscode_colors(rules: &[Value]) -> BTreeMap<TokenKind, String> {
+  // later rules win, like in vscode
+  let mut scopes = BTreeMap::new();
+  for rule in rules {
+    let Some(color) = rule["settings"]["foreground"].as_str().and_then(hex_color) else {
+      continue;
+    };
+    let names: Vec<&str> = match &rule["scope"] {
+      Value::String(names) => names.split(',').map(str::trim).collect(),
+      Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+      _ => continue,
+    };
+    for name in names {
+      scopes.insert(name.to_string(), color.clone());
+    }
   }
+  VSCODE_SCOPES
+    .into_iter()
+    .filter_map(|(kind, candidates)| {
+      // `keyword` colors `keyword.control` too
+      let color = candidates.iter().find_map(|candidate| {
+        let parts: Vec<&str> = candidate.split('.').collect();
+        (1..=parts.len())
+          .rev()
+          .find_map(|length| scopes.get(&parts[..length].join(".")))
+      })?;
+      Some((kind, color.clone()))
+    })
+    .collect()
+}
+
+fn zed_colors(syntax: &serde_json::Map<String, Value>) -> BTreeMap<TokenKind, String> {
+  ZED_SYNTAX
+    .into_iter()
+    .filter_map(|(kind, keys)| {
+      let color = keys
+        .iter()
+        .find_map(|key| syntax.get(*key)?["color"].as_str().and_then(hex_color))?;
+      Some((kind, color))
+    })
+    .collect()
+}
 
-  pub fn highlight(&self, code: &str, language: &str) {
-    todo!("to implement");
+// `#rgb`, `#rrggbb` or `#rrggbbaa` as `#rrggbb`, the alpha is dropped
+fn hex_color(color: &str) -> Option<String> {
+  let hex = color.strip_prefix('#')?;
+  if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    return None;
+  }
+  match hex.len() {
+    3 => Some(format!("#{}", hex.chars().flat_map(|c| [c, c]).collect::<String>()).to_ascii_lowercase()),
+    6 | 8 => Some(format!("#{}", &hex[..6]).to_ascii_lowercase()),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn colored(highlighter: &Highlighter, code: &str, language: &str) -> Vec<Vec<(String, Option<String>)>> {
+    highlighter
+      .highlight(code, language)
+      .into_iter()
+      .map(|line| line.into_iter().map(|token| (token.text, token.color)).collect())
+      .collect()
   }
 
-  pub fn is_supported(&self) -> bool {
-    return self.path_name.extension().unwrap() == "json";
+  fn token(text: &str, color: Option<&str>) -> (String, Option<String>) {
+    (text.to_string(), color.map(str::to_string))
   }
 
-  pub fn json_parser(&mut self) {
-    let text_json = fs::read_to_string(self.path_name).inspect_err(|err| {
-      eprintln!("Failed to read file: {}", err);
-    });
-    let text_json = text_json.unwrap().as_str();
-    // let json = serde_json::from_str(text_json).inspect_err(|err| {
-    //   eprintln!("Failed to parse json: {}", err);
-    // });
+  #[test]
+  fn tokenizes_code_into_lines() {
+    let highlighter = Highlighter::default();
+    let lines = colored(&highlighter, "const a = \"x\"; // one\n/* two\nlines */ f(1)", "ts");
+    assert_eq!(
+      lines,
+      [
+        vec![
+          token("const", Some("#c678dd")),
+          token(" a = ", None),
+          token("\"x\"", Some("#98c379")),
+          token("; ", None),
+          token("// one", Some("#7f848e")),
+        ],
+        vec![token("/* two", Some("#7f848e"))],
+        vec![
+          token("lines */", Some("#7f848e")),
+          token(" ", None),
+          token("f", Some("#61afef")),
+          token("(", None),
+          token("1", Some("#d19a66")),
+          token(")", None),
+        ],
+      ]
+    );
+    // the texts add up to the code whatever the language
+    let code = "x = 'unterminated\nπ = 3.14 # done\n\n";
+    for language in ["py", "unknown", ""] {
+      let lines = highlighter.highlight(code, language);
+      assert_eq!(lines.len(), code.split('\n').count());
+      let text: Vec<String> = lines
+        .iter()
+        .map(|line| line.iter().map(|token| token.text.as_str()).collect())
+        .collect();
+      assert_eq!(text.join("\n"), code);
+    }
+    assert!(highlighter.supports("Rust") && !highlighter.supports("brainfuck"));
+  }
+
+  #[test]
+  fn reads_vscode_themes() {
+    let highlighter = Highlighter::from_json(
+      r##"{
+        "colors": { "editor.background": "#000000" },
+        "tokenColors": [
+          { "settings": { "foreground": "#ffffff" } },
+          { "scope": "comment", "settings": { "foreground": "#AABBCC" } },
+          { "scope": ["string", "keyword"], "settings": { "foreground": "#abc" } },
+          { "scope": "keyword.control, constant", "settings": { "foreground": "#11223344" } }
+        ]
+      }"##,
+    )
+    .unwrap();
+    let lines = colored(&highlighter, "if true // x", "rust");
+    assert_eq!(
+      lines,
+      [vec![
+        token("if", Some("#112233")),
+        token(" ", None),
+        token("true", Some("#112233")),
+        token(" ", None),
+        token("// x", Some("#aabbcc")),
+      ]]
+    );
+  }
 
-    println!("{:#?}", text_json);
+  #[test]
+  fn reads_zed_themes() {
+    let highlighter = Highlighter::from_json(
+      r##"{
+        "name": "One",
+        "themes": [{ "name": "One Dark", "style": { "syntax": {
+          "keyword": { "color": "#b477cfff" },
+          "boolean": { "color": "#bf956aff" },
+          "string": { "color": null }
+        } } }]
+      }"##,
+    )
+    .unwrap();
+    let lines = colored(&highlighter, "let x = \"a\" || false", "js");
+    assert_eq!(
+      lines,
+      [vec![
+        token("let", Some("#b477cf")),
+        token(" x = \"a\" || ", None),
+        token("false", Some("#bf956a")),
+      ]]
+    );
+    assert!(Highlighter::from_json("{}").is_err());
+    assert!(Highlighter::from_json("not json").is_err());
   }
 }
@@ -0,0 +1,369 @@
+// ==================
+// a small tokenizer per language, good enough to color comments, strings, numbers and keywords
+// without a grammar. languages it doesn't know are left as plain text.
+//
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TokenKind {
+  Comment,
+  String,
+  Keyword,
+  Number,
+  Constant, // true, false, null...
+  Function, // an identifier followed by `(`
+  Type,     // an identifier that starts with an uppercase letter
+}
+
+pub struct Language {
+  line_comments: &'static [&'static str],
+  block_comment: Option<(&'static str, &'static str)>,
+  quotes: &'static [char],
+  multiline_quotes: &'static [char], // strings that can span lines, e.g. template literals
+  keywords: &'static [&'static str],
+  constants: &'static [&'static str],
+  ignore_case: bool,
+}
+
+const JAVASCRIPT: Language = Language {
+  line_comments: &["//"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"', '\''],
+  multiline_quotes: &['`'],
+  keywords: &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "of",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "type",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "yield",
+  ],
+  constants: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+  ignore_case: false,
+};
+
+const RUST: Language = Language {
+  line_comments: &["//"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"'],
+  multiline_quotes: &[],
+  keywords: &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+    "struct", "super", "trait", "type", "unsafe", "use", "where", "while",
+  ],
+  constants: &["true", "false", "None"],
+  ignore_case: false,
+};
+
+const PYTHON: Language = Language {
+  line_comments: &["#"],
+  block_comment: None,
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+    "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+    "return", "try", "while", "with", "yield",
+  ],
+  constants: &["True", "False", "None"],
+  ignore_case: false,
+};
+
+const GO: Language = Language {
+  line_comments: &["//"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"', '\''],
+  multiline_quotes: &['`'],
+  keywords: &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+  ],
+  constants: &["true", "false", "nil", "iota"],
+  ignore_case: false,
+};
+
+// c, c++, c#, java, kotlin and swift share enough to be read the same way
+const C_LIKE: Language = Language {
+  line_comments: &["//"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &[
+    "auto",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "extern",
+    "final",
+    "float",
+    "for",
+    "fun",
+    "func",
+    "if",
+    "implements",
+    "import",
+    "include",
+    "int",
+    "interface",
+    "let",
+    "long",
+    "namespace",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typedef",
+    "union",
+    "unsigned",
+    "using",
+    "val",
+    "var",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+  ],
+  constants: &["true", "false", "null", "nullptr", "NULL", "nil"],
+  ignore_case: false,
+};
+
+const SHELL: Language = Language {
+  line_comments: &["#"],
+  block_comment: None,
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local", "return",
+    "then", "until", "while",
+  ],
+  constants: &["true", "false"],
+  ignore_case: false,
+};
+
+const CSS: Language = Language {
+  line_comments: &[],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &["important", "media", "import", "supports", "keyframes", "font-face"],
+  constants: &[],
+  ignore_case: false,
+};
+
+const JSON: Language = Language {
+  line_comments: &["//"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"'],
+  multiline_quotes: &[],
+  keywords: &[],
+  constants: &["true", "false", "null"],
+  ignore_case: false,
+};
+
+const CONFIG: Language = Language {
+  line_comments: &["#"],
+  block_comment: None,
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &[],
+  constants: &["true", "false", "null", "yes", "no"],
+  ignore_case: false,
+};
+
+const SQL: Language = Language {
+  line_comments: &["--"],
+  block_comment: Some(("/*", "*/")),
+  quotes: &['"', '\''],
+  multiline_quotes: &[],
+  keywords: &[
+    "alter", "and", "as", "asc", "by", "create", "delete", "desc", "distinct", "drop", "from", "group", "having", "in",
+    "index", "insert", "into", "is", "join", "left", "limit", "not", "on", "or", "order", "primary", "key", "right",
+    "select", "set", "table", "union", "update", "values", "where", "with",
+  ],
+  constants: &["true", "false", "null"],
+  ignore_case: true,
+};
+
+// the language of a code block from its info string, e.g. `ts` or `shell`
+pub fn language(name: &str) -> Option<&'static Language> {
+  let language = match name.to_ascii_lowercase().as_str() {
+    "js" | "javascript" | "jsx" | "mjs" | "cjs" | "ts" | "typescript" | "tsx" | "mts" => &JAVASCRIPT,
+    "rs" | "rust" => &RUST,
+    "py" | "python" => &PYTHON,
+    "go" | "golang" => &GO,
+    "c" | "h" | "cpp" | "c++" | "hpp" | "cs" | "csharp" | "java" | "kt" | "kotlin" | "swift" => &C_LIKE,
+    "sh" | "bash" | "shell" | "zsh" | "console" => &SHELL,
+    "css" | "scss" | "less" => &CSS,
+    "json" | "jsonc" | "json5" => &JSON,
+    "toml" | "yaml" | "yml" | "ini" => &CONFIG,
+    "sql" => &SQL,
+    _ => return None,
+  };
+  Some(language)
+}
+
+// splits `code` into tokens whose texts add up to `code`, None for plain text
+pub fn tokenize<'c>(code: &'c str, language: &Language) -> Vec<(Option<TokenKind>, &'c str)> {
+  let mut tokens: Vec<(Option<TokenKind>, &'c str)> = Vec::new();
+  let mut index = 0;
+  while index < code.len() {
+    let rest = &code[index..];
+    let character = rest.chars().next().unwrap_or_default();
+    let (kind, length) = if language.line_comments.iter().any(|start| rest.starts_with(start)) {
+      (Some(TokenKind::Comment), rest.find('\n').unwrap_or(rest.len()))
+    } else if let Some((start, end)) = language.block_comment.filter(|(start, _)| rest.starts_with(start)) {
+      let length = rest[start.len()..]
+        .find(end)
+        .map_or(rest.len(), |found| start.len() + found + end.len());
+      (Some(TokenKind::Comment), length)
+    } else if language.quotes.contains(&character) || language.multiline_quotes.contains(&character) {
+      (
+        Some(TokenKind::String),
+        string_length(rest, character, language.multiline_quotes.contains(&character)),
+      )
+    } else if character.is_ascii_digit() {
+      let length = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.')
+        .unwrap_or(rest.len());
+      (Some(TokenKind::Number), length)
+    } else if is_identifier_start(character) {
+      let length = rest
+        .find(|c: char| !is_identifier_start(c) && !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+      (identifier_kind(&rest[..length], &rest[length..], language), length)
+    } else {
+      (None, character.len_utf8())
+    };
+    let text = &code[index..index + length];
+    match tokens.last_mut() {
+      Some((last_kind, last_text)) if *last_kind == kind => {
+        *last_text = &code[index - last_text.len()..index + length];
+      }
+      _ => tokens.push((kind, text)),
+    }
+    index += length;
+  }
+  tokens
+}
+
+// up to the closing quote, or the end of the line when there is none
+fn string_length(text: &str, quote: char, multiline: bool) -> usize {
+  let mut escaped = false;
+  for (index, character) in text.char_indices().skip(1) {
+    match character {
+      _ if escaped => escaped = false,
+      '\\' => escaped = true,
+      '\n' if !multiline => return index,
+      _ if character == quote => return index + 1,
+      _ => {}
+    }
+  }
+  text.len()
+}
+
+fn is_identifier_start(character: char) -> bool {
+  character.is_alphabetic() || character == '_' || character == '$'
+}
+
+fn identifier_kind(identifier: &str, after: &str, language: &Language) -> Option<TokenKind> {
+  let matches = |word: &&str| match language.ignore_case {
+    true => word.eq_ignore_ascii_case(identifier),
+    false => *word == identifier,
+  };
+  if language.keywords.iter().any(matches) {
+    Some(TokenKind::Keyword)
+  } else if language.constants.iter().any(matches) {
+    Some(TokenKind::Constant)
+  } else if after.trim_start_matches([' ', '\t']).starts_with('(') {
+    Some(TokenKind::Function)
+  } else if identifier.starts_with(|c: char| c.is_uppercase()) {
+    Some(TokenKind::Type)
+  } else {
+    None
+  }
+}
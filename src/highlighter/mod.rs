@@ -1,2 +1,4 @@
 mod core;
-pub use core::Highlighter;
+mod languages;
+
+pub use core::{Highlighter, Token};
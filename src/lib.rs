@@ -0,0 +1,132 @@
+// ==================
+// scc as a library, the cli in main.rs is built on top of it e.g.
+//
+// let output = scc::compile("# Hello", scc::Options::default())?;
+// assert_eq!(output.code, "<h1 id=\"hello\">Hello</h1>\n");
+//
+pub mod ast;
+pub mod build;
+pub mod compiler;
+pub mod config;
+pub mod core;
+pub mod diagnostics;
+pub mod fmt;
+pub mod format;
+pub mod highlighter;
+pub mod links;
+pub mod lint;
+pub mod parser;
+pub mod search;
+pub mod serve;
+pub mod template;
+pub mod transformer;
+pub mod watch;
+
+use std::collections::BTreeMap;
+
+pub use crate::core::{Core, Document};
+pub use compiler::{
+  AnsiCompiler, AnsiOptions, HtmlCompiler, HtmlMode, LatexCompiler, LatexOptions, MarkdownCompiler, MarkdownOptions,
+  RoffCompiler, SvelteCompiler, Target, TextCompiler, TextOptions, TextStats, TsxCompiler, VueCompiler,
+};
+pub use diagnostics::{Diagnostic, Diagnostics};
+pub use highlighter::Highlighter;
+pub use parser::{MdxParser, ParserOptions};
+pub use template::LayoutOptions;
+pub use transformer::Transformer;
+
+pub struct Options {
+  pub target: Target,
+  pub html_mode: HtmlMode,
+  pub markdown: MarkdownOptions, // the style of the markdown target
+  pub text: TextOptions,         // what the text target keeps
+  pub latex: LatexOptions,       // standalone is also set by `layout.standalone`
+  pub parser: ParserOptions,
+  pub layout: LayoutOptions, // html pages only, the html is returned as is by default
+  pub transformers: Vec<Box<dyn Transformer>>, // in the order they run
+  pub highlighter: Option<Highlighter>, // colors code blocks of the html, vue and svelte targets, off by default
+  pub debug: bool,           // print the ast after parsing and after every transformer on stderr
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Self::new(Target::Html)
+  }
+}
+
+impl Options {
+  // the default transformers and parser extensions, see `transformer::default_transformers`
+  pub fn new(target: Target) -> Self {
+    Self {
+      target,
+      html_mode: HtmlMode::default(),
+      markdown: MarkdownOptions::default(),
+      text: TextOptions::default(),
+      latex: LatexOptions::default(),
+      parser: ParserOptions::default(),
+      layout: LayoutOptions::default(),
+      transformers: transformer::default_transformers(),
+      highlighter: None,
+      debug: false,
+    }
+  }
+
+  pub fn use_transformer(mut self, transformer: Box<dyn Transformer>) -> Self {
+    self.transformers.push(transformer);
+    self
+  }
+}
+
+pub struct Output {
+  pub code: String, // ends with a newline
+  pub ast: ast::Ast,
+  pub toc: Vec<ast::TocEntry>,
+  pub frontmatter: BTreeMap<String, String>,
+}
+
+// parses, transforms and compiles `source` to `options.target`
+pub fn compile(source: &str, options: Options) -> Result<Output, Diagnostics> {
+  compile_with(options, |core| core.compile(source))
+}
+
+// transforms and compiles a tree built elsewhere, e.g. by remark through `ast::mdast::from_mdast`
+pub fn compile_ast(ast: ast::Ast, options: Options) -> Result<Output, Diagnostics> {
+  compile_with(options, |core| Ok(core.compile_ast(ast)))
+}
+
+fn compile_with(
+  options: Options,
+  document: impl FnOnce(&mut Core) -> Result<Document, Diagnostic>,
+) -> Result<Output, Diagnostics> {
+  let Options { target, html_mode, markdown, text, latex, parser, layout, transformers, highlighter, debug } = options;
+  let core = Core::new().with_parser_options(parser).with_debug(debug);
+  let mut core = transformers.into_iter().fold(core, Core::use_transformer);
+  let mut document = document(&mut core)?;
+  let content = match target {
+    Target::Markdown => MarkdownCompiler::with_options(markdown).compile(&mut document.ast),
+    Target::Text => TextCompiler::with_options(text).compile(&mut document.ast),
+    Target::Latex => {
+      let standalone = latex.standalone || layout.standalone;
+      LatexCompiler::with_options(LatexOptions { standalone, ..latex }).compile(&mut document.ast)
+    }
+    Target::Html => HtmlCompiler::with_mode(html_mode)
+      .with_highlighter(highlighter)
+      .compile(&mut document.ast),
+    Target::Vue => VueCompiler::new()
+      .with_highlighter(highlighter)
+      .compile(&mut document.ast),
+    Target::Svelte => SvelteCompiler::new()
+      .with_highlighter(highlighter)
+      .compile(&mut document.ast),
+    _ => target.compile(&mut document.ast, html_mode),
+  };
+  let mut code = match target {
+    Target::Html => template::render_page(&document.ast, &content, &layout).map_err(|err| Diagnostic::error(&err))?,
+    _ => content,
+  };
+  if !code.ends_with('\n') {
+    code.push('\n');
+  }
+  let Document { ast, toc, frontmatter } = document;
+  Ok(Output { code, ast, toc, frontmatter })
+}
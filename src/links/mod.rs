@@ -0,0 +1,306 @@
+// ==================
+// scc check-links [paths...], the links and images of the documents must point somewhere e.g.
+//
+//   error[broken-link]: docs/intro.md:4:12: `./setup.md` does not exist
+//   error[broken-anchor]: docs/intro.md:9:1: `api.md#usage`, api.md has no heading #usage
+//
+// relative paths are resolved from the document, `/` paths from the content directory and `#anchors` must be
+// the slug of a heading in the target document. external urls are only checked to be well formed, nothing is
+// fetched so it runs offline.
+//
+use std::{
+  collections::{HashMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  ast::{
+    self,
+    visit::{Visit, Visitor},
+  },
+  build::{is_source, source_files},
+  diagnostics::{Diagnostic, Diagnostics},
+  lint::locator::Locator,
+  parser::{MdxParser, ParserOptions},
+  transformer::{is_relative, Slugger},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+  pub parser: ParserOptions,
+  pub root: Option<PathBuf>, // what `/` links are resolved from, they are not checked without it
+}
+
+// a link or image in a document
+struct LinkTarget {
+  url: String,
+  image: bool,
+  line: usize,
+  column: usize,
+}
+
+// `paths` are files or directories, directories are searched for .md and .mdx files
+pub fn check_links(paths: &[PathBuf], options: &LinkOptions) -> Result<Diagnostics, String> {
+  let mut checker = Checker { options, anchors: HashMap::new() };
+  let mut diagnostics = Diagnostics::new();
+  for path in source_files(paths)? {
+    let file = path.display().to_string();
+    diagnostics.items.extend(checker.check_file(&path).in_file(&file).items);
+  }
+  Ok(diagnostics)
+}
+
+struct Checker<'o> {
+  options: &'o LinkOptions,
+  anchors: HashMap<PathBuf, HashSet<String>>, // heading slugs of the documents read so far
+}
+
+impl Checker<'_> {
+  fn check_file(&mut self, path: &Path) -> Diagnostics {
+    let source = match fs::read_to_string(path) {
+      Ok(source) => source,
+      Err(err) => return Diagnostic::error(&format!("could not read file: {}", err)).into(),
+    };
+    let parser = self.options.parser;
+    let ast = match MdxParser::parse_with(&source, parser) {
+      Ok(ast) => ast,
+      Err(diagnostic) => return diagnostic.into(),
+    };
+    self.anchors.insert(canonical(path), heading_slugs(&ast));
+    let mut diagnostics = Diagnostics::new();
+    for target in targets(&source, &ast) {
+      if let Err((code, message)) = self.check(path, &target) {
+        diagnostics.push(
+          Diagnostic::error(&message)
+            .with_code(code)
+            .at(target.line, target.column),
+        );
+      }
+    }
+    diagnostics
+  }
+
+  fn check(&mut self, document: &Path, target: &LinkTarget) -> Result<(), (&'static str, String)> {
+    let url = target.url.trim();
+    if url.is_empty() {
+      return Ok(()); // empty-link in scc lint
+    }
+    if url.starts_with("//") || !(is_relative(url) || url.starts_with('/') || url.starts_with('#')) {
+      return check_url(url).map_err(|reason| ("malformed-url", format!("`{}` {}", url, reason)));
+    }
+    let (path, fragment) = split_url(url);
+    let file = if path.is_empty() {
+      document.to_path_buf()
+    } else if let Some(path) = path.strip_prefix('/') {
+      match &self.options.root {
+        Some(root) => root.join(decode(path)),
+        None => return Ok(()),
+      }
+    } else {
+      document.parent().unwrap_or(Path::new("")).join(decode(path))
+    };
+    let Some(file) = existing(&file) else {
+      return Err(("broken-link", format!("`{}` does not exist", url)));
+    };
+    match fragment.map(decode) {
+      Some(fragment) if !fragment.is_empty() && !target.image && is_source(&file) => {
+        if self.slugs(&file).contains(&fragment) {
+          return Ok(());
+        }
+        let name = if path.is_empty() {
+          "this document".to_string()
+        } else {
+          path.to_string()
+        };
+        Err((
+          "broken-anchor",
+          format!("`{}`, {} has no heading #{}", url, name, fragment),
+        ))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  // the documents that are linked to but not checked are read once here
+  fn slugs(&mut self, file: &Path) -> &HashSet<String> {
+    let parser = self.options.parser;
+    self.anchors.entry(canonical(file)).or_insert_with(|| {
+      let source = fs::read_to_string(file).unwrap_or_default();
+      MdxParser::parse_with(&source, parser)
+        .map(|ast| heading_slugs(&ast))
+        .unwrap_or_default()
+    })
+  }
+}
+
+// the ids the headings get when compiled, duplicates with -1, -2
+fn heading_slugs(ast: &ast::Ast) -> HashSet<String> {
+  let mut slugger = Slugger::new();
+  let mut slugs = HashSet::new();
+  (|node: &ast::Node| {
+    if let ast::Node::Heading(heading) = node {
+      slugs.extend(heading.id.clone().or_else(|| slugger.heading(heading)));
+    }
+    Visit::Continue
+  })
+  .visit_ast(ast);
+  slugs
+}
+
+fn targets(source: &str, ast: &ast::Ast) -> Vec<LinkTarget> {
+  let mut locator = Locator::new(source);
+  let mut targets = Vec::new();
+  (|node: &ast::Node| {
+    let offset = locator.locate(node);
+    if let ast::Node::Link(link) | ast::Node::Image(link) = node {
+      let (line, column) = locator.position(offset.unwrap_or(locator.cursor()));
+      let image = matches!(node, ast::Node::Image(_));
+      targets.push(LinkTarget { url: link.url.clone(), image, line, column });
+    }
+    Visit::Continue
+  })
+  .visit_ast(ast);
+  targets
+}
+
+// `guide.md#setup?x` -> (`guide.md`, Some(`setup?x`)), the query of `guide.md?x#setup` is dropped
+fn split_url(url: &str) -> (&str, Option<&str>) {
+  let (rest, fragment) = match url.split_once('#') {
+    Some((rest, fragment)) => (rest, Some(fragment)),
+    None => (url, None),
+  };
+  (rest.split('?').next().unwrap_or_default(), fragment)
+}
+
+// the file itself, or the document it is compiled from when it links to the output e.g. guide.html -> guide.md
+fn existing(file: &Path) -> Option<PathBuf> {
+  if file.exists() {
+    return Some(file.to_path_buf());
+  }
+  ["md", "mdx"]
+    .iter()
+    .map(|extension| file.with_extension(extension))
+    .find(|source| file.extension().is_some_and(|extension| extension == "html") && source.is_file())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+  fs::canonicalize(path).unwrap_or(path.to_path_buf())
+}
+
+// %20 -> ' ', invalid escapes are kept as written
+fn decode(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    let escape = text.get(index + 1..index + 3).filter(|_| bytes[index] == b'%');
+    match escape.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+      Some(byte) => {
+        decoded.push(byte);
+        index += 3;
+      }
+      None => {
+        decoded.push(bytes[index]);
+        index += 1;
+      }
+    }
+  }
+  String::from_utf8(decoded).unwrap_or(text.to_string())
+}
+
+// the reason a url is not well formed, only http(s) and mailto have rules
+fn check_url(url: &str) -> Result<(), &'static str> {
+  if url.contains(char::is_whitespace) {
+    return Err("contains whitespace");
+  }
+  let (scheme, rest) = url.split_once(':').unwrap_or(("", url));
+  match scheme.to_lowercase().as_str() {
+    "http" | "https" | "" => {
+      let Some(rest) = rest.strip_prefix("//") else {
+        return Err("is missing the // after the scheme");
+      };
+      let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+      let host = host.rsplit('@').next().unwrap_or_default();
+      let name = host.split(':').next().unwrap_or_default();
+      if name.is_empty() {
+        return Err("has no host");
+      }
+      if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '.' || c == '-' || c == '[' || c == ']')
+      {
+        return Err("has an invalid host");
+      }
+      let port = host[name.len()..].strip_prefix(':');
+      if port.is_some_and(|port| port.parse::<u16>().is_err()) {
+        return Err("has an invalid port");
+      }
+      Ok(())
+    }
+    "mailto" if !rest.contains('@') => Err("has no address"),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::{check_links, check_url, LinkOptions};
+
+  #[test]
+  fn checks_files_and_anchors() {
+    let dir = std::env::temp_dir().join(format!("scc-links-{}", std::process::id()));
+    fs::create_dir_all(dir.join("guide")).unwrap();
+    fs::write(
+      dir.join("guide/setup.md"),
+      "# Setup\n\n## Install\n\n## Install\n\n## See [the docs](x.md)\n",
+    )
+    .unwrap();
+    fs::write(dir.join("logo.png"), "").unwrap();
+    let index =
+      "# Home\n\n[a](guide/setup.md#install-1) [b](guide/setup.html) [c](#home) [h](guide/setup.md#see-the-docs)\n\n\
+      ![logo](/logo.png)\n\n[d](guide/missing.md) [e](guide/setup.md#usage)\n\n[f](#nope) [g](https:/example.com)\n";
+    fs::write(dir.join("index.md"), index).unwrap();
+    let options = LinkOptions { root: Some(dir.clone()), ..LinkOptions::default() };
+    let diagnostics = check_links(&[dir.join("index.md")], &options).unwrap();
+    let found: Vec<_> = diagnostics
+      .items
+      .iter()
+      .map(|diagnostic| {
+        (
+          diagnostic.code.clone().unwrap(),
+          diagnostic.line.unwrap(),
+          diagnostic.column.unwrap(),
+        )
+      })
+      .collect();
+    let expected = [
+      ("broken-link", 7, 1),
+      ("broken-anchor", 7, 23),
+      ("broken-anchor", 9, 1),
+      ("malformed-url", 9, 12),
+    ];
+    let expected: Vec<_> = expected
+      .iter()
+      .map(|(code, line, column)| (code.to_string(), *line, *column))
+      .collect();
+    assert_eq!(found, expected);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn checks_url_syntax() {
+    assert!(check_url("https://example.com:8080/a?b#c").is_ok());
+    assert!(check_url("mailto:me@example.com").is_ok());
+    assert!(check_url("tel:+123").is_ok());
+    assert_eq!(
+      check_url("https:/example.com"),
+      Err("is missing the // after the scheme")
+    );
+    assert_eq!(check_url("https://"), Err("has no host"));
+    assert_eq!(check_url("http://example.com:99999"), Err("has an invalid port"));
+    assert_eq!(check_url("mailto:me"), Err("has no address"));
+  }
+}
@@ -0,0 +1,84 @@
+// ==================
+// source positions for the ast, which has none. the nodes are searched for in the source in document
+// order, so they have to be located in the order a visitor enters them e.g.
+//
+// let mut locator = Locator::new(source);
+// (|node: &ast::Node| {
+//   if let Some(offset) = locator.locate(node) {
+//     let (line, column) = locator.position(offset);
+//   }
+//   Visit::Continue
+// })
+// .visit_ast(&ast);
+//
+use crate::ast;
+
+pub struct Locator<'s> {
+  source: &'s str,
+  cursor: usize, // where the last node found ends
+}
+
+impl<'s> Locator<'s> {
+  pub fn new(source: &'s str) -> Self {
+    Self { source, cursor: 0 }
+  }
+
+  pub fn cursor(&self) -> usize {
+    self.cursor
+  }
+
+  // the offset of the nodes that can be found, headings, links, images and code, None for the others
+  pub fn locate(&mut self, node: &ast::Node) -> Option<usize> {
+    match node {
+      ast::Node::Heading(heading) => self.find(&format!("{} {}", "#".repeat(heading.level), heading.text.trim_end())),
+      ast::Node::Link(link) => self.find(&format!("[{}]({}", link.alt, link.url)),
+      ast::Node::Image(image) => self.find(&format!("![{}]({}", image.alt, image.url)),
+      ast::Node::CodeBlock(_) => self.find_code_block(),
+      ast::Node::InlineCode(inline_code) => self.find(&format!("`{}`", inline_code.code)),
+      _ => None,
+    }
+  }
+
+  // the offset of the next `needle`, the cursor moves past it
+  pub fn find(&mut self, needle: &str) -> Option<usize> {
+    let offset = self.source[self.cursor..].find(needle)? + self.cursor;
+    self.cursor = offset + needle.len();
+    Some(offset)
+  }
+
+  // the offset of the next code block, the cursor moves past its closing fence
+  fn find_code_block(&mut self) -> Option<usize> {
+    let mut lines = lines_from(self.source, self.cursor);
+    let (offset, fence) = lines.find_map(|(offset, line)| {
+      let content = line.trim_start_matches(['>', ' ', '\t']);
+      let marker = content.chars().next().filter(|c| *c == '`' || *c == '~')?;
+      let fence: String = content.chars().take_while(|c| *c == marker).collect();
+      let start = offset + line.len() - content.len();
+      (fence.len() >= 3).then_some((start, fence))
+    })?;
+    let closing = lines.find(|(_, line)| line.trim_start_matches(['>', ' ', '\t']).starts_with(&fence));
+    self.cursor = closing.map_or(self.source.len(), |(offset, line)| offset + line.len());
+    Some(offset)
+  }
+
+  pub fn position(&self, offset: usize) -> (usize, usize) {
+    position(self.source, offset)
+  }
+}
+
+// (offset, line) of the lines from `start`, with their newline
+pub fn lines_from(source: &str, start: usize) -> impl Iterator<Item = (usize, &str)> {
+  source[start..].split_inclusive('\n').scan(start, |offset, line| {
+    let line_offset = *offset;
+    *offset += line.len();
+    Some((line_offset, line))
+  })
+}
+
+// 1-based line and column of a byte offset
+pub fn position(source: &str, offset: usize) -> (usize, usize) {
+  let before = &source[..offset];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+  (line, column)
+}
@@ -0,0 +1,512 @@
+// ==================
+// scc lint [files...], rules run over the parsed documents e.g.
+//
+//   warning[code-language]: docs/intro.md:12:1: code block without a language
+//
+// each rule is off, warn or error, set in [lint] in scc.toml e.g. `heading-increment = "off"`,
+// and turned off in the document itself with
+//
+//   <!-- scc-disable bare-url -->               until <!-- scc-enable bare-url -->, every rule without names
+//   <!-- scc-disable-next-line empty-link -->
+//
+// --fix rewrites the problems that have one obvious fix: trailing-whitespace and bare-url.
+//
+pub mod locator;
+
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  ast::{
+    self,
+    visit::{Visit, Visitor},
+  },
+  build::source_files,
+  diagnostics::{Diagnostic, Diagnostics},
+  parser::{MdxParser, ParserOptions},
+  transformer::heading_slug,
+};
+
+use locator::{lines_from, position, Locator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  Off,
+  Warn,  // reported, the command still succeeds
+  Error, // reported and the command fails
+}
+
+impl Level {
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "off" => Some(Level::Off),
+      "warn" => Some(Level::Warn),
+      "error" => Some(Level::Error),
+      _ => None,
+    }
+  }
+}
+
+// every rule and its level when [lint] does not set it
+pub const RULES: [(&str, Level); 8] = [
+  ("heading-increment", Level::Warn),    // ## after # is fine, ### after # is not
+  ("duplicate-heading", Level::Error),   // two headings with the same slug, the second can not be linked to
+  ("empty-link", Level::Error),          // [](url) or [text]()
+  ("image-alt", Level::Warn),            // ![](image.png)
+  ("code-language", Level::Warn),        // ``` without a language
+  ("trailing-whitespace", Level::Warn),  // fixable
+  ("bare-url", Level::Warn),             // https://example.com outside of a link, fixable
+  ("undefined-reference", Level::Error), // [text][ref] without a [ref]: url
+];
+
+// [lint] on top of the defaults
+pub fn levels(config: &BTreeMap<String, String>) -> Result<BTreeMap<&'static str, Level>, String> {
+  let mut levels: BTreeMap<&'static str, Level> = RULES.into_iter().collect();
+  for (name, level) in config {
+    let rule = RULES
+      .iter()
+      .map(|(rule, _)| *rule)
+      .find(|rule| rule == name)
+      .ok_or(format!("unknown lint rule `{}`", name))?;
+    let level = Level::from_name(level).ok_or(format!(
+      "unknown level `{}` for `{}`, expected off, warn or error",
+      level, name
+    ))?;
+    levels.insert(rule, level);
+  }
+  Ok(levels)
+}
+
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+  pub levels: BTreeMap<&'static str, Level>,
+  pub parser: ParserOptions,
+  pub fix: bool, // rewrite the files with the fixable problems fixed
+}
+
+impl Default for LintOptions {
+  fn default() -> Self {
+    Self { levels: RULES.into_iter().collect(), parser: ParserOptions::default(), fix: false }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+  pub rule: &'static str,
+  pub message: String,
+  pub line: usize,
+  pub column: usize,
+  fix: Option<Fix>,
+}
+
+// replaces source[start..end]
+#[derive(Debug, Clone)]
+struct Fix {
+  start: usize,
+  end: usize,
+  replacement: String,
+}
+
+// `paths` are files or directories, directories are searched for .md and .mdx files
+pub fn lint(paths: &[PathBuf], options: &LintOptions) -> Result<Diagnostics, String> {
+  let mut diagnostics = Diagnostics::new();
+  for path in source_files(paths)? {
+    let file = path.display().to_string();
+    diagnostics.items.extend(lint_file(&path, options).in_file(&file).items);
+  }
+  Ok(diagnostics)
+}
+
+fn lint_file(path: &Path, options: &LintOptions) -> Diagnostics {
+  let mut source = match fs::read_to_string(path) {
+    Ok(source) => source,
+    Err(err) => return Diagnostic::error(&format!("could not read file: {}", err)).into(),
+  };
+  if options.fix {
+    let fixed = fix_source(&source, options);
+    if fixed != source {
+      if let Err(err) = fs::write(path, &fixed) {
+        return Diagnostic::error(&format!("could not write file: {}", err)).into();
+      }
+      source = fixed;
+    }
+  }
+  lint_source(&source, options)
+}
+
+pub fn lint_source(source: &str, options: &LintOptions) -> Diagnostics {
+  let problems = match problems(source, options) {
+    Ok(problems) => problems,
+    Err(errors) => return errors,
+  };
+  let diagnostics = problems.into_iter().map(|problem| {
+    let diagnostic = match options.levels.get(problem.rule) {
+      Some(Level::Error) => Diagnostic::error(&problem.message),
+      _ => Diagnostic::warning(&problem.message),
+    };
+    diagnostic.with_code(problem.rule).at(problem.line, problem.column)
+  });
+  Diagnostics { items: diagnostics.collect() }
+}
+
+// the source with every fixable problem fixed, as is when it does not parse
+pub fn fix_source(source: &str, options: &LintOptions) -> String {
+  let mut fixes: Vec<Fix> = match problems(source, options) {
+    Ok(problems) => problems.into_iter().filter_map(|problem| problem.fix).collect(),
+    Err(_) => return source.to_string(),
+  };
+  fixes.sort_by_key(|fix| fix.start);
+  let mut fixed = source.to_string();
+  let mut end = usize::MAX;
+  for fix in fixes.iter().rev() {
+    if fix.end > end {
+      continue; // overlaps the fix after it
+    }
+    fixed.replace_range(fix.start..fix.end, &fix.replacement);
+    end = fix.start;
+  }
+  fixed
+}
+
+// the problems of the enabled rules that are not turned off by a comment, in source order
+fn problems(source: &str, options: &LintOptions) -> Result<Vec<Problem>, Diagnostics> {
+  let parser = options.parser;
+  let ast = MdxParser::parse_with(source, parser)?;
+  let mut rules = Rules::new(source);
+  rules.visit_ast(&ast);
+  let mut problems = rules.problems;
+  problems.extend(trailing_whitespace(source));
+  problems.extend(undefined_references(source));
+  let disabled = disabled_lines(source);
+  problems.retain(|problem| {
+    let enabled = options
+      .levels
+      .get(problem.rule)
+      .is_some_and(|level| *level != Level::Off);
+    let silenced = disabled
+      .get(problem.line - 1)
+      .is_some_and(|rules| rules.contains("*") || rules.contains(problem.rule));
+    enabled && !silenced
+  });
+  problems.sort_by_key(|problem| (problem.line, problem.column));
+  Ok(problems)
+}
+
+// ==================
+// rules over the ast
+//
+struct Rules<'s> {
+  locator: Locator<'s>,
+  heading_level: usize,
+  slugs: HashSet<String>,
+  problems: Vec<Problem>,
+}
+
+impl<'s> Rules<'s> {
+  fn new(source: &'s str) -> Self {
+    Self { locator: Locator::new(source), heading_level: 0, slugs: HashSet::new(), problems: Vec::new() }
+  }
+
+  fn report(&mut self, rule: &'static str, message: String, offset: Option<usize>) {
+    let (line, column) = self.locator.position(offset.unwrap_or(self.locator.cursor()));
+    self.problems.push(Problem { rule, message, line, column, fix: None });
+  }
+
+  fn heading(&mut self, heading: &ast::Heading, offset: Option<usize>) {
+    if self.heading_level > 0 && heading.level > self.heading_level + 1 {
+      let message = format!("heading level jumps from h{} to h{}", self.heading_level, heading.level);
+      self.report("heading-increment", message, offset);
+    }
+    self.heading_level = heading.level;
+    let slug = heading_slug(heading);
+    if !slug.is_empty() && !self.slugs.insert(slug.clone()) {
+      let message = format!("duplicate heading, #{} goes to the first one", slug);
+      self.report("duplicate-heading", message, offset);
+    }
+  }
+
+  fn link(&mut self, link: &ast::Link, offset: Option<usize>) {
+    if link.url.trim().is_empty() {
+      self.report("empty-link", "link without a url".to_string(), offset);
+    }
+    if link.alt.trim().is_empty() {
+      self.report("empty-link", "link without text".to_string(), offset);
+    }
+  }
+
+  fn image(&mut self, image: &ast::Link, offset: Option<usize>) {
+    if image.alt.trim().is_empty() {
+      self.report("image-alt", "image without alt text".to_string(), offset);
+    }
+  }
+
+  fn code_block(&mut self, code_block: &ast::CodeBlock, offset: Option<usize>) {
+    if code_block.language.is_empty() {
+      self.report("code-language", "code block without a language".to_string(), offset);
+    }
+  }
+
+  // <https://example.com> and `[ref]: https://example.com` are not bare
+  fn text(&mut self, text: &ast::Text) {
+    for (index, url) in urls(&text.text) {
+      let before = text.text[..index].trim_end();
+      if before.ends_with('<') || before.ends_with("]:") {
+        continue;
+      }
+      let Some(offset) = self.locator.find(url) else {
+        continue;
+      };
+      let (line, column) = self.locator.position(offset);
+      let fix = Fix { start: offset, end: offset + url.len(), replacement: format!("[{}]({})", url, url) };
+      let message = format!("bare url, write it as [{}]({})", url, url);
+      self
+        .problems
+        .push(Problem { rule: "bare-url", message, line, column, fix: Some(fix) });
+    }
+  }
+}
+
+impl Visitor for Rules<'_> {
+  // inline code is located as well, so the urls in it are not taken for the bare ones after it
+  fn enter(&mut self, node: &ast::Node) -> Visit {
+    let offset = self.locator.locate(node);
+    match node {
+      ast::Node::Heading(heading) => self.heading(heading, offset),
+      ast::Node::Link(link) => self.link(link, offset),
+      ast::Node::Image(image) => self.image(image, offset),
+      ast::Node::CodeBlock(code_block) => self.code_block(code_block, offset),
+      ast::Node::Text(text) => self.text(text),
+      _ => {}
+    }
+    Visit::Continue
+  }
+}
+
+// (index, url) of every http and https url in `text`
+fn urls(text: &str) -> Vec<(usize, &str)> {
+  let mut urls = Vec::new();
+  let mut start = 0;
+  while let Some(index) = text[start..].find("http").map(|index| start + index) {
+    let rest = &text[index..];
+    let end = rest
+      .find(|c: char| c.is_whitespace() || "<>()[]\"'`".contains(c))
+      .unwrap_or(rest.len());
+    let url = rest[..end].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+    if url.starts_with("https://") || url.starts_with("http://") {
+      urls.push((index, url));
+    }
+    start = index + end.max(4);
+  }
+  urls
+}
+
+// ==================
+// rules over the source
+//
+fn trailing_whitespace(source: &str) -> Vec<Problem> {
+  let mut problems = Vec::new();
+  for (offset, line) in lines_from(source, 0) {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let trimmed = line.trim_end_matches([' ', '\t']);
+    if trimmed.len() == line.len() {
+      continue;
+    }
+    let (line_number, column) = position(source, offset + trimmed.len());
+    let fix = Fix { start: offset + trimmed.len(), end: offset + line.len(), replacement: String::new() };
+    problems.push(Problem {
+      rule: "trailing-whitespace",
+      message: "trailing whitespace".to_string(),
+      line: line_number,
+      column,
+      fix: Some(fix),
+    });
+  }
+  problems
+}
+
+// [text][ref] and [ref][] need a `[ref]: url` line, the labels are case insensitive
+fn undefined_references(source: &str) -> Vec<Problem> {
+  let prose = prose_lines(source);
+  let definitions: HashSet<String> = prose
+    .iter()
+    .filter_map(|(_, line)| {
+      let line = line.trim_start().strip_prefix('[')?;
+      let (label, rest) = line.split_once(']')?;
+      rest.starts_with(':').then(|| normalize_label(label))
+    })
+    .collect();
+  let mut problems = Vec::new();
+  for (offset, line) in &prose {
+    let mut start = 0;
+    while let Some(open) = line[start..].find('[').map(|index| start + index) {
+      start = open + 1;
+      let Some((text, rest)) = line[open + 1..].split_once(']') else {
+        break;
+      };
+      let Some(rest) = rest.strip_prefix('[') else {
+        continue;
+      };
+      let Some((label, _)) = rest.split_once(']') else {
+        continue;
+      };
+      let label = if label.is_empty() { text } else { label };
+      if text.contains('[') || definitions.contains(&normalize_label(label)) {
+        continue;
+      }
+      let (line, column) = position(source, offset + open);
+      let message = format!("reference `{}` is not defined", label);
+      problems.push(Problem { rule: "undefined-reference", message, line, column, fix: None });
+    }
+  }
+  problems
+}
+
+fn normalize_label(label: &str) -> String {
+  label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// the lines outside of code blocks with inline code blanked out, (offset, line)
+fn prose_lines(source: &str) -> Vec<(usize, String)> {
+  let mut lines = Vec::new();
+  let mut fence: Option<String> = None;
+  for (offset, line) in lines_from(source, 0) {
+    let content = line.trim_start_matches(['>', ' ', '\t']);
+    let marker: String = content.chars().take_while(|c| *c == '`' || *c == '~').collect();
+    match &fence {
+      Some(open) if content.starts_with(open.as_str()) => fence = None,
+      Some(_) => {}
+      None if marker.len() >= 3 => fence = Some(marker),
+      None => lines.push((offset, blank_inline_code(line))),
+    }
+  }
+  lines
+}
+
+fn blank_inline_code(line: &str) -> String {
+  let mut inside = false;
+  line
+    .chars()
+    .map(|c| {
+      if c == '`' {
+        inside = !inside;
+        return c;
+      }
+      if inside {
+        ' '
+      } else {
+        c
+      }
+    })
+    .collect()
+}
+
+// ==================
+// <!-- scc-disable --> comments, the rules turned off on each line, `*` for every rule
+//
+fn disabled_lines(source: &str) -> Vec<HashSet<String>> {
+  let mut lines = Vec::new();
+  let mut disabled: HashSet<String> = HashSet::new();
+  let mut next_line: HashSet<String> = HashSet::new();
+  for line in source.lines() {
+    let mut current = disabled.clone();
+    current.extend(std::mem::take(&mut next_line));
+    if let Some((directive, rules)) = directive(line) {
+      match directive {
+        "scc-disable" => disabled.extend(rules),
+        "scc-disable-next-line" => next_line = rules,
+        _ if rules.contains("*") => disabled.clear(),
+        _ => disabled.retain(|rule| !rules.contains(rule)),
+      }
+    }
+    lines.push(current);
+  }
+  lines
+}
+
+// <!-- scc-disable a, b --> -> ("scc-disable", {a, b})
+fn directive(line: &str) -> Option<(&str, HashSet<String>)> {
+  let comment = line.trim().strip_prefix("<!--")?.strip_suffix("-->")?;
+  let mut words = comment.split([' ', ',']).filter(|word| !word.is_empty());
+  let directive = words.next()?;
+  if !matches!(directive, "scc-disable" | "scc-enable" | "scc-disable-next-line") {
+    return None;
+  }
+  let mut rules: HashSet<String> = words.map(String::from).collect();
+  if rules.is_empty() {
+    rules.insert("*".to_string());
+  }
+  Some((directive, rules))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::{fix_source, levels, lint_source, LintOptions};
+
+  fn codes(source: &str, options: &LintOptions) -> Vec<(String, usize, usize)> {
+    lint_source(source, options)
+      .items
+      .into_iter()
+      .map(|diagnostic| {
+        (
+          diagnostic.code.unwrap(),
+          diagnostic.line.unwrap(),
+          diagnostic.column.unwrap(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn reports_rules_at_their_position() {
+    let source =
+      "# A\n\n### B\n\n## A\n\n[](https://x.com) see `https://code.com` https://y.com\n\n```\nx\n```\n\n[a][b]\n";
+    let expected = [
+      ("heading-increment", 3, 1),
+      ("duplicate-heading", 5, 1),
+      ("empty-link", 7, 1),
+      ("bare-url", 7, 42),
+      ("code-language", 9, 1),
+      ("undefined-reference", 13, 1),
+    ];
+    let expected: Vec<_> = expected
+      .iter()
+      .map(|(code, line, column)| (code.to_string(), *line, *column))
+      .collect();
+    assert_eq!(codes(source, &LintOptions::default()), expected);
+    // the slug is of the text without the link
+    assert_eq!(
+      codes("# See [the docs](x.md)\n\n## See the docs\n", &LintOptions::default()),
+      [("duplicate-heading".to_string(), 3, 1)]
+    );
+  }
+
+  #[test]
+  fn disable_comments_and_levels() {
+    let source = "<!-- scc-disable-next-line bare-url -->\nhttps://a.com\n\n<!-- scc-disable -->\n```\nx\n```\n<!-- scc-enable -->\n\n[a][b]\n\n[b]: https://b.com\n\n```\ny\n```\n";
+    assert_eq!(
+      codes(source, &LintOptions::default()),
+      [("code-language".to_string(), 14, 1)]
+    );
+    let config = BTreeMap::from([("code-language".to_string(), "off".to_string())]);
+    let options = LintOptions { levels: levels(&config).unwrap(), ..LintOptions::default() };
+    assert!(codes(source, &options).is_empty());
+    let config = BTreeMap::from([("code-languages".to_string(), "off".to_string())]);
+    assert_eq!(levels(&config).unwrap_err(), "unknown lint rule `code-languages`");
+  }
+
+  #[test]
+  fn fixes_whitespace_and_bare_urls() {
+    let source = "# A  \n\nsee https://a.com. and [b](https://b.com)\t\n";
+    let fixed = fix_source(source, &LintOptions::default());
+    assert_eq!(
+      fixed,
+      "# A\n\nsee [https://a.com](https://a.com). and [b](https://b.com)\n"
+    );
+    assert!(lint_source(&fixed, &LintOptions::default()).items.is_empty());
+  }
+}
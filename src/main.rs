@@ -1,70 +1,561 @@
-mod ast;
 mod cli;
-mod compiler;
-mod core;
-mod format;
-mod highlighter;
-mod parser;
-mod transformer;
 
+use std::{
+  env, fs,
+  io::{self, IsTerminal, Read, Write},
+  path::{Path, PathBuf},
+  process,
+};
+
+use clap::ArgMatches;
 use cli::command_line;
-use parser::MdxParser;
+use scc::{
+  ast,
+  build::{self, BuildOptions},
+  config::Config,
+  diagnostics::DiagnosticFormat,
+  fmt::{self, FmtOptions},
+  format,
+  links::{self, LinkOptions},
+  lint::{self, LintOptions},
+  serve::{self, ServeOptions},
+  watch, AnsiCompiler, AnsiOptions, Core, Diagnostic, Diagnostics, HtmlMode, LayoutOptions, MdxParser, Target,
+  TextCompiler,
+};
 
 // ========================
-// Compilte to HTML
+// scc compile, run, run-jsx, run-vue, run-svelte
+//
+// `-` reads the document from stdin, the output goes to stdout unless there is `--output`
 //
-fn run_html(source: String) {
-  let mut core = core::Core::default();
-  core.compile(&source);
+const EXIT_DIAGNOSTICS: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+struct RunOptions {
+  file: String,
+  target: Target,
+  html_mode: HtmlMode,
+  layout: LayoutOptions,
+  output: Option<PathBuf>,
+  debug: bool,
+  config: Config,
+  mdast: bool, // the file is a unified/remark tree as json instead of markdown
+}
+
+fn run(options: &RunOptions) -> Result<(), Diagnostics> {
+  let name = if options.file == "-" {
+    "<stdin>"
+  } else {
+    options.file.as_str()
+  };
+  let source = read_input(&options.file).map_err(|err| Diagnostic::error(&err).in_file(name))?;
+  let mut compile_options = options
+    .config
+    .options(options.target)
+    .map_err(|err| Diagnostic::error(&err))?;
+  compile_options.html_mode = options.html_mode;
+  compile_options.layout = options.layout.clone();
+  compile_options.debug = options.debug;
+  let output = match options.mdast {
+    true => {
+      let ast = serde_json::from_str(&source)
+        .map_err(|err| json_error(&err))
+        .and_then(|tree| ast::mdast::from_mdast(&tree))
+        .map_err(|err| err.in_file(name))?;
+      scc::compile_ast(ast, compile_options)
+    }
+    false => scc::compile(&source, compile_options),
+  };
+  let output = output.map_err(|errors| errors.in_file(name))?;
+  write_output(options.output.as_deref(), &output.code).map_err(Diagnostics::from)
+}
+
+// serde_json puts the position at the end of the message, it goes to the line and column of the diagnostic
+fn json_error(err: &serde_json::Error) -> Diagnostic {
+  let message = err.to_string();
+  let message = message.trim_end_matches(&format!(" at line {} column {}", err.line(), err.column()));
+  Diagnostic::error(message).at(err.line(), err.column())
+}
+
+fn write_output(output: Option<&Path>, code: &str) -> Result<(), Diagnostic> {
+  match output {
+    Some(path) => build::write_file(path, code.as_bytes()).map_err(|err| Diagnostic::error(&err)),
+    None => io::stdout()
+      .write_all(code.as_bytes())
+      .map_err(|err| Diagnostic::error(&format!("could not write to stdout: {}", err))),
+  }
 }
 
 // ========================
-// Compilte to JSX
+// scc inspect, the parsed ast as a tree or as json, `--stages` adds the ast after every transformer
 //
+fn inspect(file: &str, config: &Config, stages: bool, json: Option<&str>) -> Result<String, Diagnostic> {
+  let name = if file == "-" { "<stdin>" } else { file };
+  let source = read_input(file).map_err(|err| Diagnostic::error(&err).in_file(name))?;
+  let options = config.options(Target::Html).map_err(|err| Diagnostic::error(&err))?;
+  let core = Core::new().with_parser_options(options.parser);
+  let mut core = options.transformers.into_iter().fold(core, Core::use_transformer);
+  let mut stages = match stages {
+    true => core.stages(&source),
+    false => core.parse(&source).map(|ast| vec![("parse".to_string(), ast)]),
+  }
+  .map_err(|diagnostic| diagnostic.in_file(name))?;
+  let to_json = match json {
+    Some("mdast") => ast::mdast::to_mdast,
+    _ => format::ast_to_json,
+  };
+  if json.is_some() && stages.len() == 1 {
+    return Ok(format!("{:#}\n", to_json(&stages.remove(0).1)));
+  }
+  if json.is_some() {
+    let stages: Vec<serde_json::Value> = stages
+      .iter()
+      .map(|(stage, ast)| serde_json::json!({ "stage": stage, "ast": to_json(ast) }))
+      .collect();
+    return Ok(format!("{:#}\n", serde_json::Value::Array(stages)));
+  }
+  if stages.len() == 1 {
+    return Ok(stages.remove(0).1.to_string());
+  }
+  let stages: Vec<String> = stages
+    .iter()
+    .map(|(stage, ast)| format!("== {}\n{}", stage, ast))
+    .collect();
+  Ok(stages.join("\n"))
+}
 
-fn run_jsx(source: String) {
-  todo!("Not implemented yet");
+fn inspect_command(matches: &ArgMatches) {
+  let file = matches.get_one::<String>("file").unwrap();
+  let output = get_path(matches, "output");
+  let config = load_config(matches);
+  let result = inspect(
+    file,
+    &config,
+    matches.get_flag("stages"),
+    matches.get_one::<String>("json").map(String::as_str),
+  )
+  .and_then(|tree| write_output(output.as_deref(), &tree));
+  if let Err(diagnostic) = result {
+    Diagnostics::from(diagnostic).print(diagnostic_format(matches));
+    process::exit(EXIT_DIAGNOSTICS);
+  }
 }
+
+// the counts of the text target
+fn stats(file: &str, config: &Config, json: bool) -> Result<String, Diagnostic> {
+  let name = if file == "-" { "<stdin>" } else { file };
+  let source = read_input(file).map_err(|err| Diagnostic::error(&err).in_file(name))?;
+  let parser = config.parser_options();
+  let ast = MdxParser::parse_with(&source, parser).map_err(|diagnostic| diagnostic.in_file(name))?;
+  let (_, stats) = TextCompiler::new().compile_with_stats(&ast);
+  if json {
+    return Ok(format!("{:#}\n", serde_json::json!(stats)));
+  }
+  let rows = [
+    ("words", stats.words.to_string()),
+    ("characters", stats.characters.to_string()),
+    ("reading time", format!("{} min", stats.reading_time)),
+    ("headings", stats.headings.to_string()),
+    ("links", stats.links.to_string()),
+    ("images", stats.images.to_string()),
+    ("code blocks", stats.code_blocks.to_string()),
+  ];
+  Ok(
+    rows
+      .iter()
+      .map(|(name, value)| format!("{:<14}{}\n", name, value))
+      .collect(),
+  )
+}
+
+fn stats_command(matches: &ArgMatches) {
+  let file = matches.get_one::<String>("file").unwrap();
+  let output = get_path(matches, "output");
+  let config = load_config(matches);
+  let result = stats(file, &config, matches.get_flag("json")).and_then(|stats| write_output(output.as_deref(), &stats));
+  if let Err(diagnostic) = result {
+    Diagnostics::from(diagnostic).print(diagnostic_format(matches));
+    process::exit(EXIT_DIAGNOSTICS);
+  }
+}
+
 // ========================
-// Compilte to Vue
+// scc view, colors and links only when stdout is a terminal, NO_COLOR turns the colors off
 //
+fn view_command(matches: &ArgMatches) {
+  let file = matches.get_one::<String>("file").unwrap();
+  let config = load_config(matches);
+  let terminal = io::stdout().is_terminal();
+  let size = terminal_size();
+  let options = AnsiOptions {
+    width: matches
+      .get_one::<usize>("width")
+      .copied()
+      .or(env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()))
+      .or(size.map(|(_, columns)| columns))
+      .unwrap_or(80),
+    color: terminal && env::var("NO_COLOR").map_or(true, |value| value.is_empty()),
+    hyperlinks: terminal,
+  };
+  let rendered = view(file, &config, options);
+  let text = match rendered {
+    Ok(text) => text,
+    Err(diagnostic) => {
+      Diagnostics::from(diagnostic).print(diagnostic_format(matches));
+      process::exit(EXIT_DIAGNOSTICS);
+    }
+  };
+  let taller = size.is_some_and(|(rows, _)| text.lines().count() >= rows);
+  if terminal && taller && !matches.get_flag("no-pager") && page(&text).is_ok() {
+    return;
+  }
+  let _ = write_output(None, &text);
+}
+
+fn view(file: &str, config: &Config, options: AnsiOptions) -> Result<String, Diagnostic> {
+  let name = if file == "-" { "<stdin>" } else { file };
+  let source = read_input(file).map_err(|err| Diagnostic::error(&err).in_file(name))?;
+  // the transformers mark the notation lines of the code blocks
+  let compile_options = config.options(Target::Html).map_err(|err| Diagnostic::error(&err))?;
+  let core = Core::new().with_parser_options(compile_options.parser);
+  let mut core = compile_options
+    .transformers
+    .into_iter()
+    .fold(core, Core::use_transformer);
+  let mut document = core.compile(&source).map_err(|diagnostic| diagnostic.in_file(name))?;
+  // the terminal colors code with the built-in palette when scc.toml sets no theme
+  let highlighter = compile_options.highlighter.unwrap_or_default();
+  Ok(
+    AnsiCompiler::with_options(options)
+      .with_highlighter(Some(highlighter))
+      .compile(&mut document.ast),
+  )
+}
+
+// (rows, columns) of the controlling terminal
+fn terminal_size() -> Option<(usize, usize)> {
+  let tty = fs::File::open("/dev/tty").ok()?;
+  let output = process::Command::new("stty").arg("size").stdin(tty).output().ok()?;
+  let size = String::from_utf8(output.stdout).ok()?;
+  let mut size = size.split_whitespace().map(|number| number.parse::<usize>().ok());
+  Some((size.next()??, size.next()??))
+}
+
+// $PAGER or less, -R keeps the colors and links
+fn page(text: &str) -> Result<(), String> {
+  let pager = env::var("PAGER").unwrap_or("less -R".to_string());
+  let mut words = pager.split_whitespace();
+  let program = words.next().ok_or("PAGER is empty")?;
+  let mut child = process::Command::new(program)
+    .args(words)
+    .stdin(process::Stdio::piped())
+    .spawn()
+    .map_err(|err| err.to_string())?;
+  if let Some(mut stdin) = child.stdin.take() {
+    // the pager may be closed before reading everything
+    let _ = stdin.write_all(text.as_bytes());
+  }
+  child.wait().map_err(|err| err.to_string())?;
+  Ok(())
+}
+
+// ========================
+// scc fmt, `-` formats stdin to stdout, the files are rewritten unless there is --check
 //
+fn fmt_command(matches: &ArgMatches) {
+  let format = diagnostic_format(matches);
+  let config = load_config(matches);
+  let options = FmtOptions {
+    markdown: config.markdown_options().unwrap_or_default(),
+    parser: config.parser_options(),
+    check: matches.get_flag("check"),
+  };
+  let paths: Vec<PathBuf> = match matches.get_many::<String>("files") {
+    Some(files) => files.map(PathBuf::from).collect(),
+    None => vec![config.src.clone().unwrap_or(PathBuf::from("."))],
+  };
+  if paths == [PathBuf::from("-")] {
+    let formatted = read_input("-")
+      .map_err(|err| Diagnostics::from(Diagnostic::error(&err)))
+      .and_then(|source| fmt::format_source(&source, options.markdown, options.parser))
+      .map_err(|errors| errors.in_file("<stdin>"))
+      .and_then(|formatted| write_output(None, &formatted).map_err(Diagnostics::from));
+    if let Err(diagnostics) = formatted {
+      diagnostics.print(format);
+      process::exit(EXIT_DIAGNOSTICS);
+    }
+    return;
+  }
+  match fmt::fmt(&paths, &options) {
+    Ok(summary) => {
+      let diagnostics = summary.diagnostics(options.check);
+      diagnostics.print(format);
+      if !diagnostics.items.is_empty() {
+        process::exit(EXIT_DIAGNOSTICS);
+      }
+    }
+    Err(err) => {
+      Diagnostics::from(Diagnostic::error(&err)).print(format);
+      process::exit(EXIT_DIAGNOSTICS);
+    }
+  }
+}
 
-fn run_vue(source: String) {
-  todo!("Not implemented yet");
+// scc lint, `-` lints stdin and --fix only rewrites files, fails when a rule at the error level is broken
+fn lint_command(matches: &ArgMatches) {
+  let format = diagnostic_format(matches);
+  let config = load_config(matches);
+  let options = LintOptions {
+    levels: config.lint_levels().unwrap_or_default(),
+    parser: config.parser_options(),
+    fix: matches.get_flag("fix"),
+  };
+  let paths: Vec<PathBuf> = match matches.get_many::<String>("files") {
+    Some(files) => files.map(PathBuf::from).collect(),
+    None => vec![config.src.clone().unwrap_or(PathBuf::from("."))],
+  };
+  let diagnostics = if paths == [PathBuf::from("-")] {
+    match read_input("-") {
+      Ok(source) => lint::lint_source(&source, &options),
+      Err(err) => Diagnostic::error(&err).into(),
+    }
+    .in_file("<stdin>")
+  } else {
+    lint::lint(&paths, &options).unwrap_or_else(|err| Diagnostic::error(&err).into())
+  };
+  diagnostics.print(format);
+  if diagnostics.has_errors() {
+    process::exit(EXIT_DIAGNOSTICS);
+  }
+}
+
+// scc check-links, `/` links are resolved from `src` in scc.toml or the only directory given
+fn check_links_command(matches: &ArgMatches) {
+  let format = diagnostic_format(matches);
+  let config = load_config(matches);
+  let paths: Vec<PathBuf> = match matches.get_many::<String>("files") {
+    Some(files) => files.map(PathBuf::from).collect(),
+    None => vec![config.src.clone().unwrap_or(PathBuf::from("."))],
+  };
+  let root = config.src.clone().or(match paths.as_slice() {
+    [dir] if dir.is_dir() => Some(dir.clone()),
+    _ => None,
+  });
+  let options = LinkOptions { parser: config.parser_options(), root };
+  let diagnostics = links::check_links(&paths, &options).unwrap_or_else(|err| Diagnostic::error(&err).into());
+  diagnostics.print(format);
+  if diagnostics.has_errors() {
+    process::exit(EXIT_DIAGNOSTICS);
+  }
+}
+
+fn read_input(file: &str) -> Result<String, String> {
+  if file == "-" {
+    let mut source = String::new();
+    io::stdin()
+      .read_to_string(&mut source)
+      .map_err(|err| format!("could not read stdin: {}", err))?;
+    return Ok(source);
+  }
+  fs::read_to_string(file).map_err(|err| format!("could not read file: {}", err))
+}
+
+// `target` is set by run, run-jsx, run-vue and run-svelte, compile reads it from --target or scc.toml
+fn run_command(matches: &ArgMatches, target: Option<Target>) {
+  let file = matches.get_one::<String>("file").unwrap().clone();
+  let format = diagnostic_format(matches);
+  let config = load_config(matches);
+  let target = target
+    .or_else(|| {
+      matches
+        .try_get_one::<String>("target")
+        .ok()
+        .flatten()
+        .and_then(|name| Target::from_name(name))
+    })
+    .or(config.target().ok().flatten())
+    .unwrap_or(Target::Html);
+  let html_mode = html_mode(matches, &config);
+  let file_dir = Path::new(&file).parent().unwrap_or(Path::new(".")).to_path_buf();
+  let layout = layout_options(matches, &config, file_dir.join("layouts"));
+  let output = matches.get_one::<String>("output").map(PathBuf::from);
+  let debug = matches.get_flag("debug");
+  let mdast = matches
+    .try_get_one::<String>("from")
+    .ok()
+    .flatten()
+    .is_some_and(|from| from == "mdast");
+  let options = RunOptions { file, target, html_mode, layout, output, debug, config, mdast };
+  let watch = matches
+    .try_get_one::<bool>("watch")
+    .ok()
+    .flatten()
+    .copied()
+    .unwrap_or(false);
+  if !watch {
+    if let Err(diagnostics) = run(&options) {
+      diagnostics.print(format);
+      process::exit(EXIT_DIAGNOSTICS);
+    }
+    return;
+  }
+  if options.file == "-" {
+    eprintln!("error: --watch needs a file, not stdin");
+    process::exit(EXIT_USAGE);
+  }
+  let compile = || {
+    if let Err(diagnostics) = run(&options) {
+      diagnostics.print(format);
+    }
+  };
+  if let Err(err) = watch::watch_file(Path::new(&options.file), &options.layout, compile) {
+    Diagnostics::from(Diagnostic::error(&err)).print(format);
+    process::exit(EXIT_DIAGNOSTICS);
+  }
 }
-fn main() {
-  let mdx = r#"
-# Hello World
 
-Hello, My name is **Yazalde Filimone**. I'm a **Software Engineer** and I love **Rust**.
-Do you like **Rust**?
+// --config, otherwise the closest scc.toml, otherwise the defaults
+fn load_config(matches: &ArgMatches) -> Config {
+  let config = match get_path(matches, "config") {
+    Some(path) => Config::load(&path),
+    None => Config::discover(Path::new(".")).map(Option::unwrap_or_default),
+  };
+  config.unwrap_or_else(|err| {
+    Diagnostics::from(Diagnostic::error(&err)).print(diagnostic_format(matches));
+    process::exit(EXIT_DIAGNOSTICS);
+  })
+}
 
+// cli flags first, then [layout] in scc.toml
+fn layout_options(matches: &ArgMatches, config: &Config, layouts_dir: PathBuf) -> LayoutOptions {
+  let standalone = matches.try_get_one::<bool>("standalone").ok().flatten().copied();
+  let stylesheets: Vec<String> = matches
+    .try_get_many::<String>("stylesheet")
+    .ok()
+    .flatten()
+    .unwrap_or_default()
+    .cloned()
+    .collect();
+  LayoutOptions {
+    template: get_path(matches, "template").or(config.layout.template.clone()),
+    standalone: standalone == Some(true) || config.layout.standalone.unwrap_or(false),
+    layouts_dir: get_path(matches, "layouts")
+      .or(config.layout.layouts.clone())
+      .unwrap_or(layouts_dir),
+    stylesheets: match stylesheets.is_empty() {
+      true => config.layout.stylesheets.clone(),
+      false => stylesheets,
+    },
+  }
+}
 
-"#;
-  let mut ast = MdxParser::parse(mdx);
+fn html_mode(matches: &ArgMatches, config: &Config) -> HtmlMode {
+  matches
+    .try_get_one::<String>("html-mode")
+    .ok()
+    .flatten()
+    .and_then(|name| HtmlMode::from_name(name))
+    .or(config.html_mode().ok().flatten())
+    .unwrap_or_default()
+}
+
+fn get_path(matches: &ArgMatches, name: &str) -> Option<PathBuf> {
+  matches.try_get_one::<String>(name).ok().flatten().map(PathBuf::from)
+}
+
+fn diagnostic_format(matches: &ArgMatches) -> DiagnosticFormat {
+  matches
+    .try_get_one::<String>("format")
+    .ok()
+    .flatten()
+    .and_then(|name| DiagnosticFormat::from_name(name))
+    .unwrap_or_default()
+}
+
+fn main() {
   let matches = command_line();
   match matches.subcommand() {
-    Some(("run", matches)) => {
-      let file = matches.get_one::<String>("file").unwrap();
-      let debug = matches.get_flag("debug");
-      let source = std::fs::read_to_string(file).expect("could not read file");
-      run_html(source);
-    }
-    Some(("run-jsx", matches)) => {
-      let file = matches.get_one::<String>("file").unwrap();
-      let debug = matches.get_flag("debug");
-      let source = std::fs::read_to_string(file).expect("could not read file");
-      run_jsx(source);
+    Some(("compile", matches)) => run_command(matches, None),
+    Some(("run", matches)) => run_command(matches, Some(Target::Html)),
+    Some(("run-jsx", matches)) => run_command(matches, Some(Target::Jsx)),
+    Some(("run-vue", matches)) => run_command(matches, Some(Target::Vue)),
+    Some(("run-svelte", matches)) => run_command(matches, Some(Target::Svelte)),
+    Some(("inspect", matches)) => inspect_command(matches),
+    Some(("fmt", matches)) => fmt_command(matches),
+    Some(("lint", matches)) => lint_command(matches),
+    Some(("check-links", matches)) => check_links_command(matches),
+    Some(("stats", matches)) => stats_command(matches),
+    Some(("view", matches)) => view_command(matches),
+    Some(("build", matches)) => {
+      let config = load_config(matches);
+      let src = match get_path(matches, "src").or(config.src.clone()) {
+        Some(src) => src,
+        None => {
+          eprintln!("error: no content directory, pass <src> or set `src` in scc.toml");
+          process::exit(EXIT_USAGE);
+        }
+      };
+      let target = matches
+        .get_one::<String>("target")
+        .and_then(|name| Target::from_name(name))
+        .or(config.target().ok().flatten())
+        .unwrap_or(Target::Html);
+      let html_mode = html_mode(matches, &config);
+      let layout = layout_options(matches, &config, src.join("layouts"));
+      let out = get_path(matches, "out")
+        .or(config.out.clone())
+        .unwrap_or(PathBuf::from("dist"));
+      let search = config.search_options(matches.get_flag("search-index"));
+      let options = BuildOptions { src, out, target, html_mode, layout, config, search };
+      let format = diagnostic_format(matches);
+      match build::build(&options) {
+        Ok(summary) if matches.get_flag("watch") => {
+          summary.print(format);
+          if let Err(err) = watch::watch_build(&options, format, |_, _| {}) {
+            Diagnostics::from(Diagnostic::error(&err)).print(format);
+            process::exit(EXIT_DIAGNOSTICS);
+          }
+        }
+        Ok(summary) => {
+          summary.print(format);
+          if !summary.errors.is_empty() {
+            process::exit(EXIT_DIAGNOSTICS);
+          }
+        }
+        Err(err) => {
+          Diagnostics::from(Diagnostic::error(&err)).print(format);
+          process::exit(EXIT_DIAGNOSTICS);
+        }
+      }
     }
-    Some(("run-vue", matches)) => {
-      let file = matches.get_one::<String>("file").unwrap();
-      let debug = matches.get_flag("debug");
-      let source = std::fs::read_to_string(file).expect("could not read file");
-      run_vue(source);
+    Some(("serve", matches)) => {
+      let config = load_config(matches);
+      let src = match get_path(matches, "src").or(config.src.clone()) {
+        Some(src) => src,
+        None => {
+          eprintln!("error: no content directory, pass <src> or set `src` in scc.toml");
+          process::exit(EXIT_USAGE);
+        }
+      };
+      // pages are opened in a browser, they get the built-in layout unless there is a template
+      let mut layout = layout_options(matches, &config, src.join("layouts"));
+      layout.standalone = true;
+      let html_mode = html_mode(matches, &config);
+      let out = get_path(matches, "out")
+        .or(config.out.clone())
+        .unwrap_or(PathBuf::from("dist"));
+      let port = *matches.get_one::<u16>("port").unwrap();
+      let search = config.search_options(false);
+      let build = BuildOptions { src, out, target: Target::Html, html_mode, layout, config, search };
+      let format = diagnostic_format(matches);
+      if let Err(err) = serve::serve(ServeOptions { build, port, format }) {
+        Diagnostics::from(Diagnostic::error(&err)).print(format);
+        process::exit(EXIT_DIAGNOSTICS);
+      }
     }
     _ => {
-      panic!("Unknown command");
+      eprintln!("error: unknown command");
+      process::exit(EXIT_USAGE);
     }
   }
 }
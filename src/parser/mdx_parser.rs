@@ -3,109 +3,256 @@ Copyright (c) 2024 Yazalde Filimone. All rights reserved.
 
 
 */
-use crate::ast;
+use std::collections::BTreeMap;
+
+use crate::{ast, diagnostics::Diagnostic};
 use ast::Node;
 
+// errors point at the line and column of the document where parsing stopped
+type ParseResult<T> = Result<T, Diagnostic>;
+
 // ==================
 // guia: https://www.markdownguide.org/basic-syntax/
 
 pub struct MdxParser<'a> {
   pub input: &'a str,
   pub cursor: usize,
+  // lines before `input` in the document, for nested content e.g. a blockquote parsed on its own
+  line_offset: usize,
+  // the document and the offset of `input` in it, for the position of nodes. None when the input was rewritten
+  // e.g. a blockquote without its `>`, its nodes are left without a position
+  source: Option<(&'a str, usize)>,
+  options: ParserOptions,
+}
+
+// syntax on top of plain markdown, everything is enabled by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserOptions {
+  pub gfm: bool,         // github flavored markdown e.g. | tables |
+  pub mdx: bool,         // <Components /> instead of raw html
+  pub frontmatter: bool, // --- key: value ---
+}
+
+impl Default for ParserOptions {
+  fn default() -> Self {
+    Self { gfm: true, mdx: true, frontmatter: true }
+  }
 }
 
+#[allow(clippy::needless_return, clippy::match_like_matches_macro)]
 impl<'a> MdxParser<'a> {
   fn new(input: &'a str) -> MdxParser<'a> {
-    MdxParser { input, cursor: 0 }
+    MdxParser { input, cursor: 0, line_offset: 0, source: Some((input, 0)), options: ParserOptions::default() }
   }
 
-  pub fn parse(input: &str) -> ast::Ast {
+  pub fn parse(input: &str) -> ParseResult<ast::Ast> {
+    Self::parse_with(input, ParserOptions::default())
+  }
+
+  pub fn parse_with(input: &str, options: ParserOptions) -> ParseResult<ast::Ast> {
     let mut parser = MdxParser::new(input);
-    ast::Ast::Root(parser.parse_root())
+    parser.options = options;
+    Ok(ast::Ast::Root(parser.parse_root()?))
+  }
+
+  // inline markdown on its own e.g. a table cell, block syntax is read as text
+  pub fn parse_inline(input: &str) -> ParseResult<Vec<ast::Node>> {
+    MdxParser::new(input).parse_line_nodes()
+  }
+
+  // nested content starting at `line` of the current input, errors still point at the document
+  fn parse_nested<'n>(&self, input: &'n str, line: usize, source: Option<(&'n str, usize)>) -> ParseResult<Vec<Node>> {
+    let mut parser = MdxParser::new(input);
+    parser.line_offset = self.line_offset + line - 1;
+    parser.source = source;
+    parser.options = self.options;
+    Ok(*parser.parse_root()?.children)
+  }
+
+  // only the frontmatter, without parsing the document
+  pub fn parse_frontmatter_only(input: &str) -> BTreeMap<String, String> {
+    let mut parser = MdxParser::new(input);
+    let mut root = ast::Root::default();
+    parser.parse_frontmatter(&mut root);
+    root.frontmatter
   }
   // parser methods
-  fn parse_root(&mut self) -> ast::Root {
+  fn parse_root(&mut self) -> ParseResult<ast::Root> {
     let mut root = ast::Root::default();
-    while let Some(node) = self.parse_node() {
+    if self.options.frontmatter {
+      self.parse_frontmatter(&mut root);
+    }
+    while let Some(node) = self.parse_node()? {
       root.children.push(node);
     }
-    root
+    Ok(root)
+  }
+  // ==================
+  // parse frontmatter, only flat `key: value` pairs e.g.
+  // ---
+  // title: "Hello"
+  // layout: default
+  // ---
+  fn parse_frontmatter(&mut self, root: &mut ast::Root) {
+    if !self.starts_with("---\n") && !self.starts_with("---\r\n") {
+      return;
+    }
+    let start = self.cursor;
+    self.consume_line();
+    while !self.is_end() {
+      let line = self.consume_line().trim_end();
+      if line == "---" {
+        return;
+      }
+      if line.trim_start().starts_with('#') {
+        continue;
+      }
+      if let Some((key, value)) = line.split_once(':') {
+        let value = value.trim();
+        let value = value
+          .strip_prefix('"')
+          .and_then(|v| v.strip_suffix('"'))
+          .unwrap_or(value);
+        let value = value
+          .strip_prefix('\'')
+          .and_then(|v| v.strip_suffix('\''))
+          .unwrap_or(value);
+        root.frontmatter.insert(key.trim().to_string(), value.to_string());
+      }
+    }
+    // no closing `---`, it was not frontmatter after all
+    self.cursor = start;
+    root.frontmatter.clear();
   }
+
   // ==================
   // parse node
   //
-  fn parse_node(&mut self) -> Option<ast::Node> {
+  fn parse_node(&mut self) -> ParseResult<Option<ast::Node>> {
     self.skip_trivial();
     if self.is_end() {
-      return None;
+      return Ok(None);
     }
-    let node = match self.peek_one() {
-      '#' => self.parse_heading(),
+    let start = self.cursor;
+    let mut node = match self.peek_one() {
+      '#' if self.is_heading() => self.parse_heading(),
       '`' => self.parse_code(),
+      '~' if self.starts_with("~~~") => self.parse_code_block(),
       '>' => self.parse_blockquote(),
-      '!' => self.parse_image(),
-      '[' => self.parse_link(),
-      'a'..='z' | 'A'..='Z' => self.parse_paragraphs(),
-      '1'..='9' | '*' | '_' | '+' | '-' => self.parse_list(),
-      _ => panic!("Unknown character {}", self.peek_one()),
-    };
-    Some(node)
+      '!' if self.is_image() => self.parse_block_image(),
+      '<' => self.parse_element(),
+      '-' | '*' | '_' if self.is_thematic_break() => self.parse_thematic_break(),
+      '1'..='9' | '*' | '+' | '-' if self.is_list_marker() => self.parse_list(),
+      '|' if self.options.gfm && self.is_table() => self.parse_table(),
+      _ => self.parse_paragraphs(),
+    }?;
+    if let Some(position) = self.span(start) {
+      node.set_position(position);
+    }
+    Ok(Some(node))
   }
 
   // ==================
   // parse text, inline, strong, em, bold, italic, link
   //
 
-  fn parse_line_node(&mut self) -> ast::Node {
+  fn parse_line_node(&mut self) -> ParseResult<ast::Node> {
     match self.peek_one() {
       '`' => self.parse_inline_code(),
-      '*' => {
-        if self.starts_with("**") {
-          return self.parse_strong();
-        }
-        return self.parse_italic();
-      }
-      '_' => self.parse_italic(),
-      '[' => self.parse_link(),
+      '*' if self.starts_with("**") && self.is_emphasis("**") => self.parse_strong("**"),
+      '_' if self.starts_with("__") && self.is_emphasis("__") => self.parse_strong("__"),
+      '*' if self.is_emphasis("*") => self.parse_italic(),
+      '_' if self.is_emphasis("_") => self.parse_italic(),
+      '[' if self.starts_with("[[") => self.parse_text(),
+      '[' if self.is_link() => self.parse_link(),
+      '!' if self.is_image() => self.parse_image(),
       _ => self.parse_text(),
     }
   }
 
-  fn parse_strong(&mut self) -> ast::Node {
-    self.consume_expect("**");
+  // **strong** or __strong__
+  fn parse_strong(&mut self, delimiter: &str) -> ParseResult<ast::Node> {
+    let start = self.cursor;
+    self.consume_expect(delimiter)?;
     let mut children = Vec::new();
-    while !self.is_end() && self.peek_one() != '*' {
-      children.push(self.parse_line_node());
+    while !self.is_end() && !self.is_doble_newline() && !self.starts_with(delimiter) {
+      children.push(self.parse_line_node()?);
     }
-    self.consume_expect("**");
-    Node::Bold(ast::Bold { children: Box::new(children) })
+    if !self.starts_with(delimiter) {
+      return Ok(self.unclosed_emphasis(start));
+    }
+    self.consume_expect(delimiter)?;
+    Ok(Node::Bold(ast::Bold { children: Box::new(children) }))
   }
 
-  fn parse_italic(&mut self) -> ast::Node {
-    if !self.starts_with("*") || !self.starts_with("_") {
-      panic!("Expected '*' or '_' but got '{}'", self.peek_one());
-    }
+  fn parse_italic(&mut self) -> ParseResult<ast::Node> {
+    let start = self.cursor;
     let character = self.consume();
     let mut children = Vec::new();
-    while !self.is_end() && self.peek_one() != '*' {
-      children.push(self.parse_line_node());
+    while !self.is_end() && !self.is_doble_newline() && !self.is_closing(character) {
+      children.push(self.parse_line_node()?);
+    }
+    if !self.is_closing(character) {
+      return Ok(self.unclosed_emphasis(start));
     }
-    self.consume_expect(&character.to_string());
-    Node::Italic(ast::Italic { children: Box::new(children) })
+    self.consume_expect(&character.to_string())?;
+    Ok(Node::Italic(ast::Italic { children: Box::new(children) }))
+  }
+
+  // the closing delimiter was taken by a nested emphasis, the first character is text and the rest is read
+  // again e.g. `**a *b**` -> `*` and `*a *b**`
+  fn unclosed_emphasis(&mut self, start: usize) -> ast::Node {
+    self.cursor = start;
+    let character = self.consume();
+    Node::Text(ast::Text { text: character.to_string() })
   }
 
-  fn parse_paragraphs(&mut self) -> ast::Node {
+  // `delimiter` opens emphasis when it is followed by text and closed later in the paragraph, so `5 * 3` and
+  // `snake_case` stay text. `_` doesn't open or close inside a word
+  fn is_emphasis(&self, delimiter: &str) -> bool {
+    let rest = &self.input[self.cursor + delimiter.len()..];
+    let rest = &rest[..rest.find("\n\n").unwrap_or(rest.len())];
+    if rest.starts_with(char::is_whitespace) || rest.is_empty() {
+      return false;
+    }
+    if delimiter.starts_with('_') && self.input[..self.cursor].ends_with(char::is_alphanumeric) {
+      return false;
+    }
+    let character = delimiter.chars().next().unwrap_or('*');
+    rest.match_indices(delimiter).any(|(index, _)| {
+      let before = rest[..index].chars().next_back();
+      let after = rest[index + delimiter.len()..].chars().next();
+      index > 0
+        && !before.is_some_and(char::is_whitespace)
+        && !(character == '_' && after.is_some_and(char::is_alphanumeric))
+    })
+  }
+
+  // the delimiter that ends emphasis opened with `character`, one after whitespace or a `_` in the middle of a
+  // word doesn't
+  fn is_closing(&self, character: char) -> bool {
+    if self.peek_one() != character || self.input[..self.cursor].ends_with(char::is_whitespace) {
+      return false;
+    }
+    let after = self.input[self.cursor + 1..].chars().next();
+    character != '_' || !after.is_some_and(char::is_alphanumeric)
+  }
+
+  fn parse_paragraphs(&mut self) -> ParseResult<ast::Node> {
     let mut children = Vec::new();
     while !self.is_end() && !self.is_doble_newline() {
-      children.push(self.parse_line_node());
+      children.push(self.parse_line_node()?);
     }
-    Node::Paragraph(ast::Paragraph { children: Box::new(children) })
+    Ok(Node::Paragraph(ast::Paragraph {
+      children: Box::new(children),
+      position: None,
+    }))
   }
-  fn parse_list(&mut self) -> ast::Node {
+  fn parse_list(&mut self) -> ParseResult<ast::Node> {
     match self.peek_one() {
-      '+' | '-' | '*' | '_' => self.parse_unordered_list(),
+      '+' | '-' | '*' => self.parse_unordered_list(),
       '1'..='9' => self.parse_ordered_list(),
-      _ => panic!("Unknown character {}", self.peek_one()),
+      _ => Err(self.error(format!("Unknown character {}", self.peek_one()))),
     }
   }
 
@@ -113,22 +260,45 @@ impl<'a> MdxParser<'a> {
   - Item
   - Item
   */
-  fn parse_unordered_list(&mut self) -> ast::Node {
-    let indicator = self.peek_one();
+  fn parse_unordered_list(&mut self) -> ParseResult<ast::Node> {
+    let indicator = format!("{} ", self.peek_one());
     let mut children = Vec::new();
-    while !self.is_end() && self.is_doble_newline() {
-      children.push(self.parse_unordered_list_item(&indicator.to_string()));
+    let mut loose = false;
+    loop {
+      let start = self.cursor;
+      let (mut item, item_loose) = self.parse_unordered_list_item(&indicator)?;
+      if let Some(position) = self.span(start) {
+        item.set_position(position);
+      }
+      children.push(item);
+      loose |= item_loose;
+      if self.is_end() {
+        break;
+      }
+      if self.starts_with(&indicator) {
+        continue;
+      }
+      // - a\n\n- b is one loose list
+      match self.blank_lines_before(|line| line.starts_with(&indicator)) {
+        Some(length) => {
+          self.advance_many(length);
+          loose = true;
+        }
+        None => break,
+      }
     }
-    self.consume_expect_double_newline();
-    Node::List(ast::List { ordered: false, start: None, children: Box::new(children) })
+    Ok(Node::List(ast::List {
+      ordered: false,
+      start: None,
+      loose,
+      children: Box::new(children),
+      position: None,
+    }))
   }
   // - Item
-  fn parse_unordered_list_item(&mut self, indicator: &str) -> ast::Node {
-    self.consume_expect(indicator);
-    self.consume_expect_whitespace();
-
-    let line = self.parse_line_node();
-    return line;
+  fn parse_unordered_list_item(&mut self, indicator: &str) -> ParseResult<(ast::Node, bool)> {
+    self.consume_expect(indicator)?;
+    self.parse_list_item()
   }
 
   /*
@@ -136,150 +306,454 @@ impl<'a> MdxParser<'a> {
   2. Item
   */
 
-  fn parse_ordered_list(&mut self) -> ast::Node {
-    if !self.is_digit() {
-      panic!("Expected number but got '{}'", self.peek_one());
+  fn parse_ordered_list(&mut self) -> ParseResult<ast::Node> {
+    let item_start = self.cursor;
+    let start = Some(self.parse_number()?);
+    let (mut item, mut loose) = self.parse_ordered_list_item()?;
+    if let Some(position) = self.span(item_start) {
+      item.set_position(position);
+    }
+    let mut children = vec![item];
+    while !self.is_end() {
+      if !self.is_list_marker() || !self.is_digit() {
+        match self.blank_lines_before(is_ordered_marker) {
+          Some(length) => {
+            self.advance_many(length);
+            loose = true;
+          }
+          None => break,
+        }
+      }
+      let item_start = self.cursor;
+      self.parse_number()?;
+      let (mut item, item_loose) = self.parse_ordered_list_item()?;
+      if let Some(position) = self.span(item_start) {
+        item.set_position(position);
+      }
+      children.push(item);
+      loose |= item_loose;
     }
+    Ok(Node::List(ast::List {
+      ordered: true,
+      start,
+      loose,
+      children: Box::new(children),
+      position: None,
+    }))
+  }
+
+  fn parse_ordered_list_item(&mut self) -> ParseResult<(ast::Node, bool)> {
+    self.consume_expect(".")?;
+    self.consume_expect_whitespace()?;
+    self.parse_list_item()
+  }
 
-    let text = self.consume_while(|c| c != '.');
-    let start = Some(text.parse::<usize>().unwrap());
+  // the rest of the line, plus indented lines below it e.g. nested lists or paragraphs after a blank line,
+  // true when a blank line separates the blocks of the item
+  fn parse_list_item(&mut self) -> ParseResult<(ast::Node, bool)> {
+    let (line_number, _) = self.position();
+    let line = self.consume_line();
+    let mut parser = MdxParser::new(line.trim());
+    parser.line_offset = self.line_offset + line_number - 1;
+    parser.options = self.options;
+    let mut children = parser.parse_line_nodes()?;
+    let (nested_line, _) = self.position();
+    let mut nested = String::new();
+    let mut loose = false;
+    let mut fenced = false;
+    loop {
+      if !self.is_end() && is_indented(&self.input[self.cursor..]) {
+        let line = self.consume_line();
+        let line = line.strip_prefix('\t').unwrap_or(line).trim_start_matches(' ');
+        if line.starts_with("```") || line.starts_with("~~~") {
+          fenced = !fenced;
+        }
+        nested.push_str(line);
+        nested.push('\n');
+        continue;
+      }
+      match self.blank_lines_before(is_indented) {
+        Some(length) => {
+          let blank = &self.input[self.cursor..self.cursor + length];
+          nested.push_str(&"\n".repeat(blank.matches('\n').count()));
+          self.advance_many(length);
+          loose |= !fenced;
+        }
+        None => break,
+      }
+    }
+    if !nested.is_empty() {
+      children.extend(self.parse_nested(&nested, nested_line, None)?);
+    }
+    Ok((
+      Node::ListItem(ast::ListItem { children: Box::new(children), position: None }),
+      loose,
+    ))
+  }
+
+  // the length of the blank lines at the cursor when the line after them is accepted by `continues`
+  fn blank_lines_before(&self, continues: impl Fn(&str) -> bool) -> Option<usize> {
+    let mut length = 0;
+    for line in self.input[self.cursor..].split_inclusive('\n') {
+      if !line.trim().is_empty() {
+        return (length > 0 && continues(line)).then_some(length);
+      }
+      length += line.len();
+    }
+    None
+  }
+
+  fn parse_line_nodes(&mut self) -> ParseResult<Vec<ast::Node>> {
     let mut children = Vec::new();
-    while !self.is_end() && self.is_doble_newline() {
-      children.push(self.parse_ordered_list_item());
+    while !self.is_end() {
+      children.push(self.parse_line_node()?);
     }
-    self.consume_expect_double_newline();
-    Node::List(ast::List { ordered: true, start, children: Box::new(children) })
+    Ok(children)
+  }
+
+  // ---, ***, ___
+  fn parse_thematic_break(&mut self) -> ParseResult<ast::Node> {
+    self.consume_line();
+    Ok(Node::ThematicBreak)
   }
 
-  fn parse_ordered_list_item(&mut self) -> ast::Node {
-    self.parse_number();
-    self.consume_expect(".");
-    self.consume_expect_whitespace();
-    return self.parse_line_node();
+  fn parse_number(&mut self) -> ParseResult<usize> {
+    let number = self.consume_while(|c| c.is_ascii_digit());
+    number
+      .parse::<usize>()
+      .map_err(|_| self.error(format!("Expected number but got '{}'", self.peek_one())))
   }
-  fn parse_number(&mut self) -> usize {
-    let mut number = String::new();
-    while !self.is_end() && self.is_digit() {
-      number.push(self.consume());
+  // > quote
+  // > more quote
+  fn parse_blockquote(&mut self) -> ParseResult<ast::Node> {
+    let (line, _) = self.position();
+    let mut content = String::new();
+    while !self.is_end() && self.starts_with(">") {
+      let line = self.consume_line();
+      let line = line.strip_prefix('>').unwrap_or(line);
+      content.push_str(line.strip_prefix(' ').unwrap_or(line));
+      content.push('\n');
     }
-    if number.is_empty() {
-      panic!("Expected number but got '{}'", self.peek_one());
+    let children = Box::new(self.parse_nested(&content, line, None)?);
+    Ok(Node::Blockquote(ast::Blockquote { children, position: None }))
+  }
+
+  // ==================
+  // gfm tables, the second line separates the header from the rows e.g.
+  // | name | value |
+  // | ---- | :---: |
+  // | a    | 1     |
+  //
+  fn parse_table(&mut self) -> ParseResult<ast::Node> {
+    let header = table_cells(self.consume_line());
+    let align = table_align(self.consume_line());
+    let mut rows = Vec::new();
+    while !self.is_end() && self.starts_with("|") {
+      rows.push(table_cells(self.consume_line()));
     }
-    number.parse::<usize>().unwrap()
+    Ok(Node::Table(ast::Table { header, align, rows, position: None }))
   }
-  fn parse_blockquote(&mut self) -> ast::Node {
-    self.consume_expect(">");
-    let mut children = Vec::new();
-    while !self.is_end() && self.is_newline() {
-      children.push(self.parse_node().unwrap());
+
+  fn is_table(&self) -> bool {
+    let mut lines = self.input[self.cursor..].lines();
+    let delimiter = match (lines.next(), lines.next()) {
+      (Some(_), Some(delimiter)) => delimiter.trim(),
+      _ => return false,
+    };
+    delimiter.starts_with('|')
+      && delimiter.contains('-')
+      && delimiter.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+  }
+
+  // ==================
+  // parse html and jsx e.g. <div>html</div>, <!-- comment -->, <Sidebar is_open={true} />
+  //
+  fn parse_element(&mut self) -> ParseResult<ast::Node> {
+    let start = self.cursor;
+    if self.starts_with("<!--") {
+      self.consume_while_not("-->");
+      if !self.is_end() {
+        self.consume_expect("-->")?;
+      }
+      return Ok(Node::Html(self.input[start..self.cursor].to_string()));
     }
-    Node::Blockquote(ast::Blockquote { children: Box::new(children) })
+    let (line, column) = self.position();
+    self.consume_expect("<")?;
+    let name = self.consume_while(|c| c.is_alphanumeric() || c == '.' || c == '-' || c == '_' || c == ':');
+    let is_component = self.options.mdx && name.chars().next().is_some_and(|c| c.is_uppercase());
+    let (props, self_closing) = self.parse_element_props();
+    let mut children = "";
+    let mut children_line = 1;
+    let children_start = self.cursor;
+    if !self_closing {
+      let closing = format!("</{}>", name);
+      children_line = self.position().0;
+      self.consume_while_not(&closing);
+      children = &self.input[children_start..self.cursor];
+      // html is written as is, but a component without its closing tag would swallow the rest of the document
+      if self.is_end() && is_component {
+        let message = format!("Expected '{}' to close '<{}>'", closing, name);
+        return Err(Diagnostic::error(&message).at(line, column));
+      }
+      if !self.is_end() {
+        self.consume_expect(&closing)?;
+      }
+    }
+    if !is_component {
+      return Ok(Node::Html(self.input[start..self.cursor].to_string()));
+    }
+    let source = self
+      .source
+      .map(|(document, offset)| (document, offset + children_start));
+    let children = Box::new(self.parse_nested(children, children_line, source)?);
+    Ok(Node::ReactComponent(ast::ReactComponent {
+      name: name.to_string(),
+      props,
+      children,
+      position: None,
+    }))
   }
 
-  fn parse_link(&mut self) -> ast::Node {
-    // [alt](url "title")
-    self.consume_expect("[");
-    let alt = self.consume_while(|c| c != ']').to_string();
-    self.consume_expect("]");
-    self.consume_expect("(");
-    let mut url = String::new();
-    while !self.is_end() {
+  // is_open={true} title="hello" -> (["is_open={true}", "title=\"hello\""], self_closing)
+  fn parse_element_props(&mut self) -> (Vec<String>, bool) {
+    let mut props = Vec::new();
+    loop {
       self.skip_whitespace();
-      if self.peek_one() == '"' || self.peek_one() == '\'' || self.peek_one() == ')' {
-        break;
+      if self.is_end() {
+        return (props, false);
+      }
+      if self.starts_with("/>") {
+        self.advance_many(2);
+        return (props, true);
+      }
+      if self.starts_with(">") {
+        self.advance_one();
+        return (props, false);
+      }
+      let start = self.cursor;
+      self.consume_while(|c| !c.is_whitespace() && c != '=' && c != '>' && c != '/');
+      if !self.is_end() && self.peek_one() == '=' {
+        self.advance_one();
+        self.parse_element_prop_value();
+      }
+      if start == self.cursor {
+        self.consume();
+        continue;
       }
-      url.push(self.consume());
+      props.push(self.input[start..self.cursor].to_string());
     }
-    let maybe_title = self.consume_while(|c| c != ')').trim();
-    let title = if maybe_title.is_empty() {
-      None
-    } else {
-      Some(maybe_title.to_string())
-    };
-    self.consume_expect(")");
-    Node::Link(ast::Link { url, alt, title })
   }
 
-  fn parse_image(&mut self) -> ast::Node {
-    // ![alt](url "title")
-    self.consume_expect("![");
+  fn parse_element_prop_value(&mut self) {
+    if self.is_end() {
+      return;
+    }
+    match self.peek_one() {
+      quote @ ('"' | '\'') => {
+        self.consume();
+        self.consume_while(|c| c != quote);
+        if !self.is_end() {
+          self.consume();
+        }
+      }
+      '{' => {
+        let mut depth = 0;
+        while !self.is_end() {
+          match self.consume() {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+          }
+          if depth == 0 {
+            break;
+          }
+        }
+      }
+      _ => {
+        self.consume_while(|c| !c.is_whitespace() && c != '>');
+      }
+    }
+  }
+
+  // [alt](url), anything else in brackets e.g. a reference link [text][ref] or an unclosed `[a](b` is text
+  fn is_link(&self) -> bool {
+    is_link_at(&self.input[self.cursor..])
+  }
+
+  // ![alt](url) inside a line, a link after the `!`
+  fn is_image(&self) -> bool {
+    self.starts_with("![") && is_link_at(&self.input[self.cursor + 1..])
+  }
+
+  // [alt](url "title"), the `[` is text when the destination or the title is not closed
+  fn parse_link(&mut self) -> ParseResult<ast::Node> {
+    let start = self.cursor;
+    self.consume_expect("[")?;
     let alt = self.consume_while(|c| c != ']').to_string();
-    self.consume_expect("]");
+    self.consume_expect("]")?;
+    let Some((url, title)) = self.parse_destination() else {
+      self.cursor = start + 1;
+      return Ok(Node::Text(ast::Text { text: "[".to_string() }));
+    };
+    Ok(Node::Link(ast::Link { url, alt, title }))
+  }
 
-    self.consume_expect("(");
+  // `(url "title")`, the title can also be in single quotes or parentheses and is returned without them, `\"`
+  // escapes the quote. a quote only starts the title after whitespace, so urls can contain quotes. None when
+  // the title or the destination is not closed
+  fn parse_destination(&mut self) -> Option<(String, Option<String>)> {
+    self.consume_expect("(").ok()?;
     let mut url = String::new();
-    while !self.is_end() {
+    let mut title = None;
+    while !self.is_end() && self.peek_one() != ')' {
+      let after_whitespace = self.peek_one().is_whitespace();
       self.skip_whitespace();
-      if self.peek_one() == '"' || self.peek_one() == '\'' || self.peek_one() == ')' {
-        break;
+      let close = match self.peek_one() {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => '\0',
+      };
+      if close != '\0' && after_whitespace && title.is_none() {
+        self.advance_one();
+        let mut text = String::new();
+        while !self.is_end() && self.peek_one() != close {
+          if self.peek_one() == '\\' && self.peek_many(2).ends_with(close) {
+            self.advance_one();
+          }
+          text.push(self.consume());
+        }
+        self.consume_expect(&close.to_string()).ok()?;
+        title = Some(text);
+        continue;
+      }
+      if !self.is_end() && self.peek_one() != ')' {
+        url.push(self.consume());
       }
-      url.push(self.consume());
     }
-    let maybe_title = self.consume_while(|c| c != ')').trim();
-    let title = if maybe_title.is_empty() {
-      None
-    } else {
-      Some(maybe_title.to_string())
+    self.consume_expect(")").ok()?;
+    Some((url, title))
+  }
+
+  // ![alt](url "title"), the `!` is text when the destination or the title is not closed
+  fn parse_image(&mut self) -> ParseResult<ast::Node> {
+    let start = self.cursor;
+    self.consume_expect("![")?;
+    let alt = self.consume_while(|c| c != ']').to_string();
+    self.consume_expect("]")?;
+    let Some((url, title)) = self.parse_destination() else {
+      self.cursor = start + 1;
+      return Ok(Node::Text(ast::Text { text: "!".to_string() }));
     };
-    self.consume_expect(")");
-    Node::Image(ast::Link { url, alt, title })
+    Ok(Node::Image(ast::Link { url, alt, title }))
   }
 
-  fn parse_heading(&mut self) -> Node {
-    self.consume_expect("#");
-    let level = self.parse_heading_level();
-    self.consume_expect_whitespace();
-    let text = self.consume_while(|c| c != '\n').to_string();
-    self.consume_expect_newline();
-    Node::Heading(ast::Heading { level, text })
+  // an image on its own, or a paragraph when it turns out to be text
+  fn parse_block_image(&mut self) -> ParseResult<ast::Node> {
+    let start = self.cursor;
+    match self.parse_image()? {
+      Node::Text(_) => {
+        self.cursor = start;
+        self.parse_paragraphs()
+      }
+      image => Ok(image),
+    }
   }
 
-  fn parse_heading_level(&mut self) -> usize {
-    let mut level = 1;
-    while !self.is_end() && self.peek_one() == '#' {
-      self.consume();
-      level += 1;
+  // 1 to 6 `#` and a space, `#hashtag` is a paragraph
+  fn is_heading(&self) -> bool {
+    let rest = &self.input[self.cursor..];
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level) && rest[level..].starts_with([' ', '\t', '\n'])
+  }
+
+  fn parse_heading(&mut self) -> ParseResult<Node> {
+    let level = self.consume_while(|c| c == '#').len();
+    self.consume_expect_whitespace()?;
+    let text = self.consume_while(|c| c != '\n').to_string();
+    if !self.is_end() {
+      self.consume_expect_newline()?;
     }
-    level
+    Ok(Node::Heading(ast::Heading {
+      level,
+      text,
+      id: None,
+      anchor: None,
+      position: None,
+    }))
   }
 
-  fn parse_code(&mut self) -> ast::Node {
+  fn parse_code(&mut self) -> ParseResult<ast::Node> {
     if self.peek_many(3) == "```" {
       return self.parse_code_block();
     }
     return self.parse_inline_code();
   }
 
-  fn parse_inline_code(&mut self) -> ast::Node {
-    self.consume_expect("`");
+  fn parse_inline_code(&mut self) -> ParseResult<ast::Node> {
+    self.consume_expect("`")?;
     let code = self.consume_while(|c| c != '`').to_string();
-    Node::InlineCode(ast::InlineCode { code })
+    if !self.is_end() {
+      self.consume_expect("`")?;
+    }
+    Ok(Node::InlineCode(ast::InlineCode { code }))
   }
-  fn parse_code_block(&mut self) -> ast::Node {
-    let language = self.parse_code_block_language();
-    let mut code = String::new();
-    while !self.is_end() && !self.starts_with("```") {
-      code.push_str(&self.consume_while(|character| character != '\n'));
+  // the fence is three or more backticks or tildes, the code ends at the same fence e.g. ~~~~ or ````
+  fn parse_code_block(&mut self) -> ParseResult<ast::Node> {
+    let marker = self.peek_one();
+    let fence = self.consume_while(|c| c == marker).to_string();
+    let (language, meta) = self.parse_code_block_info()?;
+    let mut lines = Vec::new();
+    while !self.is_end() && !self.starts_with(&fence) {
+      lines.push(self.consume_line());
     }
-    Node::CodeBlock(ast::CodeBlock { language, code, meta: None })
+    if !self.is_end() {
+      self.consume_expect(&fence)?;
+    }
+    let code = lines.join("\n");
+    Ok(Node::CodeBlock(ast::CodeBlock::new(language, code, meta)))
   }
 
-  fn parse_code_block_language(&mut self) -> String {
-    self.consume_expect("```");
-    let language = self.consume_while(|c| c != '\n');
-    self.consume_expect("\n");
-    language.to_string()
+  // ```ts title="example.ts" -> language: ts, meta: [title="example.ts"]
+  fn parse_code_block_info(&mut self) -> ParseResult<(String, Option<Vec<String>>)> {
+    let info = self.consume_while(|c| c != '\n');
+    if !self.is_end() {
+      self.consume_expect_newline()?;
+    }
+    let mut parts = info.split_whitespace();
+    let language = parts.next().unwrap_or("").to_string();
+    let meta: Vec<String> = parts.map(|part| part.to_string()).collect();
+    let meta = if meta.is_empty() { None } else { Some(meta) };
+    Ok((language, meta))
   }
   // tex e.g: This is a test, **bold** and *italic* and `code`.
-  fn parse_text(&mut self) -> ast::Node {
+  fn parse_text(&mut self) -> ParseResult<ast::Node> {
     let mut text = String::new();
     while !self.is_end() && !self.is_doble_newline() {
-      if self.contains(vec!["`", "*", "_"]) {
+      if self.starts_with("[[") {
+        text.push_str(self.consume_while_not("]]"));
+        text.push_str(self.consume_while(|c| c == ']'));
+        continue;
+      }
+      if !text.is_empty() && self.is_inline_start() {
         break;
       }
       text.push(self.consume());
     }
-    return Node::Text(ast::Text { text });
+    Ok(Node::Text(ast::Text { text }))
+  }
+
+  // where `parse_line_node` would read something other than text, or where emphasis could end
+  fn is_inline_start(&self) -> bool {
+    match self.peek_one() {
+      '`' => true,
+      '*' | '_' if self.is_closing(self.peek_one()) => true,
+      '*' => (self.starts_with("**") && self.is_emphasis("**")) || self.is_emphasis("*"),
+      '_' => (self.starts_with("__") && self.is_emphasis("__")) || self.is_emphasis("_"),
+      '[' => !self.starts_with("[[") && self.is_link(),
+      '!' => self.is_image(),
+      _ => false,
+    }
   }
 
   // lexer methods
@@ -296,7 +770,7 @@ impl<'a> MdxParser<'a> {
   }
 
   fn advance_one(&mut self) {
-    self.cursor += 1;
+    self.cursor += self.peek_one().len_utf8();
   }
 
   fn advance_many(&mut self, count: usize) {
@@ -304,27 +778,25 @@ impl<'a> MdxParser<'a> {
   }
 
   fn consume(&mut self) -> char {
-    let mut iter = self.input[self.cursor..].char_indices();
-    let (_, cur_char) = iter.next().unwrap();
-    let (next_cursor, _) = iter.next().unwrap_or((1, ' '));
-    self.cursor += next_cursor;
+    let cur_char = self.peek_one();
+    self.cursor += cur_char.len_utf8();
     cur_char
   }
 
-  fn consume_expect(&mut self, text: &str) {
-    if &self.peek_many(text.len()) == text {
-      self.advance_many(text.len());
-    } else {
-      panic!("Expected '{}' but got '{}'", text, &self.peek_many(text.len()));
+  fn consume_expect(&mut self, text: &str) -> ParseResult<()> {
+    if !self.starts_with(text) {
+      return Err(self.error(format!("Expected '{}' but got '{}'", text, &self.peek_many(text.len()))));
     }
+    self.advance_many(text.len());
+    Ok(())
   }
 
-  fn consume_expect_whitespace(&mut self) {
-    if !self.is_end() && self.peek_one().is_whitespace() {
-      self.advance_one();
-    } else {
-      panic!("Expected whitespace but got '{}'", self.peek_one());
+  fn consume_expect_whitespace(&mut self) -> ParseResult<()> {
+    if self.is_end() || !self.peek_one().is_whitespace() {
+      return Err(self.error(format!("Expected whitespace but got '{}'", self.peek_one())));
     }
+    self.advance_one();
+    Ok(())
   }
 
   // fn consume_expect_many(&mut self, expectds: Vec<&'a str>) -> &'a str {
@@ -340,32 +812,36 @@ impl<'a> MdxParser<'a> {
   //   panic!("Expected '{}' but got '{}'", report_text, current_text);
   // }
 
-  fn contains(&self, expectds: Vec<&str>) -> bool {
-    for expectd in expectds {
-      if &self.peek_many(expectd.len()) == expectd {
-        return true;
-      }
+  fn consume_expect_newline(&mut self) -> ParseResult<()> {
+    if self.is_end() || !self.is_newline() {
+      return Err(self.error(format!("Expected newline but got '{}'", self.peek_one())));
     }
-    false
+    self.advance_one();
+    Ok(())
   }
 
-  fn consume_expect_newline(&mut self) {
-    if !self.is_end() && self.is_newline() {
-      return self.advance_one();
+  fn consume_while(&mut self, mut test: impl FnMut(char) -> bool) -> &'a str {
+    let start_cursor = self.cursor;
+    while !self.is_end() && test(self.peek_one()) {
+      self.advance_one();
     }
-    panic!("Expected newline but got '{}'", self.peek_one());
+    &self.input[start_cursor..self.cursor]
   }
-  fn consume_expect_double_newline(&mut self) {
-    if !self.is_end() && self.is_doble_newline() {
-      return self.advance_many(2);
+
+  // consumes until the end of the line, the newline is consumed but not returned
+  fn consume_line(&mut self) -> &'a str {
+    let line = self.consume_while(|c| c != '\n');
+    if !self.is_end() {
+      self.advance_one();
     }
-    panic!("Expected double newline but got '{}'", self.peek_one());
+    line
   }
 
-  fn consume_while(&mut self, mut test: impl FnMut(char) -> bool) -> &'a str {
+  fn consume_while_not(&mut self, text: &str) -> &'a str {
     let start_cursor = self.cursor;
-    while !self.is_end() && test(self.peek_one()) {
-      self.advance_one();
+    match self.input[self.cursor..].find(text) {
+      Some(offset) => self.cursor += offset,
+      None => self.cursor = self.input.len(),
     }
     &self.input[start_cursor..self.cursor]
   }
@@ -374,6 +850,7 @@ impl<'a> MdxParser<'a> {
     self.consume_while(char::is_whitespace);
   }
 
+  #[allow(dead_code)]
   fn is_whitespace(&self) -> bool {
     self.peek_one().is_whitespace()
   }
@@ -385,6 +862,23 @@ impl<'a> MdxParser<'a> {
   fn is_doble_newline(&self) -> bool {
     self.peek_many(2) == "\n\n"
   }
+  // `- `, `* `, `+ ` or `1. `
+  fn is_list_marker(&self) -> bool {
+    let rest = &self.input[self.cursor..];
+    if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+      return true;
+    }
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && rest[digits..].starts_with(". ")
+  }
+
+  fn is_thematic_break(&self) -> bool {
+    let line = self.input[self.cursor..].lines().next().unwrap_or("");
+    let marker = self.peek_one();
+    let count = line.chars().filter(|c| *c == marker).count();
+    count >= 3 && line.chars().all(|c| c == marker || c == ' ')
+  }
+
   fn is_digit(&self) -> bool {
     match self.peek_one() {
       '0'..='9' => true,
@@ -395,7 +889,182 @@ impl<'a> MdxParser<'a> {
     self.skip_whitespace();
   }
 
+  // '\0' at the end of the input
   fn peek_one(&self) -> char {
-    self.input[self.cursor..].chars().next().unwrap()
+    self.input[self.cursor..].chars().next().unwrap_or('\0')
+  }
+
+  // 1-based line and column of the cursor in the document
+  fn position(&self) -> (usize, usize) {
+    let before = &self.input[..self.cursor];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (self.line_offset + line, column)
+  }
+
+  // from `start` to the cursor in the document, without the whitespace at the end
+  fn span(&self, start: usize) -> Option<ast::Position> {
+    let (document, offset) = self.source?;
+    let end = start + self.input[start..self.cursor].trim_end().len();
+    Some(ast::Position { start: point(document, offset + start), end: point(document, offset + end) })
+  }
+
+  // an error at the cursor
+  fn error(&self, message: String) -> Diagnostic {
+    let (line, column) = self.position();
+    Diagnostic::error(&message).at(line, column)
+  }
+}
+
+fn point(document: &str, offset: usize) -> ast::Point {
+  let before = &document[..offset];
+  let line = before.matches('\n').count() + 1;
+  let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+  ast::Point { line, column, offset }
+}
+
+// nested in a list item
+fn is_indented(line: &str) -> bool {
+  line.starts_with("  ") || line.starts_with('\t')
+}
+
+// `12. item`
+fn is_ordered_marker(line: &str) -> bool {
+  let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+  digits > 0 && line[digits..].starts_with(". ")
+}
+
+// | :--- | :---: | ---: | -> left, center, right
+fn table_align(line: &str) -> Vec<Option<ast::Align>> {
+  table_cells(line)
+    .iter()
+    .map(|cell| match (cell.starts_with(':'), cell.ends_with(':')) {
+      (true, true) => Some(ast::Align::Center),
+      (true, false) => Some(ast::Align::Left),
+      (false, true) => Some(ast::Align::Right),
+      (false, false) => None,
+    })
+    .collect()
+}
+
+// | a | b | -> ["a", "b"]
+fn table_cells(line: &str) -> Vec<String> {
+  let line = line.trim();
+  let line = line.strip_prefix('|').unwrap_or(line);
+  let line = line.strip_suffix('|').unwrap_or(line);
+  line.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+// `[alt](` with a `)` later in the paragraph
+fn is_link_at(rest: &str) -> bool {
+  let rest = &rest[..rest.find("\n\n").unwrap_or(rest.len())];
+  rest
+    .find(']')
+    .and_then(|end| rest[end + 1..].strip_prefix('('))
+    .is_some_and(|destination| destination.contains(')'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::MdxParser;
+  use crate::ast;
+
+  fn paragraph(source: &str) -> Vec<ast::Node> {
+    let ast::Ast::Root(root) = MdxParser::parse(source).unwrap();
+    match root.children.into_iter().next() {
+      Some(ast::Node::Paragraph(paragraph)) => *paragraph.children,
+      node => panic!("expected a paragraph, got {:?}", node),
+    }
+  }
+
+  // the paragraph as plain text, it fails on any other node
+  fn text(nodes: &[ast::Node]) -> String {
+    let text = nodes.iter().map(|node| match node {
+      ast::Node::Text(text) => text.text.as_str(),
+      node => panic!("expected text, got {:?}", node),
+    });
+    text.collect()
+  }
+
+  #[test]
+  fn reads_lone_delimiters_as_text() {
+    assert_eq!(text(&paragraph("5 * 3 = 15 and 2*x\n")), "5 * 3 = 15 and 2*x\n");
+    assert_eq!(text(&paragraph("call snake_case_name\n")), "call snake_case_name\n");
+    let nodes = paragraph("a _b_ *c* **d**\n");
+    assert!(matches!(nodes[1], ast::Node::Italic(_)));
+    assert!(matches!(nodes[3], ast::Node::Italic(_)));
+    assert!(matches!(nodes[5], ast::Node::Bold(_)));
+    assert!(matches!(paragraph("a __b__ c\n")[1], ast::Node::Bold(_)));
+  }
+
+  #[test]
+  fn reads_loose_lists() {
+    let ast::Ast::Root(root) = MdxParser::parse("1. a\n\n   more\n2. b\n\n- c\n- d\n\ntext\n").unwrap();
+    let [ast::Node::List(ordered), ast::Node::List(bullets), ast::Node::Paragraph(_)] = root.children.as_slice() else {
+      panic!("expected two lists and a paragraph, got {:?}", root.children);
+    };
+    assert!(ordered.loose && !bullets.loose);
+    assert_eq!(ordered.children.len(), 2);
+    assert!(matches!(ordered.children[0].children()[1], ast::Node::Paragraph(_)));
+  }
+
+  #[test]
+  fn reads_unclosed_emphasis_as_text() {
+    // `**a *b**` is `*<em>a <em>b</em></em>` in commonmark
+    let nodes = paragraph("Some **a *b**\n");
+    assert_eq!(text(&nodes[..2]), "Some *");
+    let ast::Node::Italic(italic) = &nodes[2] else {
+      panic!("expected italic, got {:?}", nodes[2])
+    };
+    assert_eq!(text(&italic.children[..1]), "a ");
+    assert!(matches!(italic.children[1], ast::Node::Italic(_)));
+    assert_eq!(text(&paragraph("**a\n\nb**\n")), "**a");
+  }
+
+  #[test]
+  fn reads_unclosed_links_as_text() {
+    for source in [
+      "[a](b \"t)",
+      "![a](b \"t)",
+      "![x](y",
+      "[a](b",
+      "see ![a](b 'c) and [d](e)",
+    ] {
+      let nodes = paragraph(&format!("{}\n", source));
+      let text: String = nodes
+        .iter()
+        .map(|node| match node {
+          ast::Node::Text(text) => text.text.clone(),
+          ast::Node::Link(link) => format!("[{}]({})", link.alt, link.url),
+          node => panic!("expected text, got {:?}", node),
+        })
+        .collect();
+      assert_eq!(text, format!("{}\n", source));
+    }
+    assert!(matches!(paragraph("c ![a](b)\n")[1], ast::Node::Image(_)));
+  }
+
+  #[test]
+  fn reads_multibyte_characters_at_the_end() {
+    let nodes = MdxParser::parse_inline("**Bold** café").unwrap();
+    assert_eq!(text(&nodes[1..]), " café");
+  }
+
+  #[test]
+  fn needs_a_space_after_the_heading_marker() {
+    let ast::Ast::Root(root) = MdxParser::parse("#hashtag at the start\n\n## Heading\n").unwrap();
+    assert!(matches!(&root.children[0], ast::Node::Paragraph(_)));
+    assert!(matches!(&root.children[1], ast::Node::Heading(heading) if heading.level == 2));
+    let ast::Ast::Root(root) = MdxParser::parse("####### seven\n").unwrap();
+    assert!(matches!(&root.children[0], ast::Node::Paragraph(_)));
+  }
+
+  #[test]
+  fn reports_errors_with_the_position() {
+    let error = MdxParser::parse("# Title\n\n  <Note>\n\nnever closed\n").unwrap_err();
+    assert_eq!(error.message, "Expected '</Note>' to close '<Note>'");
+    assert_eq!((error.line, error.column), (Some(3), Some(3)));
+    let error = MdxParser::parse("<Tip>\n\nText\n\n<Note>\n\n</Tip>\n").unwrap_err();
+    assert_eq!((error.line, error.column), (Some(5), Some(1)));
   }
 }
@@ -1,3 +1,3 @@
 mod mdx_parser;
 
-pub use mdx_parser::MdxParser;
+pub use mdx_parser::{MdxParser, ParserOptions};
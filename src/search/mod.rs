@@ -0,0 +1,299 @@
+// ==================
+// search.json for a static search, written by `scc build --search-index` e.g.
+//
+// {
+//   "sections": [
+//     { "title": "Guide", "heading": "Install", "url": "guide.html#install", "content": "Run cargo install scc." }
+//   ],
+//   "index": { "install": [[0, 2]], "cargo": [[0, 1]], "run": [[0, 1]], "scc": [[0, 1]] },
+//   "stop_words": ["a", "an", "and", ...]
+// }
+//
+// each document is split into sections at its headings, the text before the first one has no heading.
+// urls are relative to the output directory. `index` maps a term to [section, count] pairs, it is left out
+// without `inverted-index`. the terms are lowercased, stop words are dropped and the rest is stemmed with
+// step 1 of the porter stemmer, the client does the same to the query. see `terms`.
+//
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+  ast,
+  compiler::{TextCompiler, TextOptions},
+  transformer::Slugger,
+};
+
+pub const STOP_WORDS: [&str; 33] = [
+  "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no", "not", "of",
+  "on", "or", "such", "that", "the", "their", "then", "there", "these", "they", "this", "to", "was", "will", "with",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchOptions {
+  pub output: PathBuf,      // relative to the output directory
+  pub inverted_index: bool, // write `index` next to the sections
+}
+
+impl Default for SearchOptions {
+  fn default() -> Self {
+    Self { output: PathBuf::from("search.json"), inverted_index: true }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchSection {
+  pub title: String,           // of the document
+  pub heading: Option<String>, // None before the first heading
+  pub url: String,             // with the heading anchor e.g. guide.html#install
+  pub content: String,         // plain text, code blocks included
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchIndex {
+  pub sections: Vec<SearchSection>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub index: Option<BTreeMap<String, Vec<(usize, usize)>>>,
+  pub stop_words: Vec<&'static str>,
+}
+
+impl SearchIndex {
+  pub fn new(sections: Vec<SearchSection>, options: &SearchOptions) -> Self {
+    let index = options.inverted_index.then(|| inverted_index(&sections));
+    Self { sections, index, stop_words: STOP_WORDS.to_vec() }
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).unwrap_or_default()
+  }
+}
+
+// the sections of a compiled document, `url` is where it is written e.g. guide/intro.html.
+// the headings keep the ids the heading-slug transformer gave them.
+pub fn document_sections(ast: &ast::Ast, url: &str, path: &Path) -> Vec<SearchSection> {
+  let ast::Ast::Root(root) = ast;
+  let title = root
+    .frontmatter
+    .get("title")
+    .cloned()
+    .or_else(|| {
+      root.children.iter().find_map(|node| match node {
+        ast::Node::Heading(heading) if heading.level == 1 => Some(heading.plain_text()),
+        _ => None,
+      })
+    })
+    .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+  let mut slugger = Slugger::new();
+  let mut sections = Vec::new();
+  let mut heading: Option<(String, Option<String>)> = None;
+  let mut nodes = Vec::new();
+  for node in root.children.iter() {
+    let ast::Node::Heading(next) = node else {
+      nodes.push(node.clone());
+      continue;
+    };
+    sections.extend(section(&title, heading.take(), url, std::mem::take(&mut nodes)));
+    let id = next.id.clone().or_else(|| slugger.heading(next));
+    heading = Some((next.plain_text(), id));
+  }
+  sections.extend(section(&title, heading, url, nodes));
+  sections
+}
+
+// None for the empty text before the first heading
+fn section(
+  title: &str,
+  heading: Option<(String, Option<String>)>,
+  url: &str,
+  nodes: Vec<ast::Node>,
+) -> Option<SearchSection> {
+  let mut root = ast::Root::default();
+  *root.children = nodes;
+  let (content, _) = TextCompiler::with_options(TextOptions::default()).compile_with_stats(&ast::Ast::Root(root));
+  if heading.is_none() && content.is_empty() {
+    return None;
+  }
+  let (heading, url) = match heading {
+    Some((heading, Some(id))) => (Some(heading), format!("{}#{}", url, id)),
+    Some((heading, None)) => (Some(heading), url.to_string()),
+    None => (None, url.to_string()),
+  };
+  Some(SearchSection { title: title.to_string(), heading, url, content: content.replace('\n', " ") })
+}
+
+fn inverted_index(sections: &[SearchSection]) -> BTreeMap<String, Vec<(usize, usize)>> {
+  let mut index: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+  for (number, section) in sections.iter().enumerate() {
+    let text = [section.heading.as_deref().unwrap_or_default(), &section.content].join(" ");
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for term in terms(&text) {
+      *counts.entry(term).or_default() += 1;
+    }
+    for (term, count) in counts {
+      index.entry(term).or_default().push((number, count));
+    }
+  }
+  index
+}
+
+// the searchable terms of a text, lowercased words without stop words, stemmed
+pub fn terms(text: &str) -> Vec<String> {
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .map(str::to_lowercase)
+    .filter(|word| word.chars().count() > 1 && !STOP_WORDS.contains(&word.as_str()))
+    .map(|word| stem(&word))
+    .collect()
+}
+
+// ==================
+// step 1 of the porter stemmer, plurals and -ed/-ing e.g. ponies -> poni, installed -> install
+// ref: https://tartarus.org/martin/PorterStemmer/def.txt
+//
+pub fn stem(word: &str) -> String {
+  if word.len() <= 2 || !word.is_ascii() {
+    return word.to_string();
+  }
+  let mut word = word.to_string();
+  // 1a
+  if word.ends_with("sses") || word.ends_with("ies") {
+    word.truncate(word.len() - 2);
+  } else if word.ends_with('s') && !word.ends_with("ss") {
+    word.pop();
+  }
+  // 1b
+  if word.ends_with("eed") {
+    if measure(&word[..word.len() - 3]) > 0 {
+      word.pop();
+    }
+  } else if let Some(stem) = ["ed", "ing"]
+    .iter()
+    .find_map(|suffix| word.strip_suffix(suffix))
+    .filter(|stem| has_vowel(stem))
+  {
+    word = stem.to_string();
+    if word.ends_with("at") || word.ends_with("bl") || word.ends_with("iz") {
+      word.push('e');
+    } else if ends_with_double_consonant(&word) && !word.ends_with(['l', 's', 'z']) {
+      word.pop();
+    } else if measure(&word) == 1 && ends_with_cvc(&word) {
+      word.push('e');
+    }
+  }
+  // 1c
+  if word.ends_with('y') && has_vowel(&word[..word.len() - 1]) {
+    word.pop();
+    word.push('i');
+  }
+  word
+}
+
+// y is a vowel after a consonant
+fn is_consonant(word: &[u8], index: usize) -> bool {
+  match word[index] {
+    b'a' | b'e' | b'i' | b'o' | b'u' => false,
+    b'y' => index == 0 || !is_consonant(word, index - 1),
+    _ => true,
+  }
+}
+
+fn has_vowel(word: &str) -> bool {
+  (0..word.len()).any(|index| !is_consonant(word.as_bytes(), index))
+}
+
+// the m in [C](VC){m}[V]
+fn measure(word: &str) -> usize {
+  let bytes = word.as_bytes();
+  let mut count = 0;
+  let mut previous_vowel = false;
+  for index in 0..bytes.len() {
+    let vowel = !is_consonant(bytes, index);
+    if previous_vowel && !vowel {
+      count += 1;
+    }
+    previous_vowel = vowel;
+  }
+  count
+}
+
+fn ends_with_double_consonant(word: &str) -> bool {
+  let bytes = word.as_bytes();
+  let length = bytes.len();
+  length >= 2 && bytes[length - 1] == bytes[length - 2] && is_consonant(bytes, length - 1)
+}
+
+// consonant, vowel, consonant and the last one is not w, x or y e.g. hop
+fn ends_with_cvc(word: &str) -> bool {
+  let bytes = word.as_bytes();
+  let length = bytes.len();
+  length >= 3
+    && is_consonant(bytes, length - 3)
+    && !is_consonant(bytes, length - 2)
+    && is_consonant(bytes, length - 1)
+    && !matches!(bytes[length - 1], b'w' | b'x' | b'y')
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::{document_sections, stem, terms, SearchIndex, SearchOptions};
+  use crate::{Options, Target};
+
+  #[test]
+  fn splits_documents_at_headings() {
+    let output = crate::compile(
+      "Intro text.\n\n# Guide\n\nRead **this**.\n\n## Install\n\n```sh\ncargo install scc\n```\n\n## Install\n\n## See [the docs](x.md)\n",
+      Options::new(Target::Html),
+    )
+    .unwrap();
+    let sections = document_sections(&output.ast, "docs/guide.html", Path::new("docs/guide.md"));
+    let found: Vec<_> = sections
+      .iter()
+      .map(|section| {
+        (
+          section.heading.as_deref(),
+          section.url.as_str(),
+          section.content.as_str(),
+        )
+      })
+      .collect();
+    let expected = [
+      (None, "docs/guide.html", "Intro text."),
+      (Some("Guide"), "docs/guide.html#guide", "Read this."),
+      (Some("Install"), "docs/guide.html#install", "cargo install scc"),
+      (Some("Install"), "docs/guide.html#install-1", ""),
+      (Some("See the docs"), "docs/guide.html#see-the-docs", ""),
+    ];
+    assert_eq!(found, expected);
+    assert!(sections.iter().all(|section| section.title == "Guide"));
+    let index = SearchIndex::new(sections, &SearchOptions::default()).index.unwrap();
+    assert_eq!(index["install"], [(2, 2), (3, 1)]);
+    assert!(!index.contains_key("this"));
+  }
+
+  #[test]
+  fn stems_like_porter() {
+    let words = [
+      "caresses",
+      "ponies",
+      "cats",
+      "feed",
+      "agreed",
+      "plastered",
+      "motoring",
+      "sing",
+      "conflated",
+    ];
+    let stems = [
+      "caress", "poni", "cat", "feed", "agree", "plaster", "motor", "sing", "conflate",
+    ];
+    assert_eq!(words.map(stem), stems);
+    let words = ["hopping", "falling", "filing", "happy", "sky"];
+    assert_eq!(words.map(stem), ["hop", "fall", "file", "happi", "sky"]);
+    assert_eq!(terms("The Installed packages, a cat"), ["install", "package", "cat"]);
+  }
+}
@@ -0,0 +1,382 @@
+// ==================
+// scc serve <src> --port 3000
+//
+// builds <src> like `scc build --watch` and serves the output on localhost. every html page gets a
+// small script that listens to server-sent events on /__scc/livereload, the browser reloads after
+// each rebuild. a document that fails to compile is served as an overlay with the error and the
+// source around it, until it compiles again. nothing is loaded from the network.
+//
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs,
+  io::{BufRead, BufReader, Write},
+  net::{TcpListener, TcpStream},
+  path::{Component, Path, PathBuf},
+  sync::{Arc, Mutex},
+  thread,
+};
+
+use crate::{
+  build::{self, BuildOptions},
+  compiler::escape_html,
+  diagnostics::{DiagnosticFormat, Diagnostics},
+  watch::{self, Rebuilt},
+};
+
+const LIVERELOAD_PATH: &str = "/__scc/livereload";
+
+const LIVERELOAD_SCRIPT: &str = r#"<script>
+  new EventSource("/__scc/livereload").onmessage = () => location.reload();
+</script>
+"#;
+
+// lines shown before and after the line of a compile error
+const EXCERPT_CONTEXT: usize = 3;
+
+pub struct ServeOptions {
+  pub build: BuildOptions,
+  pub port: u16,
+  pub format: DiagnosticFormat, // how compile errors are printed, the overlay is always html
+}
+
+// a document that did not compile, kept until it compiles again
+struct CompileError {
+  source: PathBuf,
+  diagnostics: Diagnostics,
+}
+
+struct Server {
+  out: PathBuf,
+  errors: Mutex<HashMap<PathBuf, CompileError>>, // output path relative to <out> -> error
+  clients: Mutex<Vec<TcpStream>>,                // open livereload connections
+}
+
+impl Server {
+  fn set_error(&self, options: &BuildOptions, source: &Path, error: Option<Diagnostics>) {
+    // serve only builds html, whose name does not depend on the frontmatter
+    let output = build::output_path(source, options, &BTreeMap::new());
+    let output = output.strip_prefix(&options.out).unwrap_or(&output).to_path_buf();
+    let mut errors = self.errors.lock().unwrap();
+    match error {
+      Some(diagnostics) => errors.insert(output, CompileError { source: source.to_path_buf(), diagnostics }),
+      None => errors.remove(&output),
+    };
+  }
+
+  // clients that went away fail the write and are dropped
+  fn reload(&self) {
+    let mut clients = self.clients.lock().unwrap();
+    clients.retain_mut(|client| {
+      client
+        .write_all(b"data: reload\n\n")
+        .and_then(|_| client.flush())
+        .is_ok()
+    });
+  }
+}
+
+pub fn serve(options: ServeOptions) -> Result<(), String> {
+  let ServeOptions { build: options, port, format } = options;
+  let server =
+    Arc::new(Server { out: options.out.clone(), errors: Mutex::new(HashMap::new()), clients: Mutex::new(Vec::new()) });
+  let summary = build::build(&options)?;
+  summary.print(format);
+  for (relative, error) in &summary.errors {
+    server.set_error(&options, &options.src.join(relative), Some(error.clone()));
+  }
+
+  let listener =
+    TcpListener::bind(("127.0.0.1", port)).map_err(|err| format!("could not listen on port {}: {}", port, err))?;
+  println!("serving {} on http://localhost:{}", options.out.display(), port);
+  let accepting = Arc::clone(&server);
+  thread::spawn(move || {
+    for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+      let server = Arc::clone(&accepting);
+      thread::spawn(move || handle_connection(stream, &server));
+    }
+  });
+
+  watch::watch_build(&options, format, |options, rebuilt| {
+    for Rebuilt { path, errors } in rebuilt {
+      server.set_error(options, path, errors.clone());
+    }
+    server.reload();
+  })
+}
+
+// ==================
+// http
+//
+fn handle_connection(mut stream: TcpStream, server: &Server) {
+  let target = match read_request(&stream) {
+    Some(target) => target,
+    None => return,
+  };
+  if target == LIVERELOAD_PATH {
+    let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+    if stream.write_all(headers.as_bytes()).is_ok() {
+      server.clients.lock().unwrap().push(stream);
+    }
+    return;
+  }
+  let response = match resolve_path(&server.out, &target) {
+    None => not_found(&target),
+    Some(relative) => {
+      let error = server.errors.lock().unwrap().get(&relative).map(error_overlay);
+      match error {
+        Some(page) => (
+          "500 Internal Server Error",
+          "text/html; charset=utf-8",
+          page.into_bytes(),
+        ),
+        None => static_file(&server.out.join(&relative)).unwrap_or_else(|| not_found(&target)),
+      }
+    }
+  };
+  let _ = write_response(&mut stream, response);
+}
+
+// the decoded path of `GET <path> HTTP/1.1`, without the query string
+fn read_request(stream: &TcpStream) -> Option<String> {
+  let mut reader = BufReader::new(stream);
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line).ok()?;
+  // the headers are not used but have to be read before answering
+  let mut header = String::new();
+  while reader.read_line(&mut header).ok()? > 2 {
+    header.clear();
+  }
+  let mut parts = request_line.split_whitespace();
+  if parts.next()? != "GET" {
+    return None;
+  }
+  let target = parts.next()?;
+  let target = target.split(['?', '#']).next().unwrap_or(target);
+  Some(percent_decode(target))
+}
+
+// /guide/ -> guide/index.html, /guide/intro -> guide/intro.html, `..` is never served
+fn resolve_path(out: &Path, target: &str) -> Option<PathBuf> {
+  let mut relative = PathBuf::from(target.trim_start_matches('/'));
+  if relative
+    .components()
+    .any(|component| !matches!(component, Component::Normal(_)))
+  {
+    return None;
+  }
+  if target.ends_with('/') || out.join(&relative).is_dir() {
+    relative.push("index.html");
+  } else if relative.extension().is_none() {
+    relative.set_extension("html");
+  }
+  Some(relative)
+}
+
+type Response = (&'static str, &'static str, Vec<u8>);
+
+fn static_file(path: &Path) -> Option<Response> {
+  let bytes = fs::read(path).ok()?;
+  let content_type = content_type(path);
+  if content_type.starts_with("text/html") {
+    let page = inject_livereload(&String::from_utf8_lossy(&bytes));
+    return Some(("200 OK", content_type, page.into_bytes()));
+  }
+  Some(("200 OK", content_type, bytes))
+}
+
+fn not_found(target: &str) -> Response {
+  let page = page(
+    "Not found",
+    &format!("<h1>Not found</h1>\n<p><code>{}</code></p>", escape_html(target)),
+  );
+  ("404 Not Found", "text/html; charset=utf-8", page.into_bytes())
+}
+
+fn write_response(stream: &mut TcpStream, (status, content_type, body): Response) -> std::io::Result<()> {
+  let headers = format!(
+    "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+    status,
+    content_type,
+    body.len()
+  );
+  stream.write_all(headers.as_bytes())?;
+  stream.write_all(&body)?;
+  stream.flush()
+}
+
+fn content_type(path: &Path) -> &'static str {
+  let extension = path
+    .extension()
+    .map(|extension| extension.to_string_lossy().to_lowercase());
+  match extension.as_deref() {
+    Some("html") | Some("htm") => "text/html; charset=utf-8",
+    Some("css") => "text/css; charset=utf-8",
+    Some("js") | Some("mjs") | Some("jsx") => "text/javascript; charset=utf-8",
+    Some("json") => "application/json",
+    Some("svg") => "image/svg+xml",
+    Some("png") => "image/png",
+    Some("jpg") | Some("jpeg") => "image/jpeg",
+    Some("gif") => "image/gif",
+    Some("webp") => "image/webp",
+    Some("ico") => "image/x-icon",
+    Some("woff2") => "font/woff2",
+    Some("txt") | Some("md") | Some("mdx") | Some("vue") => "text/plain; charset=utf-8",
+    _ => "application/octet-stream",
+  }
+}
+
+// %20 -> ' ', invalid escapes are kept as they are
+fn percent_decode(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    let hex = bytes
+      .get(index + 1..index + 3)
+      .and_then(|hex| std::str::from_utf8(hex).ok());
+    match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+      Some(byte) if bytes[index] == b'%' => {
+        decoded.push(byte);
+        index += 3;
+      }
+      _ => {
+        decoded.push(bytes[index]);
+        index += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// ==================
+// pages
+//
+fn inject_livereload(html: &str) -> String {
+  match html.rfind("</body>") {
+    Some(index) => format!("{}{}{}", &html[..index], LIVERELOAD_SCRIPT, &html[index..]),
+    None => format!("{}{}", html, LIVERELOAD_SCRIPT),
+  }
+}
+
+fn page(title: &str, body: &str) -> String {
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>{}</title>
+    <style>
+      body {{ margin: 0; padding: 2rem; font: 14px/1.5 ui-monospace, monospace; background: #1e1e1e; color: #ddd; }}
+      h1 {{ color: #ff6b6b; font-size: 1.2rem; }}
+      pre {{ padding: 1rem 0; background: #111; overflow-x: auto; }}
+      .line {{ display: block; padding: 0 1rem; }}
+      .line.error {{ background: #5c1f1f; }}
+      .number {{ display: inline-block; width: 3rem; color: #777; user-select: none; }}
+    </style>
+  </head>
+  <body>
+{}
+{}  </body>
+</html>
+"#,
+    escape_html(title),
+    body,
+    LIVERELOAD_SCRIPT
+  )
+}
+
+fn error_overlay(error: &CompileError) -> String {
+  let source = fs::read_to_string(&error.source).unwrap_or_default();
+  let mut body = format!(
+    "<h1>Could not compile {}</h1>\n",
+    escape_html(&error.source.display().to_string())
+  );
+  for diagnostic in &error.diagnostics.items {
+    body.push_str(&format!("<p>{}</p>\n", escape_html(&diagnostic.to_human())));
+    if let Some(line) = diagnostic.line {
+      // errors at the end of the input point one line past the last one
+      body.push_str(&source_excerpt(&source, line.min(source.lines().count().max(1))));
+    }
+  }
+  page("Compile error", &body)
+}
+
+fn source_excerpt(source: &str, line: usize) -> String {
+  let first = line.saturating_sub(EXCERPT_CONTEXT).max(1);
+  let mut excerpt = String::from("<pre>");
+  for (index, text) in source
+    .lines()
+    .enumerate()
+    .skip(first - 1)
+    .take(line + EXCERPT_CONTEXT + 1 - first)
+  {
+    let number = index + 1;
+    let class = if number == line { "line error" } else { "line" };
+    excerpt.push_str(&format!(
+      "<span class=\"{}\"><span class=\"number\">{}</span>{}</span>",
+      class,
+      number,
+      escape_html(text)
+    ));
+  }
+  excerpt.push_str("</pre>\n");
+  excerpt
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::PathBuf};
+
+  use super::{error_overlay, percent_decode, resolve_path, CompileError};
+  use crate::diagnostics::{Diagnostic, Diagnostics};
+
+  #[test]
+  fn resolves_request_paths() {
+    let out = std::env::temp_dir().join(format!("scc-serve-{}", std::process::id()));
+    fs::create_dir_all(out.join("guide")).unwrap();
+    let resolve = |target: &str| resolve_path(&out, target);
+    assert_eq!(resolve("/"), Some(PathBuf::from("index.html")));
+    assert_eq!(resolve("/guide/"), Some(PathBuf::from("guide/index.html")));
+    assert_eq!(resolve("/guide"), Some(PathBuf::from("guide/index.html")));
+    assert_eq!(resolve("/guide/intro"), Some(PathBuf::from("guide/intro.html")));
+    assert_eq!(resolve("/style.css"), Some(PathBuf::from("style.css")));
+    assert_eq!(resolve("/../secret.txt"), None);
+    assert_eq!(resolve("/guide/../../secret"), None);
+    assert_eq!(resolve("/./index.html"), None);
+    fs::remove_dir_all(out).unwrap();
+  }
+
+  #[test]
+  fn decodes_percent_escapes() {
+    assert_eq!(percent_decode("/a%20b/%C3%A9t%C3%A9.html"), "/a b/été.html");
+    assert_eq!(percent_decode("/%2e%2e/x"), "/../x");
+    assert_eq!(percent_decode("/100%/%zz/%4"), "/100%/%zz/%4");
+  }
+
+  #[test]
+  fn shows_compile_errors_with_the_source() {
+    let dir = std::env::temp_dir().join(format!("scc-overlay-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("<broken>.md");
+    fs::write(&source, "# Title\n\none\ntwo\n<Note>\nthree\nfour\nfive\nsix\n").unwrap();
+    let diagnostics = Diagnostics::from(Diagnostic::error("Expected '</Note>' to close '<Note>'").at(5, 1));
+    let page = error_overlay(&CompileError { source: source.clone(), diagnostics });
+    assert!(page.contains("&lt;broken&gt;.md</h1>"), "{}", page);
+    assert!(
+      page.contains("Expected &#39;&lt;/Note&gt;&#39; to close &#39;&lt;Note&gt;&#39;"),
+      "{}",
+      page
+    );
+    // three lines of context around the error, which is highlighted
+    assert!(
+      page.contains("<span class=\"line\"><span class=\"number\">2</span></span>"),
+      "{}",
+      page
+    );
+    assert!(!page.contains("<span class=\"number\">1</span>"), "{}", page);
+    assert!(page.contains("<span class=\"line error\"><span class=\"number\">5</span>&lt;Note&gt;</span>"));
+    assert!(page.contains("<span class=\"number\">8</span>five") && !page.contains("<span class=\"number\">9</span>"));
+    assert!(page.contains("new EventSource(\"/__scc/livereload\")"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
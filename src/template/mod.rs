@@ -0,0 +1,254 @@
+// ==================
+// full html documents e.g. `scc run doc.md --template page.html`
+//
+// placeholders: {{content}}, {{title}}, {{toc}}, {{stylesheets}}, {{description}}, {{words}}, {{reading_time}} and any
+// frontmatter field e.g. {{author}}, which wins over the others
+//
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  ast,
+  compiler::{escape_html, HtmlCompiler, TextCompiler},
+  transformer::toc_list,
+};
+
+pub const DEFAULT_LAYOUT: &str = r#"<!DOCTYPE html>
+<html lang="{{lang}}">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{{title}}</title>
+{{stylesheets}}
+  </head>
+  <body>
+{{content}}
+  </body>
+</html>
+"#;
+
+// placeholders written as is, everything else is escaped
+const RAW_PLACEHOLDERS: [&str; 3] = ["content", "toc", "stylesheets"];
+
+pub struct Template {
+  source: String,
+}
+
+impl Template {
+  pub fn new(source: &str) -> Self {
+    Self { source: source.to_string() }
+  }
+
+  pub fn default_layout() -> Self {
+    Self::new(DEFAULT_LAYOUT)
+  }
+
+  pub fn from_file(path: &Path) -> Result<Self, String> {
+    let source =
+      fs::read_to_string(path).map_err(|err| format!("could not read template {}: {}", path.display(), err))?;
+    Ok(Self::new(&source))
+  }
+
+  // replaces every `{{ name }}`, unknown names become empty
+  pub fn render(&self, values: &BTreeMap<String, String>) -> String {
+    let mut output = String::with_capacity(self.source.len());
+    let mut rest = self.source.as_str();
+    while let Some(start) = rest.find("{{") {
+      let end = match rest[start..].find("}}") {
+        Some(end) => start + end,
+        None => break,
+      };
+      output.push_str(&rest[..start]);
+      let name = rest[start + 2..end].trim();
+      let value = values.get(name).map(String::as_str).unwrap_or("");
+      if RAW_PLACEHOLDERS.contains(&name) {
+        output.push_str(value);
+      } else {
+        output.push_str(&escape_html(value));
+      }
+      rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+  }
+}
+
+// which layout wraps a document, `layout:` in the frontmatter wins over the cli options
+#[derive(Debug, Clone, Default)]
+pub struct LayoutOptions {
+  pub template: Option<PathBuf>, // --template
+  pub standalone: bool,          // --standalone, use the built-in layout
+  pub layouts_dir: PathBuf,      // --layouts, where `layout: name` is looked up as `name.html`
+  pub stylesheets: Vec<String>,  // --stylesheet
+}
+
+impl LayoutOptions {
+  pub fn resolve(&self, frontmatter: &BTreeMap<String, String>) -> Result<Option<Template>, String> {
+    match frontmatter.get("layout").map(String::as_str) {
+      Some("none") | Some("false") => Ok(None),
+      Some("default") => Ok(Some(Template::default_layout())),
+      Some(name) => Template::from_file(&self.layouts_dir.join(format!("{}.html", name))).map(Some),
+      None => match &self.template {
+        Some(path) => Template::from_file(path).map(Some),
+        None if self.standalone => Ok(Some(Template::default_layout())),
+        None => Ok(None),
+      },
+    }
+  }
+}
+
+// wraps the compiled html `content` in the document layout, or returns it as is when there is none
+pub fn render_page(ast: &ast::Ast, content: &str, options: &LayoutOptions) -> Result<String, String> {
+  let ast::Ast::Root(root) = ast;
+  let template = match options.resolve(&root.frontmatter)? {
+    Some(template) => template,
+    None => return Ok(content.to_string()),
+  };
+  let mut values = root.frontmatter.clone();
+  values.entry("lang".to_string()).or_insert_with(|| "en".to_string());
+  values
+    .entry("title".to_string())
+    .or_insert_with(|| first_heading(&root.children).unwrap_or_default());
+  let (text, stats) = TextCompiler::new().compile_with_stats(ast);
+  values
+    .entry("description".to_string())
+    .or_insert_with(|| description(&text));
+  values.entry("words".to_string()).or_insert(stats.words.to_string());
+  values
+    .entry("reading_time".to_string())
+    .or_insert(stats.reading_time.to_string());
+  values.insert("content".to_string(), content.to_string());
+  values.insert("toc".to_string(), toc_html(&root.toc));
+  values.insert("stylesheets".to_string(), stylesheets_html(options, &root.frontmatter));
+  Ok(template.render(&values))
+}
+
+fn first_heading(nodes: &[ast::Node]) -> Option<String> {
+  nodes.iter().find_map(|node| match node {
+    ast::Node::Heading(heading) => Some(heading.plain_text()),
+    _ => None,
+  })
+}
+
+// the first paragraph of the text cut at a word to fit a meta description
+fn description(text: &str) -> String {
+  const MAX_LENGTH: usize = 160;
+  let paragraph = text
+    .split("\n\n")
+    .find(|block| block.split_whitespace().count() > 3)
+    .unwrap_or_default()
+    .replace('\n', " ");
+  if paragraph.chars().count() <= MAX_LENGTH {
+    return paragraph;
+  }
+  let mut description = String::new();
+  for word in paragraph.split(' ') {
+    if description.chars().count() + word.chars().count() + 1 > MAX_LENGTH - 3 {
+      break;
+    }
+    if !description.is_empty() {
+      description.push(' ');
+    }
+    description.push_str(word);
+  }
+  format!("{}...", description.trim_end_matches(['.', ',', ';', ':']))
+}
+
+fn toc_html(toc: &[ast::TocEntry]) -> String {
+  if toc.is_empty() {
+    return String::new();
+  }
+  let mut root = ast::Root::default();
+  root.children.push(ast::Node::List(toc_list(toc)));
+  HtmlCompiler::new().compile(&mut ast::Ast::Root(root))
+}
+
+// --stylesheet options first, then `stylesheet:` from the frontmatter
+fn stylesheets_html(options: &LayoutOptions, frontmatter: &BTreeMap<String, String>) -> String {
+  let frontmatter = frontmatter.get("stylesheet").map(String::as_str);
+  let hrefs = options.stylesheets.iter().map(String::as_str).chain(frontmatter);
+  let links: Vec<String> = hrefs
+    .map(|href| format!("    <link rel=\"stylesheet\" href=\"{}\" />", escape_html(href)))
+    .collect();
+  links.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::BTreeMap, fs};
+
+  use super::{render_page, LayoutOptions, Template};
+  use crate::parser::MdxParser;
+
+  #[test]
+  fn renders_and_escapes_values() {
+    let template = Template::new("<title>{{ title }}</title>{{content}}{{missing}}{{ unclosed");
+    let values = BTreeMap::from([
+      ("title".to_string(), "Fish & <Chips>".to_string()),
+      ("content".to_string(), "<p>hi</p>".to_string()),
+    ]);
+    let expected = "<title>Fish &amp; &lt;Chips&gt;</title><p>hi</p>{{ unclosed";
+    assert_eq!(template.render(&values), expected);
+  }
+
+  #[test]
+  fn fills_the_page_values() {
+    let ast =
+      MdxParser::parse("---\nauthor: \"Ada\"\n---\n# Notes & [**more**](x.md)\n\nOne two three four five.\n").unwrap();
+    let options = LayoutOptions { stylesheets: vec!["/a.css".to_string()], ..LayoutOptions::default() };
+    let template = "{{title}}|{{author}}|{{description}}|{{words}}|{{lang}}|{{stylesheets}}";
+    let dir = std::env::temp_dir().join(format!("scc-template-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("page.html"), template).unwrap();
+    let options = LayoutOptions { template: Some(dir.join("page.html")), ..options };
+    let page = render_page(&ast, "", &options).unwrap();
+    let expected = "Notes &amp; more|Ada|One two three four five.|8|en|    <link rel=\"stylesheet\" href=\"/a.css\" />";
+    assert_eq!(page, expected);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn resolves_layouts() {
+    let dir = std::env::temp_dir().join(format!("scc-layouts-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("post.html"), "<article>{{content}}</article>").unwrap();
+    fs::write(dir.join("page.html"), "<main>{{content}}</main>").unwrap();
+    let options =
+      LayoutOptions { template: Some(dir.join("page.html")), layouts_dir: dir.clone(), ..LayoutOptions::default() };
+    let render = |layout: &str| {
+      let source = match layout {
+        "" => "# Hi\n".to_string(),
+        layout => format!("---\nlayout: {}\n---\n# Hi\n", layout),
+      };
+      render_page(&MdxParser::parse(&source).unwrap(), "<p>x</p>", &options)
+    };
+    // the frontmatter wins over --template
+    assert_eq!(render("post").unwrap(), "<article><p>x</p></article>");
+    assert_eq!(render("").unwrap(), "<main><p>x</p></main>");
+    assert_eq!(render("none").unwrap(), "<p>x</p>");
+    assert!(render("default").unwrap().starts_with("<!DOCTYPE html>"));
+    let error = render("missing").unwrap_err();
+    assert!(error.starts_with("could not read template"), "{}", error);
+    assert!(
+      error.contains(&dir.join("missing.html").display().to_string()),
+      "{}",
+      error
+    );
+
+    // --standalone uses the built-in layout, without it the content is returned as is
+    let standalone = LayoutOptions { standalone: true, ..LayoutOptions::default() };
+    let page = render_page(&MdxParser::parse("# Hi\n").unwrap(), "<p>x</p>", &standalone).unwrap();
+    assert!(page.contains("<title>Hi</title>") && page.contains("<p>x</p>"));
+    let page = render_page(
+      &MdxParser::parse("# Hi\n").unwrap(),
+      "<p>x</p>",
+      &LayoutOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(page, "<p>x</p>");
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,161 @@
+// ==================
+// heading ids compatible with github e.g. `## Hello, World!` -> hello-world
+// ref: https://github.com/Flet/github-slugger
+//
+use std::collections::HashSet;
+
+use crate::{
+  ast::{
+    self,
+    visit::{Action, VisitorMut},
+  },
+  transformer::Transformer,
+};
+
+// lowercases and keeps letters, numbers, `_`, `-` and spaces (as `-`), unicode included.
+// html comments e.g. `<!-- no-toc -->` are not part of the slug.
+pub fn slugify(text: &str) -> String {
+  let mut visible = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find("<!--") {
+    visible.push_str(&rest[..start]);
+    rest = rest[start..].find("-->").map_or("", |end| &rest[start + end + 3..]);
+  }
+  visible.push_str(rest);
+  let mut slug = String::with_capacity(visible.len());
+  for character in visible.trim().chars() {
+    if character.is_alphanumeric() || character == '_' || character == '-' {
+      slug.extend(character.to_lowercase());
+    } else if character == ' ' {
+      slug.push('-');
+    }
+  }
+  slug
+}
+
+// the slug of the heading's plain text, e.g. `## See [the docs](url)` -> see-the-docs like github
+pub fn heading_slug(heading: &ast::Heading) -> String {
+  slugify(&heading.plain_text())
+}
+
+// dedupes slugs in the same document with `-1`, `-2` suffixes
+#[derive(Debug, Default)]
+pub struct Slugger {
+  seen: HashSet<String>,
+}
+
+impl Slugger {
+  pub fn new() -> Self {
+    Self { seen: HashSet::new() }
+  }
+
+  pub fn slug(&mut self, text: &str) -> String {
+    let base = slugify(text);
+    let mut slug = base.clone();
+    let mut count = 0;
+    while self.seen.contains(&slug) {
+      count += 1;
+      slug = format!("{}-{}", base, count);
+    }
+    self.seen.insert(slug.clone());
+    slug
+  }
+
+  // the id the heading slug transformer gives a heading, None when it has no text to slug e.g. `# `
+  pub fn heading(&mut self, heading: &ast::Heading) -> Option<String> {
+    let slug = heading_slug(heading);
+    (!slug.is_empty()).then(|| self.slug(&slug))
+  }
+}
+
+#[derive(Default)]
+pub struct TransformerHeadingSlug {
+  anchor: Option<ast::AnchorPosition>,
+}
+
+impl TransformerHeadingSlug {
+  pub fn new() -> Self {
+    Self { anchor: None }
+  }
+
+  pub fn with_anchor(mut self, position: ast::AnchorPosition) -> Self {
+    self.anchor = Some(position);
+    self
+  }
+}
+
+impl Transformer for TransformerHeadingSlug {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    let mut slugger = Slugger::new();
+    (|node: &mut ast::Node| {
+      if let ast::Node::Heading(heading) = node {
+        heading.id = slugger.heading(heading);
+        heading.anchor = self.anchor;
+      }
+      Action::Continue
+    })
+    .visit_ast(ast);
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{slugify, Slugger, TransformerHeadingSlug};
+  use crate::{ast::AnchorPosition, compiler::HtmlCompiler, parser::MdxParser, transformer::Transformer};
+
+  #[test]
+  fn slugifies_like_github() {
+    assert_eq!(slugify("Hello, World!"), "hello-world");
+    assert_eq!(slugify("  API (v2) & more  "), "api-v2--more");
+    assert_eq!(slugify("snake_case and-dash"), "snake_case-and-dash");
+    assert_eq!(slugify("Ünïcödé Straße"), "ünïcödé-straße");
+    assert_eq!(slugify("日本語 タイトル"), "日本語-タイトル");
+    assert_eq!(slugify("Emoji 🚀 rocket"), "emoji--rocket");
+    assert_eq!(slugify("Intro <!-- no-toc -->"), "intro");
+    assert_eq!(slugify(""), "");
+    assert_eq!(slugify("?!"), "");
+  }
+
+  #[test]
+  fn dedupes_slugs() {
+    let mut slugger = Slugger::new();
+    let slugs: Vec<String> = ["Intro", "Intro", "intro", "Intro-1", "", ""]
+      .iter()
+      .map(|text| slugger.slug(text))
+      .collect();
+    assert_eq!(slugs, ["intro", "intro-1", "intro-2", "intro-1-1", "", "-1"]);
+  }
+
+  #[test]
+  fn slugs_the_plain_text_of_headings() {
+    let mut ast =
+      MdxParser::parse("## See [the *docs*](https://example.com/guide)\n\n# \n\n### **Bold** `code` _it_\n").unwrap();
+    let mut ast = TransformerHeadingSlug::new().transform(&mut ast);
+    assert_eq!(
+      HtmlCompiler::new().compile(&mut ast),
+      "<h2 id=\"see-the-docs\">See <a href=\"https://example.com/guide\">the <i>docs</i></a></h2>\n<h1></h1>\n<h3 id=\"bold-code-it\"><b>Bold</b> <code>code</code> <i>it</i></h3>"
+    );
+  }
+
+  #[test]
+  fn places_anchors() {
+    let compile = |position: AnchorPosition| {
+      let mut ast = MdxParser::parse("## API\n").unwrap();
+      let mut ast = TransformerHeadingSlug::new().with_anchor(position).transform(&mut ast);
+      HtmlCompiler::new().compile(&mut ast)
+    };
+    assert_eq!(
+      compile(AnchorPosition::Before),
+      "<h2 id=\"api\"><a class=\"anchor\" href=\"#api\">#</a>API</h2>"
+    );
+    assert_eq!(
+      compile(AnchorPosition::After),
+      "<h2 id=\"api\">API<a class=\"anchor\" href=\"#api\">#</a></h2>"
+    );
+    assert_eq!(
+      compile(AnchorPosition::Wrap),
+      "<h2 id=\"api\"><a class=\"anchor\" href=\"#api\">API</a></h2>"
+    );
+  }
+}
@@ -1,7 +1,37 @@
 use crate::ast;
 
-mod notation_diff;
+mod heading_slug;
+pub mod notation;
+mod rewrite_links;
+mod table_of_contents;
+
+pub use heading_slug::{heading_slug, slugify, Slugger, TransformerHeadingSlug};
+pub use notation::{
+  TransformerNotationDiff, TransformerNotationErrorLevel, TransformerNotationFocus, TransformerNotationHighlight,
+  TransformerNotationWordHighlight,
+};
+pub use rewrite_links::{is_relative, TransformerRewriteLinks};
+pub use table_of_contents::{toc_list, toc_to_json, TransformerTableOfContents};
+
+// the transformers `scc` runs when scc.toml does not list them
+pub fn default_transformers() -> Vec<Box<dyn Transformer>> {
+  vec![
+    Box::new(TransformerHeadingSlug::new()),
+    Box::new(TransformerTableOfContents::new()),
+    Box::new(TransformerNotationDiff::default()),
+    Box::new(TransformerNotationHighlight::new()),
+    Box::new(TransformerNotationFocus::new()),
+    Box::new(TransformerNotationErrorLevel::new()),
+    Box::new(TransformerNotationWordHighlight::new()),
+  ]
+}
 
 pub trait Transformer {
   fn transform(&self, ast: &mut ast::Ast) -> ast::Ast;
+
+  // shown by `scc inspect --stages` and `--debug`, the type name unless overridden
+  fn name(&self) -> &'static str {
+    let name = std::any::type_name::<Self>();
+    name.rsplit("::").next().unwrap_or(name)
+  }
 }
@@ -1 +1,201 @@
+// ==================
+// code notations e.g. `console.log("hello") // [!code ++]`
+// ref: https://shiki.style/packages/transformers
+//
+use crate::ast::{
+  self,
+  visit::{Action, VisitorMut},
+};
+
 mod transformer_notation_diff;
+mod transformer_notation_error_level;
+mod transformer_notation_focus;
+mod transformer_notation_highlight;
+mod transformer_notation_word_highlight;
+
+pub use transformer_notation_diff::TransformerNotationDiff;
+pub use transformer_notation_error_level::TransformerNotationErrorLevel;
+pub use transformer_notation_focus::TransformerNotationFocus;
+pub use transformer_notation_highlight::TransformerNotationHighlight;
+pub use transformer_notation_word_highlight::TransformerNotationWordHighlight;
+
+const NOTATION_START: &str = "[!code ";
+
+// comment syntax (open, close) used to write notations in each language
+pub fn comment_markers(language: &str) -> (&'static str, &'static str) {
+  match language.to_lowercase().as_str() {
+    "py" | "python" | "rb" | "ruby" | "sh" | "bash" | "shell" | "zsh" | "fish" | "yaml" | "yml" | "toml" | "r"
+    | "perl" | "dockerfile" | "makefile" | "elixir" | "ex" | "nix" => ("#", ""),
+    "sql" | "lua" | "haskell" | "hs" | "elm" => ("--", ""),
+    "html" | "xml" | "svg" | "vue" | "svelte" | "md" | "mdx" | "markdown" => ("<!--", "-->"),
+    "css" | "scss" | "less" => ("/*", "*/"),
+    "lisp" | "clojure" | "scheme" | "asm" => (";", ""),
+    _ => ("//", ""),
+  }
+}
+
+// splits `code // [!code ++]` into (`code`, `++`)
+// when the comment has more text than the notation, the rest of the comment is kept.
+pub fn parse_notation<'l>(line: &'l str, language: &str) -> Option<(String, &'l str)> {
+  let (open, close) = comment_markers(language);
+  let mut body = line.trim_end();
+  if !close.is_empty() {
+    body = body.strip_suffix(close)?.trim_end();
+  }
+  let body = body.strip_suffix(']')?;
+  let start = body.rfind(NOTATION_START)?;
+  let notation = body[start + NOTATION_START.len()..].trim();
+  let before = body[..start].trim_end();
+  if let Some(code) = before.strip_suffix(open) {
+    return Some((code.trim_end().to_string(), notation));
+  }
+  if !before.contains(open) {
+    return None;
+  }
+  if close.is_empty() {
+    return Some((before.to_string(), notation));
+  }
+  Some((format!("{} {}", before, close), notation))
+}
+
+// strips every notation `accepts` matches, then runs `apply` with the index of the line it belongs to.
+// lines left empty once the notation is removed are dropped and the notation applies to the next line.
+// returns true if any notation marked a line, `apply` tells if it did.
+pub fn transform_notation(
+  code_block: &mut ast::CodeBlock,
+  accepts: impl Fn(&str) -> bool,
+  mut apply: impl FnMut(&str, usize, &mut ast::CodeBlock) -> bool,
+) -> bool {
+  code_block.ensure_lines();
+  let language = code_block.language.clone();
+  let mut code = Vec::new();
+  let mut lines = Vec::new();
+  let mut notations = Vec::new();
+  for (line, code_line) in code_block.code.split('\n').zip(code_block.lines.drain(..)) {
+    match parse_notation(line, &language) {
+      Some((stripped, notation)) if accepts(notation) => {
+        if stripped.trim().is_empty() {
+          notations.push((notation.to_string(), code.len()));
+          continue;
+        }
+        notations.push((notation.to_string(), code.len()));
+        code.push(stripped);
+        lines.push(code_line);
+      }
+      _ => {
+        code.push(line.to_string());
+        lines.push(code_line);
+      }
+    }
+  }
+  code_block.code = code.join("\n");
+  code_block.lines = lines;
+  code_block.ensure_lines();
+  let mut marked = false;
+  for (notation, index) in &notations {
+    marked |= apply(notation, *index, code_block);
+  }
+  marked
+}
+
+// matches `keyword` and `keyword:count`, returns how many lines the notation applies to
+pub fn parse_range(notation: &str, keyword: &str) -> Option<usize> {
+  let rest = notation.strip_prefix(keyword)?;
+  if rest.is_empty() {
+    return Some(1);
+  }
+  rest.strip_prefix(':')?.trim().parse::<usize>().ok()
+}
+
+// runs `mark` for `count` lines starting at `index`, stopping at the end of the code block
+pub fn mark_lines(
+  code_block: &mut ast::CodeBlock,
+  index: usize,
+  count: usize,
+  mut mark: impl FnMut(&mut ast::CodeLine),
+) -> bool {
+  let mut marked = false;
+  for line in code_block.lines.iter_mut().skip(index).take(count) {
+    mark(line);
+    marked = true;
+  }
+  marked
+}
+
+pub fn visit_code_blocks(ast: &mut ast::Ast, visit: &mut impl FnMut(&mut ast::CodeBlock)) {
+  (|node: &mut ast::Node| {
+    if let ast::Node::CodeBlock(code_block) = node {
+      visit(code_block);
+    }
+    Action::Continue
+  })
+  .visit_ast(ast);
+}
+
+// runs `transformer` over a document of a single code block and returns the block
+#[cfg(test)]
+pub fn transform_code(transformer: &dyn crate::transformer::Transformer, language: &str, code: &str) -> ast::CodeBlock {
+  let code_block = ast::CodeBlock::new(language.to_string(), code.to_string(), None);
+  let mut root = ast::Root::default();
+  root.children.push(ast::Node::CodeBlock(code_block));
+  let ast::Ast::Root(root) = transformer.transform(&mut ast::Ast::Root(root));
+  match root.children.first() {
+    Some(ast::Node::CodeBlock(code_block)) => code_block.clone(),
+    _ => panic!("the code block was removed"),
+  }
+}
+
+// the classes of each line, space separated
+#[cfg(test)]
+pub fn line_classes(code_block: &ast::CodeBlock) -> Vec<String> {
+  code_block.lines.iter().map(|line| line.classes.join(" ")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_notation, parse_range};
+
+  #[test]
+  fn parses_notations_in_each_comment_syntax() {
+    assert_eq!(
+      parse_notation("let a = 1; // [!code ++]", "js"),
+      Some(("let a = 1;".to_string(), "++"))
+    );
+    assert_eq!(
+      parse_notation("a = 1  # [!code --]", "py"),
+      Some(("a = 1".to_string(), "--"))
+    );
+    assert_eq!(
+      parse_notation("select 1; -- [!code ++]", "sql"),
+      Some(("select 1;".to_string(), "++"))
+    );
+    assert_eq!(
+      parse_notation("<p>hi</p> <!-- [!code --] -->", "html"),
+      Some(("<p>hi</p>".to_string(), "--"))
+    );
+    assert_eq!(
+      parse_notation("a { } /* [!code ++] */", "css"),
+      Some(("a { }".to_string(), "++"))
+    );
+    // the rest of the comment is kept
+    assert_eq!(
+      parse_notation("f(); // call it [!code ++]", "js"),
+      Some(("f(); // call it".to_string(), "++"))
+    );
+    // a comment of another language is not a notation
+    assert_eq!(parse_notation("a = 1 // [!code ++]", "py"), None);
+    assert_eq!(parse_notation("let a = 1;", "js"), None);
+  }
+
+  #[test]
+  fn parses_ranges() {
+    assert_eq!(parse_range("highlight", "highlight"), Some(1));
+    assert_eq!(parse_range("highlight:3", "highlight"), Some(3));
+    assert_eq!(parse_range("highlight:0", "highlight"), Some(0));
+    assert_eq!(parse_range("highlight: 2", "highlight"), Some(2));
+    assert_eq!(parse_range("highlight:x", "highlight"), None);
+    assert_eq!(parse_range("highlight:-1", "highlight"), None);
+    assert_eq!(parse_range("highlights", "highlight"), None);
+    assert_eq!(parse_range("focus", "highlight"), None);
+  }
+}
@@ -0,0 +1,97 @@
+use super::{mark_lines, transform_notation, visit_code_blocks};
+use crate::{ast, transformer::Transformer};
+
+// `// [!code ++]` and `// [!code --]`
+pub struct TransformerNotationDiff {
+  pub old: String,
+  pub new: String,
+}
+
+impl Default for TransformerNotationDiff {
+  fn default() -> Self {
+    Self::new("--", "++")
+  }
+}
+
+impl TransformerNotationDiff {
+  pub fn new(old: &str, new: &str) -> Self {
+    Self { old: old.to_string(), new: new.to_string() }
+  }
+}
+
+impl Transformer for TransformerNotationDiff {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    visit_code_blocks(ast, &mut |code_block| {
+      let accepts = |notation: &str| notation == self.new || notation == self.old;
+      let has_diff = transform_notation(code_block, accepts, |notation, index, code_block| {
+        let class = if notation == self.new { "add" } else { "remove" };
+        mark_lines(code_block, index, 1, |line| {
+          line.add_class("diff");
+          line.add_class(class);
+        })
+      });
+      if has_diff {
+        code_block.add_class("has-diff");
+      }
+    });
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::TransformerNotationDiff;
+  use crate::transformer::notation::{line_classes as classes, transform_code};
+
+  #[test]
+  fn marks_added_and_removed_lines_in_each_comment_syntax() {
+    let cases = [
+      (
+        "js",
+        "let a = 1; // [!code --]\nlet a = 2; // [!code ++]\nlet b = 3;",
+        "let a = 1;\nlet a = 2;\nlet b = 3;",
+      ),
+      (
+        "py",
+        "a = 1 # [!code --]\na = 2 # [!code ++]\nb = 3",
+        "a = 1\na = 2\nb = 3",
+      ),
+      (
+        "sql",
+        "select 1; -- [!code --]\nselect 2; -- [!code ++]\nselect 3;",
+        "select 1;\nselect 2;\nselect 3;",
+      ),
+      (
+        "html",
+        "<b>a</b> <!-- [!code --] -->\n<b>b</b> <!-- [!code ++] -->\n<b>c</b>",
+        "<b>a</b>\n<b>b</b>\n<b>c</b>",
+      ),
+    ];
+    for (language, code, expected) in cases {
+      let code_block = transform_code(&TransformerNotationDiff::default(), language, code);
+      assert_eq!(code_block.code, expected, "{}", language);
+      assert_eq!(classes(&code_block), ["diff remove", "diff add", ""], "{}", language);
+      assert_eq!(code_block.classes, ["has-diff"]);
+    }
+  }
+
+  #[test]
+  fn leaves_markers_in_strings_and_other_comments() {
+    let code = "let a = \"// [!code ++]\";\nlet b = 1; # [!code ++]";
+    let code_block = transform_code(&TransformerNotationDiff::default(), "js", code);
+    assert_eq!(code_block.code, code);
+    assert_eq!(classes(&code_block), ["", ""]);
+    assert!(code_block.classes.is_empty());
+  }
+
+  #[test]
+  fn applies_a_marker_on_its_own_line_to_the_next_line() {
+    let code_block = transform_code(
+      &TransformerNotationDiff::default(),
+      "js",
+      "// [!code ++]\nlet a = 1;\nlet b = 2;",
+    );
+    assert_eq!(code_block.code, "let a = 1;\nlet b = 2;");
+    assert_eq!(classes(&code_block), ["diff add", ""]);
+  }
+}
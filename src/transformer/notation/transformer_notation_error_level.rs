@@ -0,0 +1,55 @@
+use super::{mark_lines, parse_range, transform_notation, visit_code_blocks};
+use crate::{ast, transformer::Transformer};
+
+const LEVELS: [&str; 2] = ["error", "warning"];
+
+// `// [!code error]` and `// [!code warning]`
+#[derive(Default)]
+pub struct TransformerNotationErrorLevel {}
+
+impl TransformerNotationErrorLevel {
+  pub fn new() -> Self {
+    Self {}
+  }
+
+  fn parse(notation: &str) -> Option<(&'static str, usize)> {
+    LEVELS
+      .iter()
+      .find_map(|level| parse_range(notation, level).map(|count| (*level, count)))
+  }
+}
+
+impl Transformer for TransformerNotationErrorLevel {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    visit_code_blocks(ast, &mut |code_block| {
+      let accepts = |notation: &str| Self::parse(notation).is_some();
+      let has_highlighted = transform_notation(code_block, accepts, |notation, index, code_block| {
+        let (level, count) = Self::parse(notation).unwrap_or(("error", 1));
+        mark_lines(code_block, index, count, |line| {
+          line.add_class("highlighted");
+          line.add_class(level);
+        })
+      });
+      if has_highlighted {
+        code_block.add_class("has-highlighted");
+      }
+    });
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::TransformerNotationErrorLevel;
+  use crate::transformer::notation::{line_classes, transform_code};
+
+  #[test]
+  fn marks_errors_and_warnings() {
+    let code = "a(); // [!code error]\nb(); // [!code warning:2]\nc();\nd();";
+    let code_block = transform_code(&TransformerNotationErrorLevel::new(), "ts", code);
+    assert_eq!(code_block.code, "a();\nb();\nc();\nd();");
+    let expected = ["highlighted error", "highlighted warning", "highlighted warning", ""];
+    assert_eq!(line_classes(&code_block), expected);
+    assert_eq!(code_block.classes, ["has-highlighted"]);
+  }
+}
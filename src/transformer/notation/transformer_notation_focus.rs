@@ -0,0 +1,43 @@
+use super::{mark_lines, parse_range, transform_notation, visit_code_blocks};
+use crate::{ast, transformer::Transformer};
+
+// `// [!code focus]` and `// [!code focus:3]`, lines without focus are blurred by the theme css
+#[derive(Default)]
+pub struct TransformerNotationFocus {}
+
+impl TransformerNotationFocus {
+  pub fn new() -> Self {
+    Self {}
+  }
+}
+
+impl Transformer for TransformerNotationFocus {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    visit_code_blocks(ast, &mut |code_block| {
+      let accepts = |notation: &str| parse_range(notation, "focus").is_some();
+      let has_focused = transform_notation(code_block, accepts, |notation, index, code_block| {
+        let count = parse_range(notation, "focus").unwrap_or(1);
+        mark_lines(code_block, index, count, |line| line.add_class("focused"))
+      });
+      if has_focused {
+        code_block.add_class("has-focused");
+      }
+    });
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::TransformerNotationFocus;
+  use crate::transformer::notation::{line_classes, transform_code};
+
+  #[test]
+  fn focuses_lines() {
+    let code = "# [!code focus:2]\na = 1\nb = 2\nc = 3";
+    let code_block = transform_code(&TransformerNotationFocus::new(), "py", code);
+    assert_eq!(code_block.code, "a = 1\nb = 2\nc = 3");
+    assert_eq!(line_classes(&code_block), ["focused", "focused", ""]);
+    assert_eq!(code_block.classes, ["has-focused"]);
+  }
+}
@@ -0,0 +1,72 @@
+use super::{mark_lines, parse_range, transform_notation, visit_code_blocks};
+use crate::{ast, transformer::Transformer};
+
+// `// [!code highlight]`, `// [!code hl]` and `// [!code highlight:3]`
+#[derive(Default)]
+pub struct TransformerNotationHighlight {}
+
+impl TransformerNotationHighlight {
+  pub fn new() -> Self {
+    Self {}
+  }
+
+  fn parse(notation: &str) -> Option<usize> {
+    parse_range(notation, "highlight").or_else(|| parse_range(notation, "hl"))
+  }
+}
+
+impl Transformer for TransformerNotationHighlight {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    visit_code_blocks(ast, &mut |code_block| {
+      let accepts = |notation: &str| Self::parse(notation).is_some();
+      let has_highlighted = transform_notation(code_block, accepts, |notation, index, code_block| {
+        let count = Self::parse(notation).unwrap_or(1);
+        mark_lines(code_block, index, count, |line| line.add_class("highlighted"))
+      });
+      if has_highlighted {
+        code_block.add_class("has-highlighted");
+      }
+    });
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::TransformerNotationHighlight;
+  use crate::transformer::notation::{line_classes, transform_code};
+
+  #[test]
+  fn highlights_a_range_of_lines() {
+    let code = "a(); // [!code highlight:3]\nb();\nc();\nd(); // [!code hl]\ne();";
+    let code_block = transform_code(&TransformerNotationHighlight::new(), "js", code);
+    assert_eq!(code_block.code, "a();\nb();\nc();\nd();\ne();");
+    assert_eq!(
+      line_classes(&code_block),
+      ["highlighted", "highlighted", "highlighted", "highlighted", ""]
+    );
+    assert_eq!(code_block.classes, ["has-highlighted"]);
+  }
+
+  #[test]
+  fn clamps_ranges_to_the_block() {
+    let code_block = transform_code(&TransformerNotationHighlight::new(), "js", "a();\nb(); // [!code hl:5]");
+    assert_eq!(line_classes(&code_block), ["", "highlighted"]);
+
+    // a range of 0 removes the notation but marks nothing
+    let code_block = transform_code(
+      &TransformerNotationHighlight::new(),
+      "js",
+      "a(); // [!code highlight:0]\nb();",
+    );
+    assert_eq!(code_block.code, "a();\nb();");
+    assert_eq!(line_classes(&code_block), ["", ""]);
+    assert!(code_block.classes.is_empty());
+
+    // on the last line with nothing after it
+    let code_block = transform_code(&TransformerNotationHighlight::new(), "js", "a();\n// [!code hl]");
+    assert_eq!(code_block.code, "a();");
+    assert_eq!(line_classes(&code_block), [""]);
+    assert!(code_block.classes.is_empty());
+  }
+}
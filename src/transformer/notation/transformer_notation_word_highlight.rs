@@ -0,0 +1,68 @@
+use super::{mark_lines, transform_notation, visit_code_blocks};
+use crate::{ast, transformer::Transformer};
+
+// `// [!code word:Foo]` highlights Foo until the end of the block, `// [!code word:Foo:2]` only in the next 2 lines
+#[derive(Default)]
+pub struct TransformerNotationWordHighlight {}
+
+impl TransformerNotationWordHighlight {
+  pub fn new() -> Self {
+    Self {}
+  }
+
+  fn parse(notation: &str) -> Option<(&str, Option<usize>)> {
+    let rest = notation.strip_prefix("word:")?;
+    let (word, count) = match rest.rsplit_once(':') {
+      Some((word, count)) if count.parse::<usize>().is_ok() => (word, count.parse::<usize>().ok()),
+      _ => (rest, None),
+    };
+    if word.is_empty() {
+      return None;
+    }
+    Some((word, count))
+  }
+}
+
+impl Transformer for TransformerNotationWordHighlight {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    visit_code_blocks(ast, &mut |code_block| {
+      let accepts = |notation: &str| Self::parse(notation).is_some();
+      let has_word = transform_notation(code_block, accepts, |notation, index, code_block| {
+        let Some((word, count)) = Self::parse(notation) else {
+          return false;
+        };
+        let count = count.unwrap_or(code_block.lines.len());
+        mark_lines(code_block, index, count, |line| line.add_word(word))
+      });
+      if has_word {
+        code_block.add_class("has-highlighted-words");
+      }
+    });
+    ast.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::TransformerNotationWordHighlight;
+  use crate::transformer::notation::transform_code;
+
+  #[test]
+  fn highlights_words() {
+    let code = "// [!code word:Foo:2]\nnew Foo();\nFoo.bar();\nFoo.baz();";
+    let code_block = transform_code(&TransformerNotationWordHighlight::new(), "js", code);
+    assert_eq!(code_block.code, "new Foo();\nFoo.bar();\nFoo.baz();");
+    let words: Vec<&[String]> = code_block.lines.iter().map(|line| line.words.as_slice()).collect();
+    assert_eq!(words, [&["Foo".to_string()][..], &["Foo".to_string()], &[]]);
+    assert_eq!(code_block.classes, ["has-highlighted-words"]);
+
+    // without a count the word is highlighted until the end of the block
+    let code_block = transform_code(
+      &TransformerNotationWordHighlight::new(),
+      "js",
+      "a(); // [!code word:a]\nb(a);",
+    );
+    assert!(code_block.lines.iter().all(|line| line.words == ["a"]));
+    assert_eq!(code_block.lines[0].segments("a();"), [("a", true), ("();", false)]);
+  }
+}
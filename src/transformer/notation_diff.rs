@@ -1,4 +0,0 @@
-pub struct TransformerNotationDiff {
-  pub old: String,
-  pub new: String,
-}
@@ -0,0 +1,64 @@
+// ==================
+// relative links to other documents point to their compiled output e.g. [API](./api.md#usage) -> ./api.html#usage
+//
+use crate::{
+  ast::{
+    self,
+    visit::{Action, VisitorMut},
+  },
+  transformer::Transformer,
+};
+
+const SOURCE_EXTENSIONS: [&str; 2] = [".md", ".mdx"];
+
+pub struct TransformerRewriteLinks {
+  extension: String,
+}
+
+impl TransformerRewriteLinks {
+  pub fn new(extension: &str) -> Self {
+    Self { extension: extension.to_string() }
+  }
+
+  pub fn rewrite(&self, url: &str) -> Option<String> {
+    if !is_relative(url) {
+      return None;
+    }
+    let (path, fragment) = match url.find(['#', '?']) {
+      Some(index) => url.split_at(index),
+      None => (url, ""),
+    };
+    let extension = SOURCE_EXTENSIONS.iter().find(|extension| path.ends_with(*extension))?;
+    let stem = &path[..path.len() - extension.len()];
+    Some(format!("{}.{}{}", stem, self.extension, fragment))
+  }
+}
+
+impl Transformer for TransformerRewriteLinks {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    (|node: &mut ast::Node| {
+      if let ast::Node::Link(link) = node {
+        if let Some(url) = self.rewrite(&link.url) {
+          link.url = url;
+        }
+      }
+      Action::Continue
+    })
+    .visit_ast(ast);
+    ast.clone()
+  }
+}
+
+// `guide.md`, `./guide.md`, `../guide.md` but not `/guide.md`, `#guide` or `https://example.com/guide.md`
+pub fn is_relative(url: &str) -> bool {
+  !(url.is_empty() || url.starts_with('/') || url.starts_with('#') || url.starts_with("//") || has_scheme(url))
+}
+
+fn has_scheme(url: &str) -> bool {
+  match url.find(':') {
+    Some(index) => url[..index]
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+    None => false,
+  }
+}
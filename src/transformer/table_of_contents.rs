@@ -0,0 +1,327 @@
+// ==================
+// table of contents e.g. `[[toc]]` or `<TableOfContents />`
+//
+use crate::{
+  ast::{
+    self,
+    visit::{Action, VisitorMut},
+  },
+  parser::MdxParser,
+  transformer::Slugger,
+  transformer::Transformer,
+};
+
+const PLACEHOLDERS: [&str; 2] = ["[[toc]]", "<TableOfContents />"];
+const COMPONENT_NAME: &str = "TableOfContents";
+
+pub struct TransformerTableOfContents {
+  min_depth: usize,
+  max_depth: usize,
+  exclude_marker: String, // headings containing the marker are left out e.g. `## Changelog <!-- no-toc -->`
+}
+
+impl Default for TransformerTableOfContents {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl TransformerTableOfContents {
+  pub fn new() -> Self {
+    Self { min_depth: 1, max_depth: 6, exclude_marker: "<!-- no-toc -->".to_string() }
+  }
+
+  pub fn with_depth(mut self, min_depth: usize, max_depth: usize) -> Self {
+    self.min_depth = min_depth;
+    self.max_depth = max_depth;
+    self
+  }
+
+  pub fn with_exclude_marker(mut self, marker: &str) -> Self {
+    self.exclude_marker = marker.to_string();
+    self
+  }
+
+  // headings anywhere in the document, in blockquotes, lists and components too
+  fn collect(&self, ast: &mut ast::Ast) -> Vec<ast::TocEntry> {
+    let mut slugger = Slugger::new();
+    let mut entries = Vec::new();
+    (|node: &mut ast::Node| {
+      let ast::Node::Heading(heading) = node else {
+        return Action::Continue;
+      };
+      if heading.id.is_none() {
+        heading.id = slugger.heading(heading);
+      }
+      if !self.exclude_marker.is_empty() && heading.text.contains(&self.exclude_marker) {
+        heading.text = heading.text.replace(&self.exclude_marker, "").trim().to_string();
+        return Action::Skip;
+      }
+      // a heading without an id e.g. `# ` can not be linked to
+      let Some(id) = heading.id.clone() else {
+        return Action::Skip;
+      };
+      if heading.level >= self.min_depth && heading.level <= self.max_depth {
+        entries.push(ast::TocEntry {
+          level: heading.level,
+          text: heading.plain_text(),
+          markdown: without_links(heading.text.trim()),
+          id,
+          children: Vec::new(),
+        });
+      }
+      Action::Skip
+    })
+    .visit_ast(ast);
+    entries
+  }
+
+  // a document without headings loses its placeholders rather than getting an empty list
+  fn replace_placeholders(&self, ast: &mut ast::Ast, toc: &[ast::TocEntry]) {
+    (|node: &mut ast::Node| match is_placeholder(node) {
+      true if toc.is_empty() => Action::Replace(Vec::new()),
+      true => Action::Replace(vec![ast::Node::List(toc_list(toc))]),
+      false => Action::Continue,
+    })
+    .visit_ast(ast);
+  }
+}
+
+impl Transformer for TransformerTableOfContents {
+  fn transform(&self, ast: &mut ast::Ast) -> ast::Ast {
+    let toc = nest(self.collect(ast));
+    self.replace_placeholders(ast, &toc);
+    let ast::Ast::Root(root) = ast;
+    root.toc = toc;
+    ast.clone()
+  }
+}
+
+// links can not be nested, the links of a heading become their text in its toc link e.g.
+// `See [the docs](url) **now**` -> `See the docs **now**`
+fn without_links(text: &str) -> String {
+  match MdxParser::parse_inline(text) {
+    Ok(nodes) => markdown(&nodes),
+    Err(_) => text.to_string(),
+  }
+}
+
+fn markdown(nodes: &[ast::Node]) -> String {
+  let mut text = String::new();
+  for node in nodes {
+    match node {
+      ast::Node::Text(node) => text.push_str(&node.text),
+      ast::Node::InlineCode(code) => text.push_str(&format!("`{}`", code.code)),
+      ast::Node::Bold(bold) => text.push_str(&format!("**{}**", markdown(&bold.children))),
+      ast::Node::Italic(italic) => text.push_str(&format!("*{}*", markdown(&italic.children))),
+      ast::Node::Link(link) => text.push_str(&link.alt),
+      ast::Node::Html(html) => text.push_str(html),
+      ast::Node::SoftBreak | ast::Node::HardBreak => text.push(' '),
+      _ => text.push_str(&markdown(node.children())),
+    }
+  }
+  text
+}
+
+fn is_placeholder(node: &ast::Node) -> bool {
+  match node {
+    ast::Node::Text(text) => PLACEHOLDERS.contains(&text.text.trim()),
+    ast::Node::Paragraph(paragraph) => paragraph.children.len() == 1 && is_placeholder(&paragraph.children[0]),
+    ast::Node::Html(html) => PLACEHOLDERS.contains(&html.trim()),
+    ast::Node::ReactComponent(component) => component.name == COMPONENT_NAME && component.children.is_empty(),
+    _ => false,
+  }
+}
+
+// turns a flat list of headings into a tree, deeper headings become children of the previous shallower one
+fn nest(entries: Vec<ast::TocEntry>) -> Vec<ast::TocEntry> {
+  let mut tree: Vec<ast::TocEntry> = Vec::new();
+  for entry in entries {
+    insert(&mut tree, entry);
+  }
+  tree
+}
+
+fn insert(siblings: &mut Vec<ast::TocEntry>, entry: ast::TocEntry) {
+  match siblings.last_mut() {
+    Some(last) if entry.level > last.level => insert(&mut last.children, entry),
+    _ => siblings.push(entry),
+  }
+}
+
+// - [API](#api)
+//   - [Options](#options)
+pub fn toc_list(entries: &[ast::TocEntry]) -> ast::List {
+  let mut children = Vec::new();
+  for entry in entries {
+    let url = format!("#{}", entry.id);
+    let alt = match entry.markdown.is_empty() {
+      true => entry.text.clone(),
+      false => entry.markdown.clone(),
+    };
+    let mut item = vec![ast::Node::Link(ast::Link { url, alt, title: None })];
+    if !entry.children.is_empty() {
+      item.push(ast::Node::List(toc_list(&entry.children)));
+    }
+    children.push(ast::Node::ListItem(ast::ListItem {
+      children: Box::new(item),
+      position: None,
+    }));
+  }
+  ast::List { ordered: false, start: None, loose: false, children: Box::new(children), position: None }
+}
+
+// [{ "level": 2, "text": "API", "id": "api", "children": [] }]
+pub fn toc_to_json(entries: &[ast::TocEntry]) -> serde_json::Value {
+  let entries = entries.iter().map(|entry| {
+    serde_json::json!({
+      "level": entry.level,
+      "text": entry.text,
+      "id": entry.id,
+      "children": toc_to_json(&entry.children),
+    })
+  });
+  serde_json::Value::Array(entries.collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{toc_to_json, TransformerTableOfContents};
+  use crate::{
+    ast::{
+      self,
+      visit::{Visit, Visitor},
+    },
+    compiler::HtmlCompiler,
+    parser::MdxParser,
+    transformer::Transformer,
+  };
+
+  const SOURCE: &str = "# Title\n\n[[toc]]\n\n## Install\n\n### From source\n\n#### Deep\n\n## Changelog <!-- no-toc -->\n\n> ## Quoted\n\n<Note>\n<TableOfContents />\n</Note>\n";
+
+  // `level text #id` per entry, children indented
+  fn outline(entries: &[ast::TocEntry], depth: usize, lines: &mut Vec<String>) {
+    for entry in entries {
+      lines.push(format!(
+        "{}{} {} #{}",
+        "  ".repeat(depth),
+        entry.level,
+        entry.text,
+        entry.id
+      ));
+      outline(&entry.children, depth + 1, lines);
+    }
+  }
+
+  fn toc(transformer: TransformerTableOfContents, ast: &mut ast::Ast) -> Vec<String> {
+    let ast::Ast::Root(root) = transformer.transform(ast);
+    let mut lines = Vec::new();
+    outline(&root.toc, 0, &mut lines);
+    lines
+  }
+
+  #[test]
+  fn collects_nested_headings() {
+    let lines = toc(
+      TransformerTableOfContents::new(),
+      &mut MdxParser::parse(SOURCE).unwrap(),
+    );
+    let expected = [
+      "1 Title #title",
+      "  2 Install #install",
+      "    3 From source #from-source",
+      "      4 Deep #deep",
+      "  2 Quoted #quoted",
+    ];
+    assert_eq!(lines, expected);
+  }
+
+  #[test]
+  fn collects_headings_in_vue_components() {
+    let heading = ast::Heading { level: 2, text: "Inside".to_string(), id: None, anchor: None, position: None };
+    let component = ast::VueComponent {
+      name: "Sidebar".to_string(),
+      props: Vec::new(),
+      children: Box::new(vec![ast::Node::Heading(heading)]),
+      position: None,
+    };
+    let mut root = ast::Root::default();
+    root.children.push(ast::Node::VueComponent(component));
+    let lines = toc(TransformerTableOfContents::new(), &mut ast::Ast::Root(root));
+    assert_eq!(lines, ["2 Inside #inside"]);
+  }
+
+  #[test]
+  fn limits_the_depth() {
+    let transformer = TransformerTableOfContents::new().with_depth(2, 3);
+    let lines = toc(transformer, &mut MdxParser::parse(SOURCE).unwrap());
+    assert_eq!(
+      lines,
+      ["2 Install #install", "  3 From source #from-source", "2 Quoted #quoted"]
+    );
+  }
+
+  #[test]
+  fn excludes_marked_headings() {
+    let mut ast = MdxParser::parse(SOURCE).unwrap();
+    let ast::Ast::Root(root) = TransformerTableOfContents::new()
+      .with_exclude_marker("")
+      .transform(&mut ast);
+    assert!(root.toc[0].children.iter().any(|entry| entry.id == "changelog"));
+
+    let mut ast = MdxParser::parse(SOURCE).unwrap();
+    let ast::Ast::Root(root) = TransformerTableOfContents::new().transform(&mut ast);
+    let changelog = root.children.iter().find_map(|node| match node {
+      ast::Node::Heading(heading) if heading.level == 2 && heading.text.starts_with("Changelog") => Some(heading),
+      _ => None,
+    });
+    // the marker is removed but the heading keeps its id
+    let changelog = changelog.unwrap();
+    assert_eq!(
+      (changelog.text.as_str(), changelog.id.as_deref()),
+      ("Changelog", Some("changelog"))
+    );
+  }
+
+  #[test]
+  fn replaces_placeholders_anywhere() {
+    let mut ast = MdxParser::parse(SOURCE).unwrap();
+    let ast::Ast::Root(root) = TransformerTableOfContents::new().with_depth(1, 1).transform(&mut ast);
+    let mut lists = 0;
+    let mut placeholders = 0;
+    (|node: &ast::Node| {
+      match node {
+        ast::Node::List(list) if list.children.len() == 1 => lists += 1,
+        ast::Node::ReactComponent(component) if component.name == "TableOfContents" => placeholders += 1,
+        ast::Node::Text(text) if text.text.contains("[[toc]]") => placeholders += 1,
+        _ => {}
+      }
+      Visit::Continue
+    })
+    .visit_ast(&ast::Ast::Root(root));
+    assert_eq!((lists, placeholders), (2, 0));
+  }
+
+  #[test]
+  fn keeps_the_inline_markdown_out_of_the_text() {
+    let mut ast = MdxParser::parse("[[toc]]\n\n# Deploy **now**\n\n## See [the docs](https://example.com)\n").unwrap();
+    let mut ast = TransformerTableOfContents::new().transform(&mut ast);
+    let ast::Ast::Root(root) = &ast;
+    assert_eq!(
+      toc_to_json(&root.toc),
+      serde_json::json!([{
+        "level": 1, "text": "Deploy now", "id": "deploy-now",
+        "children": [{ "level": 2, "text": "See the docs", "id": "see-the-docs", "children": [] }],
+      }])
+    );
+    let html = HtmlCompiler::new().compile(&mut ast);
+    assert!(html.starts_with("<ul>\n  <li>\n    <a href=\"#deploy-now\">Deploy <b>now</b></a>\n    <ul>\n      <li><a href=\"#see-the-docs\">See the docs</a></li>"), "{}", html);
+  }
+
+  #[test]
+  fn drops_placeholders_without_headings() {
+    let mut ast = MdxParser::parse("[[toc]]\n\nText\n\n<TableOfContents />\n").unwrap();
+    let mut ast = TransformerTableOfContents::new().transform(&mut ast);
+    assert_eq!(HtmlCompiler::new().compile(&mut ast), "<p>Text</p>");
+  }
+}
@@ -0,0 +1,417 @@
+// ==================
+// scc build --watch, scc run --watch
+//
+// recompiles only the documents that changed, plus the documents that depend on them
+// through an mdx import or a layout template.
+//
+use std::{
+  collections::{HashMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+  sync::mpsc::{channel, Receiver},
+  time::Duration,
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::{
+  build::{self, BuildOptions, Built, SearchSections},
+  diagnostics::{Diagnostic, DiagnosticFormat, Diagnostics},
+  parser::MdxParser,
+  template::LayoutOptions,
+};
+
+// editors write a file in several steps, changes closer than this are compiled once
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+pub struct Watcher {
+  watcher: RecommendedWatcher,
+  receiver: Receiver<notify::Result<Event>>,
+  watched: HashSet<PathBuf>,
+}
+
+impl Watcher {
+  pub fn new() -> Result<Self, String> {
+    let (sender, receiver) = channel();
+    let watcher = notify::recommended_watcher(sender).map_err(|err| err.to_string())?;
+    Ok(Self { watcher, receiver, watched: HashSet::new() })
+  }
+
+  pub fn watch(&mut self, path: &Path, recursive: bool) -> Result<(), String> {
+    if self.watched.contains(path) || !path.exists() {
+      return Ok(());
+    }
+    let mode = if recursive {
+      RecursiveMode::Recursive
+    } else {
+      RecursiveMode::NonRecursive
+    };
+    self
+      .watcher
+      .watch(path, mode)
+      .map_err(|err| format!("could not watch {}: {}", path.display(), err))?;
+    self.watched.insert(path.to_path_buf());
+    Ok(())
+  }
+
+  // blocks until something changes, returns None when the watcher is gone
+  pub fn next_changes(&self) -> Option<Vec<PathBuf>> {
+    let mut changes = HashSet::new();
+    collect_event(self.receiver.recv().ok()?, &mut changes);
+    while let Ok(event) = self.receiver.recv_timeout(DEBOUNCE) {
+      collect_event(event, &mut changes);
+    }
+    let mut changes: Vec<PathBuf> = changes.into_iter().collect();
+    changes.sort();
+    Some(changes)
+  }
+}
+
+fn collect_event(event: notify::Result<Event>, changes: &mut HashSet<PathBuf>) {
+  if let Ok(event) = event {
+    if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+      changes.extend(event.paths);
+    }
+  }
+}
+
+// document -> the files it is compiled with
+#[derive(Default)]
+pub struct DependencyGraph {
+  dependencies: HashMap<PathBuf, HashSet<PathBuf>>,
+}
+
+impl DependencyGraph {
+  pub fn new() -> Self {
+    Self { dependencies: HashMap::new() }
+  }
+
+  pub fn update(&mut self, document: &Path, dependencies: Vec<PathBuf>) {
+    self
+      .dependencies
+      .insert(document.to_path_buf(), dependencies.into_iter().collect());
+  }
+
+  pub fn remove(&mut self, document: &Path) {
+    self.dependencies.remove(document);
+  }
+
+  pub fn dependencies(&self) -> impl Iterator<Item = &PathBuf> {
+    self.dependencies.values().flatten()
+  }
+
+  // every document that depends on `path`, directly or through another document e.g. a partial of a partial.
+  // `path` itself is left out when the imports go in a cycle
+  pub fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+    let mut dependents = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(current) = pending.pop() {
+      for (document, dependencies) in &self.dependencies {
+        if dependencies.contains(&current) && document != path && !dependents.contains(document) {
+          dependents.push(document.clone());
+          pending.push(document.clone());
+        }
+      }
+    }
+    dependents.sort();
+    dependents
+  }
+}
+
+// the layout template and the imported .md/.mdx partials of a document
+pub fn document_dependencies(path: &Path, source: &str, layout: &LayoutOptions) -> Vec<PathBuf> {
+  let mut dependencies = Vec::new();
+  let frontmatter = MdxParser::parse_frontmatter_only(source);
+  match frontmatter.get("layout").map(String::as_str) {
+    Some("none") | Some("false") | Some("default") => {}
+    Some(name) => dependencies.push(layout.layouts_dir.join(format!("{}.html", name))),
+    None => dependencies.extend(layout.template.clone()),
+  }
+  let dir = path.parent().unwrap_or(Path::new("."));
+  for line in source.lines().map(str::trim).filter(|line| line.starts_with("import ")) {
+    if let Some(specifier) = import_specifier(line) {
+      if build::is_source(Path::new(specifier)) && !specifier.starts_with('/') {
+        dependencies.push(dir.join(specifier));
+      }
+    }
+  }
+  dependencies
+    .into_iter()
+    .map(|path| fs::canonicalize(&path).unwrap_or(path))
+    .collect()
+}
+
+// import Card from "./card.mdx" -> ./card.mdx
+fn import_specifier(line: &str) -> Option<&str> {
+  let rest = line
+    .rsplit_once(" from ")
+    .map_or(line.strip_prefix("import ")?, |(_, rest)| rest)
+    .trim();
+  let quote = rest.chars().next().filter(|quote| *quote == '"' || *quote == '\'')?;
+  let rest = &rest[1..];
+  Some(&rest[..rest.find(quote)?])
+}
+
+// a document or asset compiled again after a change, `errors` is set when it failed
+pub struct Rebuilt {
+  pub path: PathBuf,
+  pub errors: Option<Diagnostics>,
+}
+
+// ==================
+// scc build --watch, expects the initial build to be done already.
+// `on_rebuild` runs after every batch of changes e.g. `scc serve` reloads the browser there.
+// errors are printed in `format` as they happen.
+//
+pub fn watch_build(
+  options: &BuildOptions,
+  format: DiagnosticFormat,
+  mut on_rebuild: impl FnMut(&BuildOptions, &[Rebuilt]),
+) -> Result<(), String> {
+  let src = fs::canonicalize(&options.src).map_err(|err| format!("{}: {}", options.src.display(), err))?;
+  let out = fs::canonicalize(&options.out).unwrap_or(options.out.clone());
+  let options = BuildOptions {
+    src: src.clone(),
+    out: options.out.clone(),
+    target: options.target,
+    html_mode: options.html_mode,
+    layout: LayoutOptions {
+      template: options
+        .layout
+        .template
+        .as_ref()
+        .map(|path| fs::canonicalize(path).unwrap_or(path.clone())),
+      standalone: options.layout.standalone,
+      layouts_dir: fs::canonicalize(&options.layout.layouts_dir).unwrap_or(options.layout.layouts_dir.clone()),
+      stylesheets: options.layout.stylesheets.clone(),
+    },
+    config: options.config.clone(),
+    search: options.search.clone(),
+  };
+  let mut graph = DependencyGraph::new();
+  for document in source_files(&src) {
+    update_dependencies(&mut graph, &document, &options.layout);
+  }
+  let mut sections = SearchSections::collect(&options)?;
+  let mut watcher = Watcher::new()?;
+  watcher.watch(&src, true)?;
+  watch_dependencies(&mut watcher, &graph)?;
+  println!("watching {} for changes...", options.src.display());
+  while let Some(changes) = watcher.next_changes() {
+    let mut documents = Vec::new();
+    let changes = changes.into_iter().filter(|path| !path.starts_with(&out));
+    for path in changes.filter(|path| !is_hidden(path.strip_prefix(&src).unwrap_or(path))) {
+      for dependent in graph.dependents(&path) {
+        if !documents.contains(&dependent) {
+          documents.push(dependent);
+        }
+      }
+      if path.starts_with(&src) && !path.starts_with(&options.layout.layouts_dir) && !documents.contains(&path) {
+        documents.push(path);
+      }
+    }
+    let rebuilt: Vec<Rebuilt> = documents
+      .into_iter()
+      .filter(|path| !path.is_dir())
+      .map(|path| {
+        let errors = rebuild_path(&path, &options, &mut graph, &mut sections, format);
+        Rebuilt { path, errors }
+      })
+      .collect();
+    if options.search.is_some() && rebuilt.iter().any(|rebuilt| build::is_source(&rebuilt.path)) {
+      if let Err(err) = sections.write(&options) {
+        Diagnostics::from(Diagnostic::error(&err)).print(format);
+      }
+    }
+    if !rebuilt.is_empty() {
+      on_rebuild(&options, &rebuilt);
+    }
+    watch_dependencies(&mut watcher, &graph)?;
+  }
+  Ok(())
+}
+
+fn rebuild_path(
+  path: &Path,
+  options: &BuildOptions,
+  graph: &mut DependencyGraph,
+  sections: &mut SearchSections,
+  format: DiagnosticFormat,
+) -> Option<Diagnostics> {
+  let relative = path.strip_prefix(&options.src).unwrap_or(path).display().to_string();
+  if !path.exists() {
+    graph.remove(path);
+    sections.remove(path);
+    for output in build::outputs(path, options) {
+      let _ = fs::remove_file(output);
+    }
+    println!("removed {}", relative);
+    return None;
+  }
+  if build::is_source(path) {
+    update_dependencies(graph, path, &options.layout);
+  }
+  match build::build_path(path, options) {
+    Ok(Built::Compiled(document)) => {
+      sections.update(path, document);
+      println!("compiled {}", relative);
+    }
+    Ok(Built::Copied) => println!("copied {}", relative),
+    Err(errors) => {
+      sections.remove(path);
+      errors.clone().in_file(&relative).print(format);
+      return Some(errors);
+    }
+  }
+  None
+}
+
+fn update_dependencies(graph: &mut DependencyGraph, document: &Path, layout: &LayoutOptions) {
+  let source = fs::read_to_string(document).unwrap_or_default();
+  graph.update(document, document_dependencies(document, &source, layout));
+}
+
+// dependencies outside of the watched directory e.g. a --template somewhere else.
+// the directory is watched instead of the file, editors often save by replacing the file.
+fn watch_dependencies(watcher: &mut Watcher, graph: &DependencyGraph) -> Result<(), String> {
+  let dirs: HashSet<PathBuf> = graph
+    .dependencies()
+    .filter_map(|path| path.parent())
+    .map(Path::to_path_buf)
+    .collect();
+  for dir in dirs {
+    watcher.watch(&dir, false)?;
+  }
+  Ok(())
+}
+
+fn source_files(dir: &Path) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+  let entries = match fs::read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => return files,
+  };
+  for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+    if path
+      .file_name()
+      .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    {
+      continue;
+    }
+    if path.is_dir() {
+      files.extend(source_files(&path));
+    } else if build::is_source(&path) {
+      files.push(path);
+    }
+  }
+  files
+}
+
+fn is_hidden(path: &Path) -> bool {
+  path
+    .components()
+    .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+// ==================
+// scc run --watch, `compile` runs once now and again every time the file or its dependencies change
+//
+pub fn watch_file(file: &Path, layout: &LayoutOptions, mut compile: impl FnMut()) -> Result<(), String> {
+  let file = fs::canonicalize(file).map_err(|err| format!("{}: {}", file.display(), err))?;
+  let mut watcher = Watcher::new()?;
+  let mut graph = DependencyGraph::new();
+  compile();
+  loop {
+    update_dependencies(&mut graph, &file, layout);
+    watcher.watch(file.parent().unwrap_or(Path::new("/")), false)?;
+    watch_dependencies(&mut watcher, &graph)?;
+    let changes = match watcher.next_changes() {
+      Some(changes) => changes,
+      None => return Ok(()),
+    };
+    if changes
+      .iter()
+      .any(|path| *path == file || graph.dependents(path).contains(&file))
+    {
+      compile();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::PathBuf};
+
+  use super::{document_dependencies, import_specifier, DependencyGraph};
+  use crate::template::LayoutOptions;
+
+  #[test]
+  fn finds_dependents_transitively() {
+    let path = |name: &str| PathBuf::from(format!("/docs/{}", name));
+    let mut graph = DependencyGraph::new();
+    graph.update(&path("index.mdx"), vec![path("card.mdx"), path("page.html")]);
+    graph.update(&path("card.mdx"), vec![path("icon.mdx")]);
+    graph.update(&path("about.mdx"), vec![path("page.html")]);
+    assert_eq!(
+      graph.dependents(&path("icon.mdx")),
+      [path("card.mdx"), path("index.mdx")]
+    );
+    assert_eq!(
+      graph.dependents(&path("page.html")),
+      [path("about.mdx"), path("index.mdx")]
+    );
+    assert!(graph.dependents(&path("index.mdx")).is_empty());
+
+    // a cycle ends, without the changed document in it
+    graph.update(&path("icon.mdx"), vec![path("card.mdx")]);
+    assert_eq!(
+      graph.dependents(&path("card.mdx")),
+      [path("icon.mdx"), path("index.mdx")]
+    );
+    graph.remove(&path("index.mdx"));
+    assert_eq!(graph.dependents(&path("card.mdx")), [path("icon.mdx")]);
+  }
+
+  #[test]
+  fn reads_import_specifiers() {
+    assert_eq!(import_specifier("import Card from \"./card.mdx\""), Some("./card.mdx"));
+    assert_eq!(import_specifier("import Card from './card.mdx';"), Some("./card.mdx"));
+    assert_eq!(
+      import_specifier("import { a, b } from \"../lib.mdx\""),
+      Some("../lib.mdx")
+    );
+    assert_eq!(import_specifier("import './styles.css'"), Some("./styles.css"));
+    assert_eq!(import_specifier("import Card from card"), None);
+    assert_eq!(import_specifier("import Card from \"./card.mdx"), None);
+  }
+
+  #[test]
+  fn collects_imports_and_layouts() {
+    let dir = std::env::temp_dir().join(format!("scc-watch-{}", std::process::id()));
+    fs::create_dir_all(dir.join("layouts")).unwrap();
+    fs::write(dir.join("card.mdx"), "").unwrap();
+    fs::write(dir.join("layouts/post.html"), "").unwrap();
+    fs::write(dir.join("page.html"), "").unwrap();
+    let dir = fs::canonicalize(&dir).unwrap();
+    let layout = LayoutOptions {
+      template: Some(dir.join("page.html")),
+      layouts_dir: dir.join("layouts"),
+      ..LayoutOptions::default()
+    };
+    let document = dir.join("index.mdx");
+    let imports =
+      "import Card from './card.mdx'\nimport Chart from \"./chart.js\"\nimport X from \"/abs.mdx\"\n\n# Hi\n";
+    let dependencies = document_dependencies(&document, imports, &layout);
+    assert_eq!(dependencies, [dir.join("page.html"), dir.join("card.mdx")]);
+
+    let source = format!("---\nlayout: post\n---\n{}", imports);
+    let dependencies = document_dependencies(&document, &source, &layout);
+    assert_eq!(dependencies, [dir.join("layouts/post.html"), dir.join("card.mdx")]);
+
+    for name in ["none", "default"] {
+      let source = format!("---\nlayout: {}\n---\n# Hi\n", name);
+      assert!(document_dependencies(&document, &source, &layout).is_empty());
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
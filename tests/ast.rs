@@ -0,0 +1,193 @@
+// the ast as json, in the scc shape and in the unified/remark (mdast) shape
+use scc::{
+  ast::{
+    self, mdast,
+    visit::{Action, Visit, Visitor, VisitorMut},
+  },
+  MdxParser,
+};
+use serde_json::json;
+
+const SOURCE: &str = "---\ntitle: Hi\n---\n# Hi\n\n- one *a*\n- two\n\n<Note kind=\"x\" open={true}>\ninner **b**\n</Note>\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n```ts {1}\nlet a\n```\n\n<div>h</div>\n";
+
+#[test]
+fn round_trips_through_json() {
+  let ast = MdxParser::parse(SOURCE).unwrap();
+  let json = serde_json::to_value(&ast).unwrap();
+  let parsed: ast::Ast = serde_json::from_value(json.clone()).unwrap();
+  assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+  assert_eq!(json["children"][5], json!({ "type": "html", "html": "<div>h</div>" }));
+}
+
+#[test]
+fn round_trips_through_mdast() {
+  let ast = MdxParser::parse(SOURCE).unwrap();
+  let tree = mdast::to_mdast(&ast);
+  assert_eq!(tree["children"][0], json!({ "type": "yaml", "value": "title: Hi" }));
+  assert_eq!(tree["children"][3]["type"], "mdxJsxFlowElement");
+  let parsed = mdast::from_mdast(&tree).unwrap();
+  assert_eq!(
+    serde_json::to_value(&parsed).unwrap(),
+    serde_json::to_value(&ast).unwrap()
+  );
+}
+
+#[test]
+fn keeps_the_inline_markdown_of_headings() {
+  let ast = MdxParser::parse("# **Bold** *and* `code` title\n").unwrap();
+  let tree = mdast::to_mdast(&ast);
+  assert_eq!(
+    tree["children"][0]["children"],
+    json!([
+      { "type": "strong", "children": [{ "type": "text", "value": "Bold" }] },
+      { "type": "text", "value": " " },
+      { "type": "emphasis", "children": [{ "type": "text", "value": "and" }] },
+      { "type": "text", "value": " " },
+      { "type": "inlineCode", "value": "code" },
+      { "type": "text", "value": " title" },
+    ])
+  );
+  assert_eq!(
+    tree["children"][0]["position"],
+    json!({ "start": { "line": 1, "column": 1, "offset": 0 }, "end": { "line": 1, "column": 30, "offset": 29 } })
+  );
+  assert!(tree["children"][0]["children"][0].get("position").is_none());
+  let parsed = mdast::from_mdast(&tree).unwrap();
+  let ast::Ast::Root(root) = &parsed;
+  let ast::Node::Heading(heading) = &root.children[0] else {
+    panic!("expected a heading");
+  };
+  assert_eq!(heading.text, "**Bold** *and* `code` title");
+  let output = scc::compile_ast(parsed, scc::Options::default()).unwrap();
+  assert_eq!(
+    output.code,
+    "<h1 id=\"bold-and-code-title\"><b>Bold</b> <i>and</i> <code>code</code> title</h1>\n"
+  );
+}
+
+#[test]
+fn reads_remark_trees() {
+  let tree = json!({
+    "type": "root",
+    "children": [
+      { "type": "heading", "depth": 2, "children": [{ "type": "text", "value": "A " }, { "type": "inlineCode", "value": "b" }] },
+      { "type": "paragraph", "children": [{ "type": "link", "url": "/x", "title": null, "children": [{ "type": "text", "value": "x" }] }] },
+    ],
+  });
+  let output = scc::compile_ast(mdast::from_mdast(&tree).unwrap(), scc::Options::default()).unwrap();
+  assert_eq!(
+    output.code,
+    "<h2 id=\"a-b\">A <code>b</code></h2>\n<p><a href=\"/x\">x</a></p>\n"
+  );
+  let footnote = json!({ "type": "root", "children": [{ "type": "footnoteReference", "identifier": "1" }] });
+  assert_eq!(
+    mdast::from_mdast(&footnote).err().unwrap().message,
+    "unsupported mdast node `footnoteReference`"
+  );
+}
+
+#[test]
+fn keeps_the_position_of_blocks() {
+  let ast = MdxParser::parse(
+    "# Hi
+
+- one
+- two
+
+<Note>
+  text
+</Note>
+
+> quote
+",
+  )
+  .unwrap();
+  let tree = mdast::to_mdast(&ast);
+  let start = |pointer: &str| {
+    let start = &tree.pointer(pointer).unwrap()["position"]["start"];
+    (
+      start["line"].as_u64(),
+      start["column"].as_u64(),
+      start["offset"].as_u64(),
+    )
+  };
+  assert_eq!(start("/children/1"), (Some(3), Some(1), Some(6)));
+  assert_eq!(start("/children/1/children/1"), (Some(4), Some(1), Some(12)));
+  assert_eq!(tree["children"][1]["position"]["end"]["offset"], 17);
+  // the children of a component are read where they are written, a blockquote is read without its `>`
+  assert_eq!(start("/children/2/children/0"), (Some(7), Some(3), Some(28)));
+  assert!(tree["children"][3]["children"][0].get("position").is_none());
+}
+
+#[test]
+fn places_errors_at_the_node() {
+  let point = |line: usize| json!({ "line": line, "column": 1, "offset": 0 });
+  let tree = json!({
+    "type": "root",
+    "children": [{
+      "type": "paragraph",
+      "position": { "start": point(3), "end": point(3) },
+      "children": [{ "type": "delete", "children": [{ "type": "text", "value": "gone" }] }],
+    }],
+  });
+  let error = mdast::from_mdast(&tree).err().unwrap();
+  assert_eq!(error.message, "unsupported mdast node `delete`");
+  assert_eq!((error.line, error.column), (Some(3), Some(1)));
+}
+
+// node labels in the order they are entered, `/label` when they are exited
+struct Trace(Vec<String>);
+
+impl Visitor for Trace {
+  fn enter(&mut self, node: &ast::Node) -> Visit {
+    self.0.push(node.to_string());
+    match node {
+      ast::Node::Italic(_) => Visit::Skip,
+      _ => Visit::Continue,
+    }
+  }
+
+  fn exit(&mut self, node: &ast::Node) {
+    self.0.push(format!("/{}", node));
+  }
+}
+
+#[test]
+fn visits_every_node() {
+  let ast = MdxParser::parse("> a **b** *c*\n").unwrap();
+  let mut trace = Trace(Vec::new());
+  trace.visit_ast(&ast);
+  let expected = [
+    "blockquote",
+    "paragraph",
+    "text \"a \"",
+    "/text \"a \"",
+    "bold",
+    "text \"b\"",
+    "/text \"b\"",
+    "/bold",
+    "text \" \"",
+    "/text \" \"",
+    "italic",
+    "/italic",
+    "text \"\\n\"",
+    "/text \"\\n\"",
+    "/paragraph",
+    "/blockquote",
+  ];
+  assert_eq!(trace.0, expected);
+}
+
+#[test]
+fn removes_and_replaces_nodes() {
+  let mut ast = MdxParser::parse("a **b** `c` d\n").unwrap();
+  (|node: &mut ast::Node| match node {
+    ast::Node::Bold(bold) => Action::Replace(bold.children.to_vec()),
+    ast::Node::InlineCode(_) => Action::Remove,
+    _ => Action::Continue,
+  })
+  .visit_ast(&mut ast);
+  let ast::Ast::Root(root) = &ast;
+  let texts: Vec<String> = root.children[0].children().iter().map(ToString::to_string).collect();
+  assert_eq!(texts, ["text \"a \"", "text \"b\"", "text \" \"", "text \" d\\n\""]);
+}
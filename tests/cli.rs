@@ -0,0 +1,273 @@
+// the scc binary, as a shell or a build script runs it
+use std::{
+  fs,
+  io::Write,
+  path::PathBuf,
+  process::{Command, Output, Stdio},
+};
+
+// a directory of its own per test, so no scc.toml up the tree is picked up
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("scc-cli-{}-{}", name, std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn scc(dir: &PathBuf, args: &[&str], stdin: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_scc"))
+    .args(args)
+    .current_dir(dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  // scc may exit before reading stdin, e.g. on usage errors
+  child.stdin.take().unwrap().write_all(stdin.as_bytes()).ok();
+  child.wait_with_output().unwrap()
+}
+
+fn text(bytes: &[u8]) -> String {
+  String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[test]
+fn compiles_stdin_to_stdout() {
+  let dir = temp_dir("stdin");
+  let output = scc(&dir, &["compile", "-"], "# Hello\n\nSome **text**.\n");
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  assert_eq!(
+    text(&output.stdout),
+    "<h1 id=\"hello\">Hello</h1>\n<p>Some <b>text</b>.</p>\n"
+  );
+  let output = scc(
+    &dir,
+    &["compile", "-", "--target", "text"],
+    "# Hello\n\nSome **text**.\n",
+  );
+  assert_eq!(text(&output.stdout), "Hello\n\nSome text.\n");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn writes_the_output_file() {
+  let dir = temp_dir("output");
+  fs::write(dir.join("doc.md"), "# Doc\n").unwrap();
+  let output = scc(&dir, &["compile", "doc.md", "--output", "out/doc.html"], "");
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  assert!(output.stdout.is_empty());
+  assert_eq!(
+    fs::read_to_string(dir.join("out/doc.html")).unwrap(),
+    "<h1 id=\"doc\">Doc</h1>\n"
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn exits_with_1_on_diagnostics() {
+  let dir = temp_dir("diagnostics");
+  let output = scc(&dir, &["compile", "-"], "# Title\n\nSome **a *b**\n\n<Note>\n");
+  assert_eq!(output.status.code(), Some(1));
+  assert!(output.stdout.is_empty());
+  assert_eq!(
+    text(&output.stderr),
+    "error: <stdin>:5:1: Expected '</Note>' to close '<Note>'\n"
+  );
+
+  let output = scc(&dir, &["compile", "-", "--format", "json"], "Some text\n\n  <Note>\n");
+  assert_eq!(output.status.code(), Some(1));
+  let diagnostic: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+  assert_eq!(diagnostic["file"], "<stdin>");
+  assert_eq!(
+    (diagnostic["line"].as_u64(), diagnostic["column"].as_u64()),
+    (Some(3), Some(3))
+  );
+
+  let output = scc(&dir, &["compile", "missing.md"], "");
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    text(&output.stderr).starts_with("error: missing.md: "),
+    "{}",
+    text(&output.stderr)
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn reports_malformed_mdast_at_its_position() {
+  let dir = temp_dir("mdast");
+  let output = scc(
+    &dir,
+    &["compile", "-", "--from", "mdast"],
+    "{\n  \"type\": \"root\",\n  \"children\": [}\n",
+  );
+  assert_eq!(output.status.code(), Some(1));
+  assert_eq!(text(&output.stderr), "error: <stdin>:3:16: expected value\n");
+
+  let tree = serde_json::json!({
+    "type": "root",
+    "children": [{
+      "type": "delete",
+      "position": { "start": { "line": 2, "column": 5, "offset": 6 }, "end": { "line": 2, "column": 12, "offset": 13 } },
+      "children": [],
+    }],
+  });
+  let output = scc(&dir, &["compile", "-", "--from", "mdast"], &tree.to_string());
+  assert_eq!(output.status.code(), Some(1));
+  assert_eq!(
+    text(&output.stderr),
+    "error: <stdin>:2:5: unsupported mdast node `delete`\n"
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn exits_with_2_on_usage_errors() {
+  let dir = temp_dir("usage");
+  for args in [
+    &["compile"][..],
+    &["compile", "-", "--target", "pdf"],
+    &["nope"],
+    &["compile", "-", "--watch"],
+  ] {
+    let output = scc(&dir, args, "# Hi\n");
+    assert_eq!(output.status.code(), Some(2), "{:?}: {}", args, text(&output.stderr));
+  }
+  fs::remove_dir_all(dir).unwrap();
+}
+
+// ==================
+// inspect snapshots
+
+const INSPECT_INPUT: &str =
+  "---\ntitle: Hi\n---\n# Hello\n\nSome **bold** [link](/a).\n\n```js\nlet a = 1; // [!code ++]\n```\n";
+
+#[test]
+fn prints_the_ast_tree() {
+  let dir = temp_dir("inspect-tree");
+  let output = scc(&dir, &["inspect", "-"], INSPECT_INPUT);
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  assert_eq!(
+    text(&output.stdout),
+    "root
+│  title: \"Hi\"
+├─ heading h1 \"Hello\"
+├─ paragraph
+│  ├─ text \"Some \"
+│  ├─ bold
+│  │  └─ text \"bold\"
+│  ├─ text \" \"
+│  ├─ link \"link\" -> \"/a\"
+│  └─ text \".\"
+└─ code_block \"js\" lines=1
+"
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prints_the_ast_after_each_transformer() {
+  let dir = temp_dir("inspect-stages");
+  let output = scc(
+    &dir,
+    &["inspect", "-", "--stages"],
+    "# Hello\n\n```js\nlet a = 1; // [!code ++]\n```\n",
+  );
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  let stdout = text(&output.stdout);
+  let stages: Vec<&str> = stdout.split("\n\n").collect();
+  assert_eq!(
+    stages,
+    [
+      "== parse\nroot\n├─ heading h1 \"Hello\"\n└─ code_block \"js\" lines=1",
+      "== TransformerHeadingSlug\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" lines=1",
+      "== TransformerTableOfContents\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" lines=1",
+      "== TransformerNotationDiff\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" classes=[\"has-diff\"] lines=1",
+      "== TransformerNotationHighlight\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" classes=[\"has-diff\"] lines=1",
+      "== TransformerNotationFocus\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" classes=[\"has-diff\"] lines=1",
+      "== TransformerNotationErrorLevel\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" classes=[\"has-diff\"] lines=1",
+      "== TransformerNotationWordHighlight\nroot\n├─ heading h1 \"Hello\" #hello\n└─ code_block \"js\" classes=[\"has-diff\"] lines=1\n",
+    ]
+  );
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prints_the_ast_as_json() {
+  let dir = temp_dir("inspect-json");
+  let output = scc(
+    &dir,
+    &["inspect", "-", "--json"],
+    "---\ntitle: Hi\n---\n# Hello\n\nSome **bold**.\n",
+  );
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  let ast: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  assert_eq!(
+    ast,
+    serde_json::json!({
+      "type": "root",
+      "frontmatter": { "title": "Hi" },
+      "toc": [],
+      "children": [
+        {
+          "type": "heading",
+          "level": 1,
+          "text": "Hello",
+          "id": null,
+          "anchor": null,
+          "position": {
+            "start": { "line": 4, "column": 1, "offset": 18 },
+            "end": { "line": 4, "column": 8, "offset": 25 }
+          }
+        },
+        {
+          "type": "paragraph",
+          "position": {
+            "start": { "line": 6, "column": 1, "offset": 27 },
+            "end": { "line": 6, "column": 15, "offset": 41 }
+          },
+          "children": [
+            { "type": "text", "text": "Some " },
+            { "type": "bold", "children": [{ "type": "text", "text": "bold" }] },
+            { "type": "text", "text": ".\n" }
+          ]
+        }
+      ]
+    })
+  );
+
+  let output = scc(&dir, &["inspect", "-", "--json", "--stages"], "# Hello\n");
+  let stages: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  let names: Vec<&str> = stages
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|stage| stage["stage"].as_str().unwrap())
+    .collect();
+  assert_eq!(names.first(), Some(&"parse"));
+  assert_eq!(names.len(), 8);
+  assert_eq!(stages[0]["ast"]["children"][0]["id"], serde_json::Value::Null);
+  assert_eq!(stages[1]["ast"]["children"][0]["id"], "hello");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_flags_win_over_scc_toml() {
+  let dir = temp_dir("config");
+  fs::write(dir.join("scc.toml"), "target = \"text\"\nhtml-mode = \"minified\"\n").unwrap();
+  let output = scc(&dir, &["compile", "-"], "# Hello\n");
+  assert_eq!(output.status.code(), Some(0), "{}", text(&output.stderr));
+  assert_eq!(text(&output.stdout), "Hello\n");
+  let output = scc(
+    &dir,
+    &["compile", "-", "--target", "html", "--html-mode", "pretty"],
+    "# Hello\n\nText\n",
+  );
+  assert_eq!(text(&output.stdout), "<h1 id=\"hello\">Hello</h1>\n<p>Text</p>\n");
+
+  fs::write(dir.join("scc.toml"), "target = \"pdf\"\n").unwrap();
+  let output = scc(&dir, &["compile", "-", "--target", "html"], "# Hello\n");
+  assert_eq!(output.status.code(), Some(1));
+  assert!(text(&output.stderr).contains("unknown target `pdf`"));
+  fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,113 @@
+// the public api, as a crate depending on scc uses it
+use scc::{ast, Options, Target};
+
+#[test]
+fn compiles_html_by_default() {
+  let output = scc::compile("# Hello\n\nSome **text**.\n", Options::default()).unwrap();
+  assert_eq!(output.code, "<h1 id=\"hello\">Hello</h1>\n<p>Some <b>text</b>.</p>\n");
+  assert_eq!(output.toc.len(), 1);
+}
+
+#[test]
+fn compiles_other_targets() {
+  let output = scc::compile("# Hello\n", Options::new(Target::Vue)).unwrap();
+  assert_eq!(output.code, "<template>\n<h1 id=\"hello\">Hello</h1>\n</template>\n");
+  let output = scc::compile("# Hello\n\nSome **text**.\n", Options::new(Target::Text)).unwrap();
+  assert_eq!(output.code, "Hello\n\nSome text.\n");
+  let output = scc::compile("# Hello\n\nSome **text**.\n", Options::new(Target::Latex)).unwrap();
+  assert_eq!(output.code, "\\section{Hello}\\label{hello}\n\nSome \\textbf{text}.\n");
+}
+
+#[test]
+fn returns_the_frontmatter_and_ast() {
+  let output = scc::compile("---\ntitle: Hi\n---\n# Hello\n", Options::default()).unwrap();
+  assert_eq!(output.frontmatter.get("title").map(String::as_str), Some("Hi"));
+  let ast::Ast::Root(root) = output.ast;
+  assert!(matches!(root.children[0], ast::Node::Heading(_)));
+}
+
+#[test]
+fn reports_parse_errors_as_diagnostics() {
+  let diagnostics = scc::compile("# Title\n\n<Note>\n\nSome **a *b**\n", Options::default())
+    .err()
+    .unwrap();
+  let diagnostic = &diagnostics.items[0];
+  assert_eq!(diagnostic.message, "Expected '</Note>' to close '<Note>'");
+  assert_eq!((diagnostic.line, diagnostic.column), (Some(3), Some(1)));
+}
+
+#[test]
+fn strips_the_quotes_of_link_titles() {
+  let source = "[a](/x?q=\"1\" \"2\") and [b](/y 'c') [d](/z (e))\n";
+  let output = scc::compile(source, Options::new(Target::Tsx)).unwrap();
+  assert!(
+    output.code.contains("<a href=\"/x?q=&quot;1&quot;\" title=\"2\">a</a>"),
+    "{}",
+    output.code
+  );
+  assert!(
+    output.code.contains("<a href=\"/y\" title=\"c\">b</a>"),
+    "{}",
+    output.code
+  );
+  assert!(
+    output.code.contains("<a href=\"/z\" title=\"e\">d</a>"),
+    "{}",
+    output.code
+  );
+  let output = scc::compile(source, Options::new(Target::Markdown)).unwrap();
+  assert_eq!(output.code, "[a](/x?q=\"1\" \"2\") and [b](/y \"c\") [d](/z \"e\")\n");
+}
+
+#[test]
+fn highlights_code_with_a_theme() {
+  let source = "```js\nconst a = 1; // [!code word:a = 1]\n```\n\n```text\nconst b\n```\n";
+  let options = Options { highlighter: Some(scc::Highlighter::default()), ..Options::default() };
+  let output = scc::compile(source, options).unwrap();
+  assert_eq!(
+    output.code,
+    "<pre class=\"has-highlighted-words\"><code class=\"language-js\"><span class=\"line\">\
+     <span style=\"color:#c678dd\">const</span> \
+     <span class=\"highlighted-word\">a = <span style=\"color:#d19a66\">1</span></span>;</span></code></pre>\n\
+     <pre><code class=\"language-text\"><span class=\"line\">const b</span></code></pre>\n"
+  );
+  // off by default
+  let output = scc::compile("```js\nconst a\n```\n", Options::default()).unwrap();
+  assert_eq!(
+    output.code,
+    "<pre><code class=\"language-js\"><span class=\"line\">const a</span></code></pre>\n"
+  );
+}
+
+#[test]
+fn aligns_table_columns() {
+  let output = scc::compile("| a | b | c |\n|:--|:-:|--:|\n| 1 | 2 | 3 |\n", Options::default()).unwrap();
+  assert_eq!(
+    output.code,
+    "<table>\n  <thead>\n    <tr><th align=\"left\">a</th><th align=\"center\">b</th><th align=\"right\">c</th></tr>\n  \
+     </thead>\n  <tbody>\n    <tr><td align=\"left\">1</td><td align=\"center\">2</td><td align=\"right\">3</td></tr>\n  \
+     </tbody>\n</table>\n"
+  );
+}
+
+#[test]
+fn compiles_the_inline_markdown_of_headings_and_cells_to_tsx() {
+  let source = "## See [the **docs**](x.md)\n\n| *a* | b |\n|:--|--:|\n| `1` | {2} |\n";
+  let output = scc::compile(source, Options::new(Target::Tsx)).unwrap();
+  assert!(
+    output
+      .code
+      .contains("<h2 id=\"see-the-docs\">See <a href=\"x.md\">the <b>docs</b></a></h2>"),
+    "{}",
+    output.code
+  );
+  assert!(
+    output.code.contains(
+      "<table><thead><tr><th style={{ textAlign: \"left\" }}><i>a</i></th><th style={{ textAlign: \"right\" }}>b</th>\
+       </tr></thead><tbody><tr><td style={{ textAlign: \"left\" }}><code>{\"1\"}</code></td>\
+       <td style={{ textAlign: \"right\" }}>{\"{\"}2{\"}\"}</td></tr></tbody></table>"
+    ),
+    "{}",
+    output.code
+  );
+}